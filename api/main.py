@@ -226,3 +226,48 @@ async def increment_watch_by_path(path: str = Query(..., description="file_key o
     if not updated:
         raise HTTPException(status_code=404, detail="Movie not found for provided path")
     return {"status": "ok", "watch_count": updated.get("watch_count")}
+
+
+@app.post("/movies/set_watched/")
+async def set_watched_by_path(
+    path: str = Query(..., description="file_key or file path variant"),
+    watched: bool = Query(..., description="true to mark watched, false to reset the watch count"),
+):
+    """Mark a movie as watched or unwatched by a file path or file_key."""
+    updated = await db_access.set_watched_by_path(path, watched)
+    if not updated:
+        raise HTTPException(status_code=404, detail="Movie not found for provided path")
+    return {"status": "ok", "watch_count": updated.get("watch_count")}
+
+
+@app.post("/movies/set_rating/")
+async def set_rating_by_path(
+    path: str = Query(..., description="file_key or file path variant"),
+    rating: float = Query(..., ge=0, le=10, description="rating from 0 to 10"),
+):
+    """Store a user's rating for a movie by a file path or file_key."""
+    updated = await db_access.set_rating_by_path(path, rating)
+    if not updated:
+        raise HTTPException(status_code=404, detail="Movie not found for provided path")
+    return {"status": "ok", "rating": updated.get("rating")}
+
+
+@app.post("/movies/remove_file/")
+async def remove_file_by_path(path: str = Query(..., description="file_key or file path variant")):
+    """Forget a movie file deleted from disk, keeping the movie's metadata."""
+    updated = await db_access.remove_file_by_path(path)
+    if not updated:
+        raise HTTPException(status_code=404, detail="Movie not found for provided path")
+    return {"status": "ok"}
+
+
+@app.post("/movies/rename_file/")
+async def rename_file_by_path(
+    path: str = Query(..., description="file_key or file path variant"),
+    new_path: str = Query(..., description="the same variant of the file's new path"),
+):
+    """Follow a movie file renamed on disk, keeping the movie's metadata."""
+    updated = await db_access.rename_file_by_path(path, new_path)
+    if not updated:
+        raise HTTPException(status_code=404, detail="Movie not found for provided path")
+    return {"status": "ok"}
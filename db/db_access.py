@@ -66,3 +66,52 @@ async def increment_watch_by_path(path: str):
     )
     await conn.close()
     return dict(row) if row else None
+
+
+async def set_watched_by_path(path: str, watched: bool):
+    # Watched keeps an existing watch_count (at least 1); unwatched resets it to 0
+    conn = await asyncpg.connect(**DB_CONFIG)
+    jsonb = json.dumps([path])
+    row = await conn.fetchrow(
+        "UPDATE movies SET watch_count = CASE WHEN $3 THEN GREATEST(COALESCE(watch_count, 0), 1) ELSE 0 END WHERE file_key=$1 OR file_paths @> $2::jsonb RETURNING watch_count",
+        path, jsonb, watched
+    )
+    await conn.close()
+    return dict(row) if row else None
+
+
+async def set_rating_by_path(path: str, rating: float):
+    conn = await asyncpg.connect(**DB_CONFIG)
+    jsonb = json.dumps([path])
+    row = await conn.fetchrow(
+        "UPDATE movies SET rating = $3 WHERE file_key=$1 OR file_paths @> $2::jsonb RETURNING rating",
+        path, jsonb, rating
+    )
+    await conn.close()
+    return dict(row) if row else None
+
+
+async def remove_file_by_path(path: str):
+    # The movie keeps its metadata; only the deleted file leaves file_key and file_paths
+    conn = await asyncpg.connect(**DB_CONFIG)
+    jsonb = json.dumps([path])
+    row = await conn.fetchrow(
+        "UPDATE movies SET file_paths = COALESCE(file_paths, '[]'::jsonb) - $1, file_key = NULLIF(file_key, $1) WHERE file_key=$1 OR file_paths @> $2::jsonb RETURNING id",
+        path, jsonb
+    )
+    await conn.close()
+    return dict(row) if row else None
+
+
+async def rename_file_by_path(path: str, new_path: str):
+    # Whichever of file_key and file_paths held the old name gets the new one
+    conn = await asyncpg.connect(**DB_CONFIG)
+    jsonb = json.dumps([path])
+    row = await conn.fetchrow(
+        "UPDATE movies SET file_paths = CASE WHEN file_paths @> $2::jsonb THEN (file_paths - $1) || $4::jsonb ELSE file_paths END, "
+        "file_key = CASE WHEN file_key = $1 THEN $3 ELSE file_key END "
+        "WHERE file_key=$1 OR file_paths @> $2::jsonb RETURNING id",
+        path, jsonb, new_path, json.dumps([new_path])
+    )
+    await conn.close()
+    return dict(row) if row else None
@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+use reqwest::blocking::{Client as HttpClient, RequestBuilder, Response};
+use serde_json::Value as JsonValue;
+
+use crate::MovieInfo;
+
+// api_url from the config file, used when API_URL is not set
+static CONFIGURED_BASE: OnceLock<String> = OnceLock::new();
+
+pub fn set_configured_base(url: &str) {
+    let _ = CONFIGURED_BASE.set(url.to_string());
+}
+
+/// Extra attempts after a failed request, and the wait before the first of them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy { retries: 3, base_delay: Duration::from_millis(200) }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before retry number `retry` (0-based), doubling each time
+    fn delay(self, retry: u32) -> Duration {
+        self.base_delay.saturating_mul(1 << retry.min(16))
+    }
+}
+
+// Default connect and request timeout for API calls
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// api_timeout_secs from the config file
+static TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+pub fn set_timeout(timeout: Duration) {
+    let _ = TIMEOUT.set(timeout);
+}
+
+/// An HTTP client that gives up on a dead API after the configured timeout
+pub fn client() -> HttpClient {
+    let timeout = TIMEOUT.get().copied().unwrap_or(DEFAULT_TIMEOUT);
+    HttpClient::builder()
+        .connect_timeout(timeout)
+        .timeout(timeout)
+        .build()
+        .unwrap_or_else(|_| HttpClient::new())
+}
+
+// api_retries / api_retry_delay_ms from the config file
+static RETRY_POLICY: OnceLock<RetryPolicy> = OnceLock::new();
+
+pub fn set_retry_policy(policy: RetryPolicy) {
+    let _ = RETRY_POLICY.set(policy);
+}
+
+/// Send the request built by `build`, retrying with exponential backoff when the API
+/// is unreachable or answers with a server error. A request that is not `idempotent`
+/// is only retried when it could not connect, so it never takes effect twice
+pub fn send_with_retry(build: impl Fn() -> RequestBuilder, idempotent: bool) -> reqwest::Result<Response> {
+    let policy = RETRY_POLICY.get().copied().unwrap_or_default();
+    let mut retry = 0;
+    loop {
+        let result = build().send();
+        let failed = match &result {
+            Ok(resp) => idempotent && resp.status().is_server_error(),
+            Err(e) => idempotent || e.is_connect(),
+        };
+        if !failed || retry >= policy.retries {
+            return result;
+        }
+        thread::sleep(policy.delay(retry));
+        retry += 1;
+    }
+}
+
+// How much of an error page is worth logging
+const ERROR_BODY_LIMIT: usize = 200;
+
+/// An error body cut down to one line of at most ERROR_BODY_LIMIT characters
+fn truncate_body(body: &str) -> String {
+    let body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    match body.char_indices().nth(ERROR_BODY_LIMIT) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body,
+    }
+}
+
+/// Describe a failed response as its status and the start of its body, for logging
+pub fn error_summary(resp: Response) -> String {
+    let status = resp.status();
+    let body = truncate_body(&resp.text().unwrap_or_default());
+    if body.is_empty() { format!("HTTP {}", status) } else { format!("HTTP {}: {}", status, body) }
+}
+
+/// Whether an API location was given explicitly (API_URL or the config file)
+pub fn is_configured() -> bool {
+    env::var("API_URL").is_ok() || CONFIGURED_BASE.get().is_some()
+}
+
+/// Base URL of the movies API, from API_URL, then the config file
+pub fn api_base() -> String {
+    env::var("API_URL")
+        .ok()
+        .or_else(|| CONFIGURED_BASE.get().cloned())
+        .unwrap_or_else(|| "http://127.0.0.1:8000".to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Check that the API answers a minimal `/movies/` request
+pub fn health_check(client: &HttpClient) -> Result<(), String> {
+    let url = format!("{}/movies/?limit=1", api_base());
+    match client.get(&url).send() {
+        Ok(resp) if resp.status().is_success() => Ok(()),
+        Ok(resp) => Err(format!("{} returned HTTP {}", url, resp.status())),
+        Err(e) => Err(format!("{} unreachable: {}", url, e)),
+    }
+}
+
+/// Mark the movie stored under one of `keys` (file_key or file path variants) as watched or unwatched
+pub fn set_watched(client: &HttpClient, keys: &[String], watched: bool) -> Result<(), String> {
+    let url = format!("{}/movies/set_watched/", api_base());
+    let watched = if watched { "true" } else { "false" };
+    for key in keys {
+        match client.post(&url).query(&[("path", key.as_str()), ("watched", watched)]).send() {
+            Ok(resp) if resp.status().is_success() => return Ok(()),
+            // 404: not stored under this key, try the next variant
+            Ok(resp) if resp.status().as_u16() == 404 => continue,
+            Ok(resp) => return Err(format!("{} returned HTTP {}", url, resp.status())),
+            Err(e) => return Err(format!("{} unreachable: {}", url, e)),
+        }
+    }
+    Err(format!("no movie found for {}", keys.join(" | ")))
+}
+
+/// Count one watch of the movie stored under `file_key`, or under `imdb_id` when it has no key
+pub fn increment_watch(client: &HttpClient, file_key: Option<&str>, imdb_id: Option<&str>) -> Result<(), String> {
+    let (url, path) = increment_watch_request(&api_base(), file_key, imdb_id)?;
+    let request = || match path {
+        Some(path) => client.post(&url).query(&[("path", path)]),
+        None => client.post(&url),
+    };
+    // A watch that reached the API may already be counted, so it is not sent again
+    match send_with_retry(request, false) {
+        Ok(resp) if resp.status().is_success() => Ok(()),
+        Ok(resp) => Err(format!("{} failed: {}", url, error_summary(resp))),
+        Err(e) => Err(format!("{} unreachable: {}", url, e)),
+    }
+}
+
+/// Where a watch is counted, with the path to pass: /movies/increment_watch/?path= for a file key,
+/// otherwise /movies/{imdb_id}/watch
+fn increment_watch_request<'a>(base: &str, file_key: Option<&'a str>, imdb_id: Option<&str>) -> Result<(String, Option<&'a str>), String> {
+    match (file_key, imdb_id) {
+        (Some(key), _) => Ok((format!("{}/movies/increment_watch/", base), Some(key))),
+        (None, Some(id)) => Ok((format!("{}/movies/{}/watch", base, id), None)),
+        (None, None) => Err("no file_key or imdb_id to count the watch under".to_string()),
+    }
+}
+
+/// Store a rating from 0 to 10 for the movie stored under one of `keys`
+pub fn set_rating(client: &HttpClient, keys: &[String], rating: f64) -> Result<(), String> {
+    let url = format!("{}/movies/set_rating/", api_base());
+    let rating = rating.to_string();
+    for key in keys {
+        match client.post(&url).query(&[("path", key.as_str()), ("rating", rating.as_str())]).send() {
+            Ok(resp) if resp.status().is_success() => return Ok(()),
+            // 404: not stored under this key, try the next variant
+            Ok(resp) if resp.status().as_u16() == 404 => continue,
+            Ok(resp) => return Err(format!("{} returned HTTP {}", url, resp.status())),
+            Err(e) => return Err(format!("{} unreachable: {}", url, e)),
+        }
+    }
+    Err(format!("no movie found for {}", keys.join(" | ")))
+}
+
+/// Drop a deleted file from the movie stored under one of `keys`
+pub fn remove_file(client: &HttpClient, keys: &[String]) -> Result<(), String> {
+    let url = format!("{}/movies/remove_file/", api_base());
+    for key in keys {
+        match client.post(&url).query(&[("path", key.as_str())]).send() {
+            Ok(resp) if resp.status().is_success() => return Ok(()),
+            // 404: not stored under this key, try the next variant
+            Ok(resp) if resp.status().as_u16() == 404 => continue,
+            Ok(resp) => return Err(format!("{} returned HTTP {}", url, resp.status())),
+            Err(e) => return Err(format!("{} unreachable: {}", url, e)),
+        }
+    }
+    Err(format!("no movie found for {}", keys.join(" | ")))
+}
+
+/// Point the movie stored under one of `keys` at a renamed file; `new_keys` holds the same
+/// variants of the new path, in the same order
+pub fn rename_file(client: &HttpClient, keys: &[String], new_keys: &[String]) -> Result<(), String> {
+    let url = format!("{}/movies/rename_file/", api_base());
+    for (key, new_key) in keys.iter().zip(new_keys) {
+        match client.post(&url).query(&[("path", key.as_str()), ("new_path", new_key.as_str())]).send() {
+            Ok(resp) if resp.status().is_success() => return Ok(()),
+            // 404: not stored under this key, try the next variant
+            Ok(resp) if resp.status().as_u16() == 404 => continue,
+            Ok(resp) => return Err(format!("{} returned HTTP {}", url, resp.status())),
+            Err(e) => return Err(format!("{} unreachable: {}", url, e)),
+        }
+    }
+    Err(format!("no movie found for {}", keys.join(" | ")))
+}
+
+/// Read a string field, ignoring non-string values
+fn json_string(mv: &JsonValue, key: &str) -> Option<String> {
+    mv.get(key).and_then(|v| v.as_str().map(|s| s.to_string()))
+}
+
+/// Read an integer field that the backend may send either as a number or as a string like "1994"
+fn json_i64_lenient(mv: &JsonValue, key: &str) -> Option<i64> {
+    match mv.get(key)? {
+        JsonValue::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|f| f as i64)),
+        JsonValue::String(s) => s.trim().parse::<i64>().ok(),
+        _ => None,
+    }
+}
+
+/// Read a float field that the backend may send either as a number or as a string like "8.5"
+fn json_f64_lenient(mv: &JsonValue, key: &str) -> Option<f64> {
+    match mv.get(key)? {
+        JsonValue::Number(n) => n.as_f64(),
+        JsonValue::String(s) => s.trim().parse::<f64>().ok().filter(|f| f.is_finite()),
+        _ => None,
+    }
+}
+
+/// Content rating may be a top-level field, or only present in the raw OMDB payload kept in `additional_info`
+fn json_content_rating(mv: &JsonValue) -> Option<String> {
+    json_string(mv, "content_rating")
+        .or_else(|| json_string(mv, "rated"))
+        .or_else(|| {
+            let extra = match mv.get("additional_info")? {
+                JsonValue::String(raw) => serde_json::from_str::<JsonValue>(raw).ok()?,
+                other => other.clone(),
+            };
+            json_string(&extra, "Rated")
+        })
+        .filter(|r| !r.is_empty() && r != "N/A")
+}
+
+/// A stored file key or path in the form keys are compared in: forward slashes, no "./"
+pub fn normalize_key(key: &str) -> String {
+    let key = key.trim().replace('\\', "/");
+    key.strip_prefix("./").map(str::to_string).unwrap_or(key)
+}
+
+/// Which `/movies/` entry each stored file key and path belongs to
+pub struct KeyIndex {
+    exact: HashMap<String, usize>,
+    // The same keys lowercased, for libraries whose case differs from the database
+    folded: HashMap<String, usize>,
+}
+
+impl KeyIndex {
+    pub fn build(api_movies: &[JsonValue]) -> Self {
+        let mut index = KeyIndex { exact: HashMap::new(), folded: HashMap::new() };
+        for (api_idx, mv) in api_movies.iter().enumerate() {
+            let file_key = mv.get("file_key").and_then(|v| v.as_str());
+            let file_paths = mv.get("file_paths").and_then(|v| v.as_array()).into_iter().flatten().filter_map(|p| p.as_str());
+            for key in file_key.into_iter().chain(file_paths) {
+                let key = normalize_key(key);
+                index.folded.insert(key.to_lowercase(), api_idx);
+                index.exact.insert(key, api_idx);
+            }
+        }
+        index
+    }
+
+    /// The entry stored under the first matching candidate, trying exact matches before ignoring case
+    pub fn find(&self, candidates: &[String]) -> Option<usize> {
+        let keys: Vec<String> = candidates.iter().map(|c| normalize_key(c)).collect();
+        keys.iter().find_map(|k| self.exact.get(k))
+            .or_else(|| keys.iter().find_map(|k| self.folded.get(&k.to_lowercase())))
+            .copied()
+    }
+}
+
+/// Map one movie object from the `/movies/` endpoint onto `MovieInfo`
+pub fn movie_info_from_json(mv: &JsonValue) -> MovieInfo {
+    MovieInfo {
+        title: json_string(mv, "title"),
+        year: json_i64_lenient(mv, "year").map(|n| n as i32),
+        genre: json_string(mv, "genre"),
+        director: json_string(mv, "director"),
+        plot: json_string(mv, "plot"),
+        runtime: json_string(mv, "runtime"),
+        rating: json_f64_lenient(mv, "rating"),
+        watch_count: json_i64_lenient(mv, "watch_count").map(|n| n as i32),
+        content_rating: json_content_rating(mv),
+        poster_url: json_string(mv, "poster_url").filter(|u| u.starts_with("http")),
+        trailer_url: json_string(mv, "trailer_url").filter(|u| u.starts_with("http")),
+        imdb_id: json_string(mv, "imdb_id"),
+        file_key: json_string(mv, "file_key"),
+        file_size: None,
+        file_bytes: None,
+        codec: None,
+        resolution: None,
+        chapters: Vec::new(),
+        subtitles: Vec::new(),
+        duplicates: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn keys_match_across_separators_and_case() {
+        let api_movies = vec![
+            json!({ "file_key": "movies\\Action\\Heat.mkv" }),
+            json!({ "file_key": "x", "file_paths": ["./movies/Drama/Ran.AVI", "Alien.mkv"] }),
+        ];
+        let index = KeyIndex::build(&api_movies);
+        assert_eq!(index.find(&["movies/Action/Heat.mkv".to_string()]), Some(0));
+        assert_eq!(index.find(&["movies/drama/ran.avi".to_string()]), Some(1));
+        assert_eq!(index.find(&["movies/Sci-Fi/Alien.mkv".to_string(), "Alien.mkv".to_string()]), Some(1));
+        assert_eq!(index.find(&["Heat.mkv".to_string()]), None);
+    }
+
+    #[test]
+    fn error_bodies_are_shortened() {
+        assert_eq!(truncate_body("<html>\n  <h1>Internal Server Error</h1>\n</html>\n"), "<html> <h1>Internal Server Error</h1> </html>");
+        let long = "x".repeat(ERROR_BODY_LIMIT + 50);
+        assert_eq!(truncate_body(&long), format!("{}...", "x".repeat(ERROR_BODY_LIMIT)));
+    }
+
+    #[test]
+    fn retry_delays_double() {
+        let policy = RetryPolicy::default();
+        let delays: Vec<u128> = (0..policy.retries).map(|r| policy.delay(r).as_millis()).collect();
+        assert_eq!(delays, vec![200, 400, 800]);
+    }
+
+    #[test]
+    fn year_accepts_number_or_string() {
+        assert_eq!(movie_info_from_json(&json!({"year": 1994})).year, Some(1994));
+        assert_eq!(movie_info_from_json(&json!({"year": "1994"})).year, Some(1994));
+        assert_eq!(movie_info_from_json(&json!({"year": " 2001 "})).year, Some(2001));
+        assert_eq!(movie_info_from_json(&json!({"year": "N/A"})).year, None);
+        assert_eq!(movie_info_from_json(&json!({"year": null})).year, None);
+    }
+
+    #[test]
+    fn rating_accepts_number_or_string() {
+        assert_eq!(movie_info_from_json(&json!({"rating": 8.5})).rating, Some(8.5));
+        assert_eq!(movie_info_from_json(&json!({"rating": 7})).rating, Some(7.0));
+        assert_eq!(movie_info_from_json(&json!({"rating": "8.5"})).rating, Some(8.5));
+        assert_eq!(movie_info_from_json(&json!({"rating": "NaN"})).rating, None);
+        assert_eq!(movie_info_from_json(&json!({"rating": [8.5]})).rating, None);
+    }
+
+    #[test]
+    fn watch_count_accepts_number_or_string() {
+        assert_eq!(movie_info_from_json(&json!({"watch_count": 3})).watch_count, Some(3));
+        assert_eq!(movie_info_from_json(&json!({"watch_count": "12"})).watch_count, Some(12));
+        assert_eq!(movie_info_from_json(&json!({"watch_count": 2.0})).watch_count, Some(2));
+        assert_eq!(movie_info_from_json(&json!({"watch_count": "many"})).watch_count, None);
+    }
+
+    #[test]
+    fn mixed_types_in_one_object() {
+        let info = movie_info_from_json(&json!({
+            "title": "Heat",
+            "year": "1995",
+            "rating": 8.3,
+            "watch_count": "4",
+            "genre": 12,
+            "file_key": "movies/Heat.mkv",
+            "imdb_id": "tt0113277",
+        }));
+        assert_eq!(info.title.as_deref(), Some("Heat"));
+        assert_eq!(info.year, Some(1995));
+        assert_eq!(info.rating, Some(8.3));
+        assert_eq!(info.watch_count, Some(4));
+        assert_eq!(info.genre, None);
+        assert_eq!(info.file_key.as_deref(), Some("movies/Heat.mkv"));
+        assert_eq!(info.imdb_id.as_deref(), Some("tt0113277"));
+    }
+
+    #[test]
+    fn watches_are_counted_by_path_or_imdb_id() {
+        assert_eq!(
+            increment_watch_request("http://nas:8000", Some("movies/Heat.mkv"), Some("tt0113277")),
+            Ok(("http://nas:8000/movies/increment_watch/".to_string(), Some("movies/Heat.mkv")))
+        );
+        assert_eq!(
+            increment_watch_request("http://nas:8000", None, Some("tt0113277")),
+            Ok(("http://nas:8000/movies/tt0113277/watch".to_string(), None))
+        );
+        assert!(increment_watch_request("http://nas:8000", None, None).is_err());
+    }
+
+    #[test]
+    fn poster_url_skips_placeholders() {
+        let url = "https://m.media-amazon.com/images/M/heat.jpg";
+        assert_eq!(movie_info_from_json(&json!({"poster_url": url})).poster_url.as_deref(), Some(url));
+        assert_eq!(movie_info_from_json(&json!({"poster_url": "N/A"})).poster_url, None);
+        assert_eq!(movie_info_from_json(&json!({"poster_url": ""})).poster_url, None);
+        assert_eq!(movie_info_from_json(&json!({"trailer_url": "N/A"})).trailer_url, None);
+    }
+}
@@ -0,0 +1,247 @@
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use crate::chapters::SkipPlan;
+use crate::mpv::StartSpec;
+
+/// The program that plays each title (`--player` or `player` in the config file)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Player {
+    #[default]
+    Mpv,
+    Vlc,
+}
+
+/// How a player run ended, as far as the queue is concerned
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayerExit {
+    // Played to the end (or closed normally), so the queue may continue
+    Finished,
+    // Stopped by the user or a signal
+    Interrupted,
+    // The player could not play the file
+    Failed(i32),
+}
+
+impl Player {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_lowercase().as_str() {
+            "mpv" => Ok(Player::Mpv),
+            "vlc" | "cvlc" => Ok(Player::Vlc),
+            _ => Err(format!("unknown player \"{}\" (expected mpv or vlc)", name)),
+        }
+    }
+
+    pub fn binary(self) -> &'static str {
+        match self {
+            Player::Mpv => "mpv",
+            Player::Vlc => "vlc",
+        }
+    }
+
+    /// Arguments every title gets: fullscreen, exit when the file ends, and subtitles
+    /// off or loaded from the file and any subtitle files next to it
+    pub fn base_args(self, subtitles: bool) -> Vec<String> {
+        let args: &[&str] = match (self, subtitles) {
+            (Player::Mpv, false) => &["--fullscreen", "--no-terminal", "--no-sub"],
+            (Player::Mpv, true) => &["--fullscreen", "--no-terminal", "--sub-auto=fuzzy"],
+            (Player::Vlc, false) => &["--fullscreen", "--play-and-exit", "--no-spu", "--no-sub-autodetect-file"],
+            (Player::Vlc, true) => &["--fullscreen", "--play-and-exit"],
+        };
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    /// The argument for starting at `start`; None when this player cannot start there
+    pub fn start_arg(self, start: StartSpec) -> Option<String> {
+        match (self, start) {
+            (Player::Mpv, start) => Some(start.to_mpv_arg()),
+            (Player::Vlc, StartSpec::Seconds(secs)) => Some(format!("--start-time={}", secs)),
+            // VLC has no percentage start, and its chapter numbering depends on the demuxer
+            (Player::Vlc, _) => None,
+        }
+    }
+
+    /// Arguments replacing the plain file argument to leave out skipped chapters;
+    /// None when this player cannot follow the plan
+    pub fn skip_args(self, plan: &SkipPlan, path: &Path) -> Option<Vec<String>> {
+        match (self, plan) {
+            (Player::Mpv, plan) => Some(plan.mpv_args(path)),
+            (Player::Vlc, SkipPlan::StartAt(secs)) => {
+                Some(vec![format!("--start-time={}", secs), path.to_string_lossy().to_string()])
+            }
+            // VLC cannot play an edit list, so chapters in the middle stay in
+            (Player::Vlc, SkipPlan::Segments(_)) => None,
+        }
+    }
+
+    /// Arguments preferring the given subtitle and audio languages (comma separated, e.g. "eng,en")
+    pub fn language_args(self, subtitles: Option<&str>, audio: Option<&str>) -> Vec<String> {
+        let (sub_flag, audio_flag) = match self {
+            Player::Mpv => ("--slang", "--alang"),
+            Player::Vlc => ("--sub-language", "--audio-language"),
+        };
+        [(sub_flag, subtitles), (audio_flag, audio)]
+            .into_iter()
+            .filter_map(|(flag, langs)| Some(format!("{}={}", flag, langs?.trim())))
+            .collect()
+    }
+
+    /// Arguments loading the subtitle files found next to the movie; VLC takes only one
+    pub fn subtitle_file_args(self, files: &[PathBuf]) -> Vec<String> {
+        let files = match self {
+            Player::Mpv => files,
+            Player::Vlc => &files[..files.len().min(1)],
+        };
+        files.iter().map(|f| format!("--sub-file={}", f.display())).collect()
+    }
+
+    /// The argument for starting at `volume` percent (0-130, 100 is unchanged)
+    pub fn volume_arg(self, volume: u8) -> String {
+        match self {
+            Player::Mpv => format!("--volume={}", volume),
+            // VLC's gain is a factor, 1.0 leaving the volume as it is
+            Player::Vlc => format!("--gain={:.2}", f64::from(volume) / 100.0),
+        }
+    }
+
+    /// The argument for playing at `speed` times normal speed
+    pub fn speed_arg(self, speed: f64) -> String {
+        match self {
+            Player::Mpv => format!("--speed={}", speed),
+            Player::Vlc => format!("--rate={}", speed),
+        }
+    }
+
+    /// Interpret the exit code (None when killed by a signal)
+    pub fn exit(self, code: Option<i32>) -> PlayerExit {
+        match (self, code) {
+            (_, Some(0)) => PlayerExit::Finished,
+            (_, None) => PlayerExit::Interrupted,
+            // mpv exits with 4 when quit by a signal or Ctrl+C
+            (Player::Mpv, Some(4)) => PlayerExit::Interrupted,
+            (_, Some(code)) => PlayerExit::Failed(code),
+        }
+    }
+
+    /// Whether the binary can be found on PATH
+    pub fn is_installed(self) -> bool {
+        on_path(self.binary())
+    }
+}
+
+/// Whether `binary` is a file in one of the PATH directories
+fn on_path(binary: &str) -> bool {
+    let Some(path) = env::var_os("PATH") else {
+        return false;
+    };
+    env::split_paths(&path).any(|dir| dir.join(binary).is_file())
+}
+
+/// Whether ffprobe can be run; looked up once, so a missing ffprobe costs nothing per file
+pub fn ffprobe_installed() -> bool {
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+    *INSTALLED.get_or_init(|| {
+        let installed = on_path("ffprobe");
+        if !installed {
+            log::info!("ffprobe not found on PATH; runtime, codec, resolution and chapters come from the API only");
+        }
+        installed
+    })
+}
+
+/// Whether ffmpeg can be run, for the preview frames of movies without a poster
+pub fn ffmpeg_installed() -> bool {
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+    *INSTALLED.get_or_init(|| {
+        let installed = on_path("ffmpeg");
+        if !installed {
+            log::info!("ffmpeg not found on PATH; movies without a poster show no preview frame");
+        }
+        installed
+    })
+}
+
+/// Whether yt-dlp can be run, which mpv needs to stream trailers
+pub fn ytdlp_installed() -> bool {
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+    *INSTALLED.get_or_init(|| {
+        let installed = on_path("yt-dlp");
+        if !installed {
+            log::info!("yt-dlp not found on PATH; trailers open in the browser");
+        }
+        installed
+    })
+}
+
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.binary())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names() {
+        assert_eq!(Player::parse("VLC").unwrap(), Player::Vlc);
+        assert_eq!(Player::parse(" mpv ").unwrap(), Player::Mpv);
+        assert!(Player::parse("quicktime").is_err());
+    }
+
+    #[test]
+    fn start_positions() {
+        assert_eq!(Player::Mpv.start_arg(StartSpec::Percent(10.0)).as_deref(), Some("--start=10%"));
+        assert_eq!(Player::Vlc.start_arg(StartSpec::Seconds(90.0)).as_deref(), Some("--start-time=90"));
+        assert_eq!(Player::Vlc.start_arg(StartSpec::Chapter(2)), None);
+    }
+
+    #[test]
+    fn vlc_only_skips_leading_chapters() {
+        let path = Path::new("a.mkv");
+        assert_eq!(Player::Vlc.skip_args(&SkipPlan::StartAt(90.0), path), Some(vec!["--start-time=90".to_string(), "a.mkv".to_string()]));
+        assert_eq!(Player::Vlc.skip_args(&SkipPlan::Segments(vec![(0.0, 10.0), (20.0, 30.0)]), path), None);
+    }
+
+    #[test]
+    fn languages() {
+        assert_eq!(Player::Mpv.language_args(Some("eng"), Some("jpn")), vec!["--slang=eng", "--alang=jpn"]);
+        assert_eq!(Player::Vlc.language_args(None, Some("jpn,ja")), vec!["--audio-language=jpn,ja"]);
+        assert!(Player::Mpv.language_args(None, None).is_empty());
+    }
+
+    #[test]
+    fn subtitle_files() {
+        let files = [PathBuf::from("/m/Heat.en.srt"), PathBuf::from("/m/Heat.de.ass")];
+        assert_eq!(Player::Mpv.subtitle_file_args(&files), vec!["--sub-file=/m/Heat.en.srt", "--sub-file=/m/Heat.de.ass"]);
+        assert_eq!(Player::Vlc.subtitle_file_args(&files), vec!["--sub-file=/m/Heat.en.srt"]);
+        assert!(Player::Mpv.subtitle_file_args(&[]).is_empty());
+    }
+
+    #[test]
+    fn volume() {
+        assert_eq!(Player::Mpv.volume_arg(60), "--volume=60");
+        assert_eq!(Player::Vlc.volume_arg(130), "--gain=1.30");
+    }
+
+    #[test]
+    fn speed() {
+        assert_eq!(Player::Mpv.speed_arg(1.25), "--speed=1.25");
+        assert_eq!(Player::Vlc.speed_arg(2.0), "--rate=2");
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(Player::Mpv.exit(Some(0)), PlayerExit::Finished);
+        assert_eq!(Player::Mpv.exit(Some(4)), PlayerExit::Interrupted);
+        assert_eq!(Player::Mpv.exit(Some(2)), PlayerExit::Failed(2));
+        assert_eq!(Player::Vlc.exit(Some(4)), PlayerExit::Failed(4));
+        assert_eq!(Player::Vlc.exit(None), PlayerExit::Interrupted);
+    }
+}
@@ -0,0 +1,201 @@
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// Chapter names skipped when SKIP_CHAPTERS is not set
+const DEFAULT_SKIP_NAMES: &[&str] = &["intro", "opening", "recap", "previously"];
+
+/// One chapter as reported by ffprobe
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub start: f64,
+    pub end: f64,
+    pub title: Option<String>,
+}
+
+/// Read the `chapters` array of `ffprobe -show_chapters -of json` output
+pub fn from_ffprobe_json(json: &JsonValue) -> Vec<Chapter> {
+    let Some(chapters) = json.get("chapters").and_then(|c| c.as_array()) else {
+        return Vec::new();
+    };
+    chapters.iter()
+        .filter_map(|ch| {
+            // ffprobe reports times as strings like "95.512000"
+            let time = |key: &str| ch.get(key).and_then(|v| v.as_str()).and_then(|s| s.parse::<f64>().ok());
+            Some(Chapter {
+                start: time("start_time")?,
+                end: time("end_time")?,
+                title: ch.get("tags")
+                    .and_then(|t| t.get("title"))
+                    .and_then(|t| t.as_str())
+                    .map(|t| t.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Chapters of `path`, empty if ffprobe is missing or the file has none
+pub fn probe(path: &Path) -> Vec<Chapter> {
+    if !crate::backend::ffprobe_installed() {
+        return Vec::new();
+    }
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_chapters", "-of", "json"])
+        .arg(path)
+        .output();
+    match output {
+        Ok(output) if output.status.success() => serde_json::from_slice(&output.stdout)
+            .map(|json| from_ffprobe_json(&json))
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// How to play a file with its skip-worthy chapters left out
+#[derive(Clone, Debug, PartialEq)]
+pub enum SkipPlan {
+    // Only leading chapters are skipped, so starting later is enough
+    StartAt(f64),
+    // Time ranges (start, end) to play, in order
+    Segments(Vec<(f64, f64)>),
+}
+
+impl SkipPlan {
+    /// mpv arguments replacing the plain file argument for `path`
+    pub fn mpv_args(&self, path: &Path) -> Vec<String> {
+        let path = path.to_string_lossy();
+        match self {
+            SkipPlan::StartAt(secs) => vec![format!("--start={}", secs), path.to_string()],
+            SkipPlan::Segments(ranges) => {
+                // EDL entries take a length-prefixed file name so commas in paths are safe
+                let entries: Vec<String> = ranges.iter()
+                    .map(|(start, end)| format!("%{}%{},{},{}", path.len(), path, start, end - start))
+                    .collect();
+                vec![format!("edl://{}", entries.join(";"))]
+            }
+        }
+    }
+}
+
+/// Which chapters to skip automatically, and for which groups (SKIP_CHAPTERS / SKIP_CHAPTER_GROUPS)
+#[derive(Clone, Debug, Default)]
+pub struct ChapterSkip {
+    // Lowercase chapter names; a chapter is skipped when its title contains one of them
+    names: Vec<String>,
+    // Groups that opted in; "*" enables every group
+    groups: Vec<String>,
+}
+
+impl ChapterSkip {
+    pub fn from_env() -> Self {
+        let list = |key: &str| -> Option<Vec<String>> {
+            env::var(key).ok().map(|v| {
+                v.split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+        };
+        ChapterSkip {
+            names: list("SKIP_CHAPTERS")
+                .unwrap_or_else(|| DEFAULT_SKIP_NAMES.iter().map(|s| s.to_string()).collect())
+                .into_iter()
+                .map(|s| s.to_lowercase())
+                .collect(),
+            groups: list("SKIP_CHAPTER_GROUPS").unwrap_or_default(),
+        }
+    }
+
+    pub fn applies_to(&self, group_name: &str) -> bool {
+        self.groups.iter().any(|g| g == "*" || g.eq_ignore_ascii_case(group_name))
+    }
+
+    fn is_skippable(&self, chapter: &Chapter) -> bool {
+        let Some(title) = chapter.title.as_deref() else {
+            return false;
+        };
+        let title = title.to_lowercase();
+        self.names.iter().any(|name| title.contains(name.as_str()))
+    }
+
+    /// Work out how to leave out skip-worthy chapters. None when there is nothing to skip
+    /// (or everything would be skipped, which more likely means the names are too broad).
+    pub fn plan(&self, chapters: &[Chapter]) -> Option<SkipPlan> {
+        let mut ranges: Vec<(f64, f64)> = Vec::new();
+        let mut skipped_any = false;
+        for chapter in chapters {
+            if self.is_skippable(chapter) {
+                skipped_any = true;
+                continue;
+            }
+            match ranges.last_mut() {
+                // Merge with the previous kept chapter when they are adjacent
+                Some(last) if (chapter.start - last.1).abs() < 0.5 => last.1 = chapter.end,
+                _ => ranges.push((chapter.start, chapter.end)),
+            }
+        }
+        if !skipped_any || ranges.is_empty() {
+            return None;
+        }
+        if ranges.len() == 1 && (ranges[0].1 - chapters[chapters.len() - 1].end).abs() < 0.5 {
+            return Some(SkipPlan::StartAt(ranges[0].0));
+        }
+        Some(SkipPlan::Segments(ranges))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chapter(start: f64, end: f64, title: &str) -> Chapter {
+        Chapter { start, end, title: Some(title.to_string()) }
+    }
+
+    fn skip() -> ChapterSkip {
+        ChapterSkip {
+            names: vec!["intro".to_string(), "recap".to_string()],
+            groups: vec!["Shows".to_string()],
+        }
+    }
+
+    #[test]
+    fn parses_ffprobe_chapters() {
+        let json = json!({"chapters": [
+            {"start_time": "0.000000", "end_time": "95.500000", "tags": {"title": "Intro"}},
+            {"start_time": "95.500000", "end_time": "1400.000000"},
+        ]});
+        assert_eq!(from_ffprobe_json(&json), vec![
+            chapter(0.0, 95.5, "Intro"),
+            Chapter { start: 95.5, end: 1400.0, title: None },
+        ]);
+        assert!(from_ffprobe_json(&json!({})).is_empty());
+    }
+
+    #[test]
+    fn leading_chapters_become_a_start_offset() {
+        let chapters = [chapter(0.0, 60.0, "Recap"), chapter(60.0, 90.0, "Opening Intro"), chapter(90.0, 1400.0, "Part 1")];
+        assert_eq!(skip().plan(&chapters), Some(SkipPlan::StartAt(90.0)));
+    }
+
+    #[test]
+    fn cold_open_keeps_both_sides_of_the_intro() {
+        let chapters = [chapter(0.0, 120.0, "Cold Open"), chapter(120.0, 210.0, "Intro"), chapter(210.0, 1400.0, "Episode")];
+        let plan = skip().plan(&chapters).unwrap();
+        assert_eq!(plan, SkipPlan::Segments(vec![(0.0, 120.0), (210.0, 1400.0)]));
+        assert_eq!(plan.mpv_args(Path::new("a,b.mkv")), vec!["edl://%7%a,b.mkv,0,120;%7%a,b.mkv,210,1190"]);
+    }
+
+    #[test]
+    fn nothing_to_skip() {
+        assert_eq!(skip().plan(&[]), None);
+        assert_eq!(skip().plan(&[chapter(0.0, 100.0, "Chapter 1")]), None);
+        assert_eq!(skip().plan(&[chapter(0.0, 100.0, "Intro")]), None);
+        assert!(skip().applies_to("shows"));
+        assert!(!skip().applies_to("Movies"));
+    }
+}
@@ -0,0 +1,125 @@
+use serde::Deserialize;
+use serde::de::value::{Error as ValueError, StrDeserializer};
+
+use crate::keys::Action;
+use crate::ratings;
+use crate::sort::SortMode;
+use crate::years::YearRange;
+
+/// Something typed on the `:` command line
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    // :sort year
+    Sort(SortMode),
+    // :filter genre=comedy,drama / year=1990s / rating=7 / clear
+    Genres(Vec<String>),
+    Years(YearRange),
+    MinRating(f64),
+    ClearFilters,
+    // :queue clear (":queue add" and ":queue" run the Enqueue and ShowQueue actions)
+    ClearQueue,
+    // :playlist save friday / :playlist load friday
+    SavePlaylist(String),
+    LoadPlaylist(String),
+    // :export ~/movies.json (":export" alone asks for the file)
+    Export(String),
+    // Any key action by its config name, e.g. ":refresh" or ":toggle_shuffle"
+    Action(Action),
+}
+
+/// A sort order by its label, ignoring case, spaces and underscores: "year", "watch_count"
+fn sort_mode(name: &str) -> Option<SortMode> {
+    let squash = |s: &str| s.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+    let wanted = squash(name);
+    let mut mode = SortMode::default();
+    loop {
+        if squash(mode.label()) == wanted {
+            return Some(mode);
+        }
+        mode = mode.next();
+        if mode == SortMode::default() {
+            return None;
+        }
+    }
+}
+
+fn action(name: &str) -> Option<Action> {
+    Action::deserialize(StrDeserializer::<ValueError>::new(&name.replace('-', "_"))).ok()
+}
+
+fn filter(spec: &str) -> Result<Command, String> {
+    if matches!(spec, "clear" | "reset" | "off") {
+        return Ok(Command::ClearFilters);
+    }
+    let Some((field, value)) = spec.split_once('=') else {
+        return Err("usage: filter genre=NAME, year=RANGE, rating=MIN or clear".to_string());
+    };
+    let value = value.trim();
+    match field.trim() {
+        "genre" | "genres" => {
+            let genres: Vec<String> = value.split(',').map(str::trim).filter(|g| !g.is_empty()).map(String::from).collect();
+            if genres.is_empty() {
+                return Err("no genre given".to_string());
+            }
+            Ok(Command::Genres(genres))
+        }
+        "year" | "years" => YearRange::parse(value).map(Command::Years),
+        "rating" => ratings::parse(value).map(Command::MinRating),
+        other => Err(format!("cannot filter by \"{}\"", other)),
+    }
+}
+
+/// Parse a command line (without the leading ':')
+pub fn parse(input: &str) -> Result<Command, String> {
+    let input = input.trim().trim_start_matches(':').trim_start();
+    let (word, rest) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    let rest = rest.trim();
+    match (word, rest) {
+        ("", _) => Err("no command".to_string()),
+        ("sort", "") => Ok(Command::Action(Action::CycleSort)),
+        ("sort", name) => sort_mode(name).map(Command::Sort).ok_or_else(|| format!("unknown sort order \"{}\"", name)),
+        ("filter", spec) => filter(spec),
+        ("queue", "") | ("queue", "show") => Ok(Command::Action(Action::ShowQueue)),
+        ("queue", "add") => Ok(Command::Action(Action::Enqueue)),
+        ("queue", "clear") => Ok(Command::ClearQueue),
+        ("export", "") => Ok(Command::Action(Action::ExportList)),
+        ("export", file) => Ok(Command::Export(file.to_string())),
+        ("playlist", spec) => match spec.split_once(char::is_whitespace) {
+            Some(("save", name)) => Ok(Command::SavePlaylist(name.trim().to_string())),
+            Some(("load", name)) => Ok(Command::LoadPlaylist(name.trim().to_string())),
+            _ => Err("usage: playlist save NAME or playlist load NAME".to_string()),
+        },
+        (name, "") => action(name).map(Command::Action).ok_or_else(|| format!("unknown command \"{}\"", name)),
+        (name, _) => Err(format!("unknown command \"{}\"", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_command() {
+        assert_eq!(parse("sort year"), Ok(Command::Sort(SortMode::Year)));
+        assert_eq!(parse(":sort watch_count"), Ok(Command::Sort(SortMode::WatchCount)));
+        assert_eq!(parse("filter genre=comedy, drama"), Ok(Command::Genres(vec!["comedy".to_string(), "drama".to_string()])));
+        assert_eq!(parse("filter year=1990s"), Ok(Command::Years(YearRange { min: Some(1990), max: Some(1999) })));
+        assert_eq!(parse("filter rating=7.5"), Ok(Command::MinRating(7.5)));
+        assert_eq!(parse("filter clear"), Ok(Command::ClearFilters));
+        assert_eq!(parse("queue add"), Ok(Command::Action(Action::Enqueue)));
+        assert_eq!(parse("playlist save friday night"), Ok(Command::SavePlaylist("friday night".to_string())));
+        assert_eq!(parse("  refresh "), Ok(Command::Action(Action::Refresh)));
+        assert_eq!(parse("export ~/movies.json"), Ok(Command::Export("~/movies.json".to_string())));
+        assert_eq!(parse("toggle-shuffle"), Ok(Command::Action(Action::ToggleShuffle)));
+    }
+
+    #[test]
+    fn mistakes_are_explained() {
+        assert_eq!(parse("sort colour"), Err("unknown sort order \"colour\"".to_string()));
+        assert_eq!(parse("refresh now"), Err("unknown command \"refresh\"".to_string()));
+        assert!(parse("filter rating=11").is_err());
+        assert!(parse("filter size=2").is_err());
+        assert!(parse("playlist delete x").is_err());
+        assert!(parse("").is_err());
+    }
+}
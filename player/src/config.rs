@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::backend::Player;
+use crate::keys::Action;
+use crate::persist;
+use crate::RepeatMode;
+use crate::theme::{ColorOverrides, ThemePreset};
+
+/// Settings from ~/.config/movieplayer/config.toml. Every key is optional;
+/// anything missing keeps its built-in default, and CLI flags and env vars win over the file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // Movie roots, as with --movies-dir
+    pub movies_dirs: Vec<PathBuf>,
+    pub api_url: Option<String>,
+    // Seconds to wait for the API to connect and to answer (default 5)
+    pub api_timeout_secs: Option<u64>,
+    // Retries of a failed API request, waiting api_retry_delay_ms and doubling each time
+    pub api_retries: Option<u32>,
+    pub api_retry_delay_ms: Option<u64>,
+    // Seconds of inactivity before a random movie starts (0 turns the idle auto-play off)
+    pub autoplay_timeout: Option<u64>,
+    // Initial state of the shuffle (s), autoplay next (n), idle auto-play (t) and subtitles (c) toggles
+    pub shuffle: Option<bool>,
+    pub autoplay_next: Option<bool>,
+    pub idle_autoplay: Option<bool>,
+    pub subtitles: Option<bool>,
+    // Initial repeat mode (l): "off", "all" (the queue) or "one" (the same title)
+    pub repeat: Option<RepeatMode>,
+    // Start with the high-contrast colors (H switches them)
+    pub high_contrast: Option<bool>,
+    // Program used for playback: "mpv" (default) or "vlc"
+    pub player: Option<Player>,
+    // Extra arguments for every player invocation (formerly mpv_args)
+    #[serde(alias = "mpv_args")]
+    pub player_args: Vec<String>,
+    // Use player_args instead of the built-in arguments (e.g. to drop --no-sub or --fullscreen)
+    pub replace_default_args: bool,
+    // Extra file extensions listed as movies, e.g. ["divx"]; with replace_video_extensions
+    // they are the only ones
+    pub video_extensions: Vec<String>,
+    pub replace_video_extensions: bool,
+    // Delete the movie's subtitle and AppleDouble ("._") files along with it (default off), and
+    // drop the file from the API's database (default on)
+    pub delete_sidecars: Option<bool>,
+    pub delete_from_api: Option<bool>,
+    // Folder groups to list first, in this order; the others follow by name ("Root" first)
+    pub group_order: Vec<String>,
+    // Preferred subtitle and audio track languages, comma separated (mpv's --slang/--alang)
+    pub slang: Option<String>,
+    pub alang: Option<String>,
+    // Starting volume in percent, 0-130 (default: the player's own)
+    pub volume: Option<u32>,
+    // Playback speed as a factor, 0.25-4 (default 1)
+    pub speed: Option<f64>,
+    // Show mpv's progress in the terminal while it plays, with pause and seek keys (default on)
+    pub now_playing: Option<bool>,
+    // A playback counts as a watch once the player was open this long (default 60),
+    // or once mpv reports this much of the film as played (default 90)
+    pub watched_after_secs: Option<u64>,
+    pub watched_at_percent: Option<u8>,
+    // Save the position when quitting mpv and resume from it next time (default on)
+    pub resume: Option<bool>,
+    // Whether unrated movies stay listed while the minimum rating filter (+/-) is on (default on)
+    pub include_unrated: Option<bool>,
+    // Make the random pick favor movies watched fewer times; off picks uniformly (default on)
+    pub favor_unwatched: Option<bool>,
+    // Random picks only choose unwatched movies while there are any (default off)
+    pub random_unwatched_only: Option<bool>,
+    // Run ffprobe over the whole library in the background at startup (default off)
+    pub prewarm_probe: Option<bool>,
+    // Rescan the library when files are added to or removed from the movie folders (default off)
+    pub watch_folders: Option<bool>,
+    // Click to select and play, wheel to move through the list (default on; off leaves the
+    // mouse to the terminal, e.g. for selecting text)
+    pub mouse: Option<bool>,
+    // Vim navigation: j/k move, G jumps to the bottom and g g to the top (default off;
+    // k and g then no longer toggle the content filter or open the genre picker)
+    pub vim_keys: Option<bool>,
+    // Ask y/n before Esc quits from the list (default off)
+    pub confirm_exit: Option<bool>,
+    // Show the movie's poster, or a frame from the film without one, in the info panel in
+    // terminals with graphics support (kitty, iTerm2, sixel; default on)
+    pub posters: Option<bool>,
+    // Width of the info panel in percent of the screen, 15-60 (default 30); </> move it at runtime
+    pub info_panel_percent: Option<u16>,
+    // Color scheme: "default", "mono" or "high-contrast", with per-role colors from [colors] on top
+    pub theme: Option<ThemePreset>,
+    pub colors: ColorOverrides,
+    // Key bindings per action, e.g. navigate_down = ["Down", "j"]
+    pub keys: HashMap<Action, Vec<String>>,
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    persist::config_dir().map(|dir| dir.join("config.toml"))
+}
+
+/// Read the config file; a missing file gives the defaults, a malformed one is an error
+pub fn load() -> Result<Config, String> {
+    let Some(path) = config_path() else {
+        return Ok(Config::default());
+    };
+    match fs::read_to_string(&path) {
+        Ok(raw) => toml::from_str(&raw).map_err(|e| format!("Invalid config {}: {}", path.display(), e)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(format!("Cannot read config {}: {}", path.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_keys_keep_defaults() {
+        let config: Config = toml::from_str("shuffle = true").unwrap();
+        assert_eq!(config.shuffle, Some(true));
+        assert_eq!(config.autoplay_timeout, None);
+        assert!(config.movies_dirs.is_empty());
+        assert!(config.player_args.is_empty());
+        assert!(!config.replace_default_args);
+    }
+
+    #[test]
+    fn full_config() {
+        let config: Config = toml::from_str(r#"
+            movies_dirs = ["/media/movies", "/mnt/nas/movies"]
+            api_url = "http://nas:8000"
+            api_timeout_secs = 2
+            api_retries = 1
+            api_retry_delay_ms = 500
+            autoplay_timeout = 60
+            autoplay_next = false
+            idle_autoplay = false
+            subtitles = true
+            repeat = "one"
+            high_contrast = true
+            favor_unwatched = false
+            prewarm_probe = true
+            watch_folders = true
+            vim_keys = true
+            mouse = false
+            posters = false
+            confirm_exit = true
+            info_panel_percent = 40
+            theme = "high-contrast"
+            video_extensions = ["divx", ".VOB"]
+            group_order = ["Must Watch", "Root"]
+            delete_sidecars = true
+            delete_from_api = false
+            random_unwatched_only = true
+            player = "vlc"
+            volume = 70
+            speed = 1.25
+            slang = "eng"
+            alang = "jpn,ja"
+            now_playing = false
+            watched_after_secs = 1200
+            watched_at_percent = 80
+            mpv_args = ["--volume=80"]
+        "#).unwrap();
+        assert_eq!(config.movies_dirs, vec![PathBuf::from("/media/movies"), PathBuf::from("/mnt/nas/movies")]);
+        assert_eq!(config.api_url.as_deref(), Some("http://nas:8000"));
+        assert_eq!(config.api_timeout_secs, Some(2));
+        assert_eq!((config.api_retries, config.api_retry_delay_ms), (Some(1), Some(500)));
+        assert_eq!(config.autoplay_timeout, Some(60));
+        assert_eq!(config.autoplay_next, Some(false));
+        assert_eq!(config.idle_autoplay, Some(false));
+        assert_eq!(config.subtitles, Some(true));
+        assert_eq!(config.repeat, Some(RepeatMode::One));
+        assert_eq!(config.high_contrast, Some(true));
+        assert_eq!(config.favor_unwatched, Some(false));
+        assert_eq!(config.prewarm_probe, Some(true));
+        assert_eq!(config.watch_folders, Some(true));
+        assert_eq!(config.vim_keys, Some(true));
+        assert_eq!(config.mouse, Some(false));
+        assert_eq!(config.posters, Some(false));
+        assert_eq!(config.confirm_exit, Some(true));
+        assert_eq!(config.info_panel_percent, Some(40));
+        assert_eq!(config.theme, Some(ThemePreset::HighContrast));
+        assert_eq!(config.video_extensions, vec!["divx", ".VOB"]);
+        assert_eq!(config.group_order, vec!["Must Watch", "Root"]);
+        assert_eq!((config.delete_sidecars, config.delete_from_api), (Some(true), Some(false)));
+        assert!(!config.replace_video_extensions);
+        assert_eq!(config.random_unwatched_only, Some(true));
+        assert_eq!(config.player, Some(Player::Vlc));
+        assert_eq!(config.volume, Some(70));
+        assert_eq!(config.speed, Some(1.25));
+        assert_eq!((config.slang.as_deref(), config.alang.as_deref()), (Some("eng"), Some("jpn,ja")));
+        assert_eq!(config.now_playing, Some(false));
+        assert_eq!((config.watched_after_secs, config.watched_at_percent), (Some(1200), Some(80)));
+        assert_eq!(config.player_args, vec!["--volume=80"]);
+    }
+
+    #[test]
+    fn player_args_can_replace_the_defaults() {
+        let config: Config = toml::from_str(r#"
+            player_args = ["--fullscreen", "--sub-auto=fuzzy"]
+            replace_default_args = true
+        "#).unwrap();
+        assert_eq!(config.player_args, vec!["--fullscreen", "--sub-auto=fuzzy"]);
+        assert!(config.replace_default_args);
+    }
+
+    #[test]
+    fn colors_table() {
+        let config: Config = toml::from_str(r##"
+            theme = "mono"
+            [colors]
+            selected = "yellow"
+            border = "#336699"
+        "##).unwrap();
+        assert_eq!(config.theme, Some(ThemePreset::Mono));
+        assert_eq!(config.colors.selected.as_deref(), Some("yellow"));
+        assert_eq!(config.colors.border.as_deref(), Some("#336699"));
+        assert!(toml::from_str::<Config>("[colors]\nborderr = \"red\"").is_err());
+    }
+
+    #[test]
+    fn typos_are_rejected() {
+        assert!(toml::from_str::<Config>("shufle = true").is_err());
+    }
+}
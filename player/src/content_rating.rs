@@ -0,0 +1,148 @@
+use std::env;
+
+use crate::MovieInfo;
+
+/// Minimum viewer age implied by a content rating such as "PG-13", "TV-MA" or "16+".
+/// Returns None for unrated or unrecognised values.
+pub fn rating_age(rating: &str) -> Option<u8> {
+    let normalized = rating.trim().to_uppercase();
+    let age = match normalized.as_str() {
+        "G" | "TV-Y" | "TV-G" | "U" | "ALL" => 0,
+        "TV-Y7" | "TV-Y7-FV" => 7,
+        "PG" => 8,
+        "TV-PG" => 10,
+        "PG-13" => 13,
+        "TV-14" => 14,
+        "R" | "TV-MA" => 17,
+        "NC-17" | "X" => 18,
+        other => {
+            // Numeric age ratings like "12", "16+" or "FSK 18"
+            let digits: String = other.chars().filter(|c| c.is_ascii_digit()).collect();
+            return digits.parse::<u8>().ok();
+        }
+    };
+    Some(age)
+}
+
+/// Hides movies above a chosen content rating (parental control)
+#[derive(Clone, Debug, Default)]
+pub struct ContentFilter {
+    // Label of the highest allowed rating, e.g. "PG-13"
+    pub max_rating: Option<String>,
+    pub show_unrated: bool,
+    // Kids mode keeps the filter on unless turning it off is confirmed
+    pub kids_mode: bool,
+    pub enabled: bool,
+}
+
+impl ContentFilter {
+    /// Read MAX_CONTENT_RATING, SHOW_UNRATED and KIDS_MODE
+    pub fn from_env() -> Self {
+        Self::from_vars(|key| env::var(key).ok())
+    }
+
+    /// The filter set by the variables `var` looks up
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let max_rating = var("MAX_CONTENT_RATING").filter(|r| rating_age(r).is_some());
+        let flag = |key: &str| var(key).map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"));
+        let kids_mode = flag("KIDS_MODE").unwrap_or(false) && max_rating.is_some();
+        ContentFilter {
+            enabled: max_rating.is_some(),
+            max_rating,
+            // Unrated titles are hidden in kids mode unless explicitly allowed
+            show_unrated: flag("SHOW_UNRATED").unwrap_or(!kids_mode),
+            kids_mode,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.enabled && self.max_rating.is_some()
+    }
+
+    pub fn allows(&self, info: Option<&MovieInfo>) -> bool {
+        if !self.is_active() {
+            return true;
+        }
+        let max_age = self.max_rating.as_deref().and_then(rating_age).unwrap_or(u8::MAX);
+        match info.and_then(|i| i.content_rating.as_deref()).and_then(rating_age) {
+            Some(age) => age <= max_age,
+            None => self.show_unrated,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(vars: &[(&str, &str)]) -> ContentFilter {
+        ContentFilter::from_vars(|key| vars.iter().find(|(k, _)| *k == key).map(|(_, v)| v.to_string()))
+    }
+
+    fn rated(rating: &str) -> MovieInfo {
+        MovieInfo { content_rating: Some(rating.to_string()), ..Default::default() }
+    }
+
+    #[test]
+    fn us_ratings() {
+        assert_eq!(rating_age("G"), Some(0));
+        assert_eq!(rating_age(" pg-13 "), Some(13));
+        assert_eq!(rating_age("R"), Some(17));
+        assert_eq!(rating_age("NC-17"), Some(18));
+        assert_eq!(rating_age("TV-MA"), Some(17));
+        assert_eq!(rating_age("TV-Y7-FV"), Some(7));
+    }
+
+    #[test]
+    fn uk_and_numeric_ratings() {
+        assert_eq!(rating_age("U"), Some(0));
+        assert_eq!(rating_age("12A"), Some(12));
+        assert_eq!(rating_age("15"), Some(15));
+        assert_eq!(rating_age("18"), Some(18));
+        assert_eq!(rating_age("FSK 16"), Some(16));
+    }
+
+    #[test]
+    fn unknown_ratings() {
+        assert_eq!(rating_age(""), None);
+        assert_eq!(rating_age("Not Rated"), None);
+        assert_eq!(rating_age("Approved"), None);
+    }
+
+    #[test]
+    fn reads_the_environment() {
+        let off = filter(&[]);
+        assert!(!off.is_active());
+        assert!(off.allows(Some(&rated("NC-17"))));
+
+        // An unknown maximum leaves the filter off
+        assert!(!filter(&[("MAX_CONTENT_RATING", "family")]).is_active());
+
+        let pg13 = filter(&[("MAX_CONTENT_RATING", "PG-13")]);
+        assert!(pg13.is_active() && !pg13.kids_mode && pg13.show_unrated);
+
+        // Kids mode hides unrated titles unless SHOW_UNRATED says otherwise
+        let kids = filter(&[("MAX_CONTENT_RATING", "PG"), ("KIDS_MODE", "yes")]);
+        assert!(kids.kids_mode && !kids.show_unrated);
+        assert!(filter(&[("MAX_CONTENT_RATING", "PG"), ("KIDS_MODE", "1"), ("SHOW_UNRATED", "true")]).show_unrated);
+        assert!(!filter(&[("KIDS_MODE", "1")]).kids_mode);
+    }
+
+    #[test]
+    fn allows_up_to_the_maximum() {
+        let pg13 = filter(&[("MAX_CONTENT_RATING", "PG-13")]);
+        assert!(pg13.allows(Some(&rated("PG"))));
+        assert!(pg13.allows(Some(&rated("12A"))));
+        assert!(pg13.allows(Some(&rated("PG-13"))));
+        assert!(!pg13.allows(Some(&rated("R"))));
+        assert!(!pg13.allows(Some(&rated("15"))));
+
+        // Unrated titles, with or without metadata, follow SHOW_UNRATED
+        assert!(pg13.allows(None));
+        assert!(pg13.allows(Some(&rated("Not Rated"))));
+        let strict = filter(&[("MAX_CONTENT_RATING", "PG-13"), ("SHOW_UNRATED", "no")]);
+        assert!(!strict.allows(None));
+        assert!(!strict.allows(Some(&MovieInfo::default())));
+        assert!(strict.allows(Some(&rated("G"))));
+    }
+}
@@ -0,0 +1,51 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::subtitles;
+
+/// macOS's AppleDouble companion of `file` ("._Heat.mkv" next to "Heat.mkv")
+fn apple_double(file: &Path) -> Option<PathBuf> {
+    let name = file.file_name()?.to_str()?;
+    Some(file.with_file_name(format!("._{}", name)))
+}
+
+/// Files that only make sense next to `video`: its subtitle files, and the AppleDouble files
+/// of both, when they exist
+pub fn sidecars(video: &Path) -> Vec<PathBuf> {
+    let subs = subtitles::find(video);
+    let doubles = std::iter::once(video).chain(subs.iter().map(PathBuf::as_path)).filter_map(apple_double);
+    let doubles: Vec<PathBuf> = doubles.filter(|p| p.is_file()).collect();
+    subs.into_iter().chain(doubles).collect()
+}
+
+/// Delete `video` from disk, with its sidecar files when `with_sidecars` is set. Returns the
+/// sidecars removed; a sidecar that cannot be removed is left alone once the video is gone.
+pub fn delete(video: &Path, with_sidecars: bool) -> Result<Vec<PathBuf>, String> {
+    // Looked up first: the subtitles are found by the video's name in its folder
+    let extra = if with_sidecars { sidecars(video) } else { Vec::new() };
+    fs::remove_file(video).map_err(|e| format!("Cannot delete {}: {}", video.display(), e))?;
+    Ok(extra.into_iter().filter(|p| fs::remove_file(p).is_ok()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::test_dir;
+
+    #[test]
+    fn removes_the_video_and_its_sidecars() {
+        let dir = test_dir("delete");
+        for name in ["Heat.mkv", "._Heat.mkv", "Heat.en.srt", "._Heat.en.srt", "Heat 2.mkv", "Heat 2.srt"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        let video = dir.join("Heat.mkv");
+        let mut removed = delete(&video, true).unwrap();
+        removed.sort();
+        assert_eq!(removed, vec![dir.join("._Heat.en.srt"), dir.join("._Heat.mkv"), dir.join("Heat.en.srt")]);
+        assert!(!video.exists());
+        assert!(dir.join("Heat 2.srt").exists());
+        assert!(delete(&dir.join("Heat 2.mkv"), false).unwrap().is_empty());
+        assert!(dir.join("Heat 2.srt").exists());
+        assert!(delete(&video, true).is_err());
+    }
+}
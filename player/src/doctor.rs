@@ -0,0 +1,248 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde_json::Value as JsonValue;
+
+use crate::backend::Player;
+use crate::{api, api_keys, classify_file, scan_movies, FileKind, MovieEntry};
+
+/// The outcome of one check, with the files or keys it is about
+#[derive(Debug, PartialEq)]
+enum Finding {
+    Ok(String),
+    Warn(String, Vec<String>),
+    Fail(String, Vec<String>),
+}
+
+#[derive(Default)]
+struct Report {
+    problems: usize,
+    warnings: usize,
+}
+
+impl Report {
+    fn print(&mut self, finding: Finding) {
+        let (tag, msg, details) = match finding {
+            Finding::Ok(msg) => ("[ok]  ", msg, Vec::new()),
+            Finding::Warn(msg, details) => {
+                self.warnings += 1;
+                ("[warn]", msg, details)
+            }
+            Finding::Fail(msg, details) => {
+                self.problems += 1;
+                ("[FAIL]", msg, details)
+            }
+        };
+        println!("  {} {}", tag, msg);
+        for detail in details {
+            println!("         - {}", detail);
+        }
+    }
+}
+
+/// First line of `<tool> --version`, if the tool can be run
+fn tool_version(tool: &str) -> Option<String> {
+    let arg = if tool == "ffprobe" { "-version" } else { "--version" };
+    let output = Command::new(tool).arg(arg).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).lines().next().map(|l| l.trim().to_string())
+}
+
+fn walk_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Whether a file key stored by the API (e.g. "movies/Action/x.mkv") exists under any movie root
+fn key_exists(key: &str, roots: &[PathBuf]) -> bool {
+    let rel = key.trim_start_matches("./");
+    let rel = rel.strip_prefix("movies/").unwrap_or(rel);
+    roots.iter().any(|root| root.join(rel).exists())
+}
+
+fn display_all(paths: &[&PathBuf]) -> Vec<String> {
+    paths.iter().map(|p| p.display().to_string()).collect()
+}
+
+/// Whether a tool is installed; `needed` makes a missing tool a problem rather than a warning
+fn check_tool(name: &str, version: Option<String>, needed: bool, missing: &str) -> Finding {
+    match version {
+        Some(v) => Finding::Ok(format!("{}: {}", name, v)),
+        None if needed => Finding::Fail(format!("{} not found on PATH ({})", name, missing), Vec::new()),
+        None => Finding::Warn(format!("{} not found on PATH ({})", name, missing), Vec::new()),
+    }
+}
+
+/// Whether each movie root can be read, adding the files found to `files`
+fn check_roots(roots: &[PathBuf], files: &mut Vec<PathBuf>) -> Vec<Finding> {
+    roots.iter()
+        .map(|root| match walk_files(root, files) {
+            Ok(()) => Finding::Ok(format!("{} is readable", root.display())),
+            Err(e) => Finding::Fail(format!("cannot read {}: {}", root.display(), e), Vec::new()),
+        })
+        .collect()
+}
+
+/// How many videos there are, and which files are empty or will be ignored
+fn check_files(files: &[PathBuf]) -> Vec<Finding> {
+    let videos: Vec<&PathBuf> = files.iter().filter(|f| classify_file(f) == FileKind::Video).collect();
+    let mut findings = vec![Finding::Ok(format!("{} video file(s) found", videos.len()))];
+
+    let empty: Vec<&PathBuf> = videos.iter()
+        .copied()
+        .filter(|f| fs::metadata(f).map(|m| m.len() == 0).unwrap_or(false))
+        .collect();
+    if !empty.is_empty() {
+        findings.push(Finding::Fail(format!("{} zero-byte video file(s)", empty.len()), display_all(&empty)));
+    }
+
+    let unsupported: Vec<&PathBuf> = files.iter().filter(|f| classify_file(f) == FileKind::Unsupported).collect();
+    if !unsupported.is_empty() {
+        findings.push(Finding::Warn(format!("{} unsupported file(s) ignored", unsupported.len()), display_all(&unsupported)));
+    }
+    findings
+}
+
+/// Files the database knows about that are not on disk
+fn check_api_files(api_movies: &[JsonValue], roots: &[PathBuf]) -> Finding {
+    let missing: Vec<String> = api_movies.iter()
+        .filter_map(|mv| mv.get("file_paths").and_then(|v| v.as_array()))
+        .flatten()
+        .filter_map(|p| p.as_str())
+        .filter(|key| !key_exists(key, roots))
+        .map(|key| key.to_string())
+        .collect();
+    if missing.is_empty() {
+        Finding::Ok("every file referenced by the API exists".to_string())
+    } else {
+        Finding::Warn(format!("{} file(s) referenced by the API are missing", missing.len()), missing)
+    }
+}
+
+/// Movies on disk the API has no metadata for, matched the way the list matches them
+fn check_metadata(movies: &[MovieEntry], api_movies: &[JsonValue], roots: &[PathBuf]) -> Finding {
+    let index = api::KeyIndex::build(api_movies);
+    let unmatched: Vec<&PathBuf> = movies.iter()
+        .filter(|m| index.find(&api_keys(&m.path, roots)).is_none())
+        .map(|m| &m.path)
+        .collect();
+    let matched = movies.len() - unmatched.len();
+    if unmatched.is_empty() {
+        Finding::Ok(format!("metadata matched for all {} movie(s)", matched))
+    } else {
+        Finding::Warn(format!("metadata matched for {} of {} movie(s)", matched, movies.len()), display_all(&unmatched))
+    }
+}
+
+/// Check the environment and library without starting the TUI.
+/// Returns the process exit status: 0 when no problems were found.
+pub fn run(roots: &[PathBuf], player: Player) -> i32 {
+    let mut report = Report::default();
+    println!("moviePlayer doctor\n");
+
+    println!("Tools");
+    report.print(check_tool(&player.to_string(), tool_version(player.binary()), true, "needed for playback"));
+    report.print(check_tool("ffprobe", tool_version("ffprobe"), false, "file-level info will be missing"));
+    report.print(check_tool("yt-dlp", tool_version("yt-dlp"), false, "trailers open in the browser instead"));
+
+    println!("\nLibrary");
+    let mut files = Vec::new();
+    for finding in check_roots(roots, &mut files) {
+        report.print(finding);
+    }
+    for finding in check_files(&files) {
+        report.print(finding);
+    }
+
+    println!("\nAPI ({})", api::api_base());
+    let client = api::client();
+    if let Err(e) = api::health_check(&client) {
+        report.print(Finding::Fail(e, Vec::new()));
+    } else {
+        report.print(Finding::Ok("API is reachable".to_string()));
+        let url = format!("{}/movies/?limit=1000", api::api_base());
+        match client.get(&url).send().and_then(|r| r.json::<Vec<JsonValue>>()) {
+            Ok(api_movies) => {
+                report.print(check_api_files(&api_movies, roots));
+                match scan_movies(roots) {
+                    Ok(movies) => report.print(check_metadata(&movies, &api_movies, roots)),
+                    Err(e) => report.print(Finding::Fail(format!("failed to load library: {}", e), Vec::new())),
+                }
+            }
+            Err(e) => report.print(Finding::Fail(format!("cannot read {}: {}", url, e), Vec::new())),
+        }
+    }
+
+    println!("\n{} problem(s), {} warning(s)", report.problems, report.warnings);
+    if report.problems > 0 { 1 } else { 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::{TestDir, test_dir};
+
+    fn temp_library(name: &str) -> TestDir {
+        let root = test_dir(&format!("doctor-{}", name));
+        fs::create_dir_all(root.join("Action")).unwrap();
+        fs::write(root.join("Heat.mkv"), b"video").unwrap();
+        fs::write(root.join("Action/Ran.mp4"), b"").unwrap();
+        fs::write(root.join("notes.txt"), b"").unwrap();
+        root
+    }
+
+    #[test]
+    fn missing_tools_warn_unless_needed() {
+        assert_eq!(check_tool("mpv", Some("mpv 0.38".to_string()), true, "x"), Finding::Ok("mpv: mpv 0.38".to_string()));
+        assert!(matches!(check_tool("mpv", None, true, "needed for playback"), Finding::Fail(..)));
+        assert_eq!(
+            check_tool("yt-dlp", None, false, "trailers open in the browser instead"),
+            Finding::Warn("yt-dlp not found on PATH (trailers open in the browser instead)".to_string(), Vec::new())
+        );
+    }
+
+    #[test]
+    fn finds_empty_and_unsupported_files() {
+        let root = temp_library("files");
+        let mut files = Vec::new();
+        let roots = [root.to_path_buf(), root.join("missing")];
+        let findings = check_roots(&roots, &mut files);
+        assert!(matches!(findings[0], Finding::Ok(_)));
+        assert!(matches!(findings[1], Finding::Fail(..)));
+
+        assert_eq!(check_files(&files), vec![
+            Finding::Ok("2 video file(s) found".to_string()),
+            Finding::Fail("1 zero-byte video file(s)".to_string(), vec![root.join("Action/Ran.mp4").display().to_string()]),
+            Finding::Warn("1 unsupported file(s) ignored".to_string(), vec![root.join("notes.txt").display().to_string()]),
+        ]);
+    }
+
+    #[test]
+    fn compares_the_api_with_the_disk() {
+        let root = temp_library("api");
+        let roots = [root.to_path_buf()];
+        let api_movies: Vec<JsonValue> = serde_json::from_str(
+            r#"[{"file_key": "movies/Heat.mkv", "file_paths": ["movies/Heat.mkv", "movies/Gone.mkv"]}]"#
+        ).unwrap();
+        assert_eq!(
+            check_api_files(&api_movies, &roots),
+            Finding::Warn("1 file(s) referenced by the API are missing".to_string(), vec!["movies/Gone.mkv".to_string()])
+        );
+
+        let movies = scan_movies(&roots).unwrap();
+        assert_eq!(
+            check_metadata(&movies, &api_movies, &roots),
+            Finding::Warn("metadata matched for 1 of 2 movie(s)".to_string(), vec![root.join("Action/Ran.mp4").display().to_string()])
+        );
+    }
+}
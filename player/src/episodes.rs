@@ -0,0 +1,106 @@
+use std::fmt;
+use std::path::Path;
+
+/// Season and episode numbers read from a file name such as `Show.S01E02.mkv` or `Show 1x02.mkv`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EpisodeInfo {
+    // Show name before the episode marker, lowercased with separators collapsed
+    pub show: String,
+    pub season: u32,
+    pub episode: u32,
+}
+
+impl EpisodeInfo {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::parse(path.file_stem()?.to_str()?)
+    }
+
+    /// Find an `SxxEyy` (any case, one or more digits) or `NxMM` marker
+    pub fn parse(name: &str) -> Option<Self> {
+        let lower = name.to_lowercase();
+        let bytes = lower.as_bytes();
+        for start in 0..bytes.len() {
+            // Markers start with an ASCII letter or digit, which keeps the slices on char boundaries
+            if !bytes[start].is_ascii_alphanumeric() {
+                continue;
+            }
+            // The marker has to start a word: "Show.S01E02", not "ClassicS01E02"
+            if start > 0 && bytes[start - 1].is_ascii_alphanumeric() {
+                continue;
+            }
+            let marker = if bytes[start] == b's' {
+                parse_marker(&lower[start + 1..], b'e')
+            } else {
+                parse_marker(&lower[start..], b'x')
+            };
+            if let Some((season, episode)) = marker {
+                return Some(EpisodeInfo { show: show_name(&lower[..start]), season, episode });
+            }
+        }
+        None
+    }
+}
+
+/// `<digits><sep><digits>` at the start of `text`, not followed by another digit
+fn parse_marker(text: &str, separator: u8) -> Option<(u32, u32)> {
+    let season_len = text.bytes().take_while(|b| b.is_ascii_digit()).count();
+    if season_len == 0 || season_len > 2 || text.as_bytes().get(season_len) != Some(&separator) {
+        return None;
+    }
+    let rest = &text[season_len + 1..];
+    let episode_len = rest.bytes().take_while(|b| b.is_ascii_digit()).count();
+    // "1x2" is too loose to be an episode; require two digits after an x
+    if episode_len == 0 || episode_len > 3 || (separator == b'x' && episode_len < 2) {
+        return None;
+    }
+    Some((text[..season_len].parse().ok()?, rest[..episode_len].parse().ok()?))
+}
+
+fn show_name(prefix: &str) -> String {
+    prefix
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl fmt::Display for EpisodeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "S{:02}E{:02}", self.season, self.episode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn episode(name: &str) -> Option<(String, u32, u32)> {
+        EpisodeInfo::parse(name).map(|e| (e.show, e.season, e.episode))
+    }
+
+    #[test]
+    fn markers() {
+        assert_eq!(episode("Show.S01E02.1080p"), Some(("show".to_string(), 1, 2)));
+        assert_eq!(episode("The Show - s2e10 - Title"), Some(("the show".to_string(), 2, 10)));
+        assert_eq!(episode("The_Show_3x07"), Some(("the show".to_string(), 3, 7)));
+        assert_eq!(episode("S01E01"), Some((String::new(), 1, 1)));
+    }
+
+    #[test]
+    fn movies_are_not_episodes() {
+        assert_eq!(episode("Alien (1979)"), None);
+        assert_eq!(episode("Se7en"), None);
+        assert_eq!(episode("1920x1080 test"), None);
+        assert_eq!(episode("ClassicS01E02"), None);
+        assert_eq!(episode("Amélie 2001"), None);
+    }
+
+    #[test]
+    fn episodes_order_numerically() {
+        let e2 = EpisodeInfo::parse("Show.S01E02").unwrap();
+        let e10 = EpisodeInfo::parse("Show.S01E10").unwrap();
+        let s2 = EpisodeInfo::parse("Show.S02E01").unwrap();
+        assert!(e2 < e10 && e10 < s2);
+        assert_eq!(e10.to_string(), "S01E10");
+    }
+}
@@ -0,0 +1,131 @@
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+
+use serde::Serialize;
+
+/// One movie as written to an export file; unknown fields are left blank (CSV) or null (JSON)
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Row {
+    pub title: String,
+    pub year: Option<i32>,
+    pub genre: Option<String>,
+    pub director: Option<String>,
+    pub runtime: Option<String>,
+    pub rating: Option<f64>,
+    pub watch_count: Option<i32>,
+    pub path: String,
+    // Read from the file itself (ffprobe); left out of JSON when unknown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub codec: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_size: Option<String>,
+}
+
+const COLUMNS: &[&str] = &["title", "year", "genre", "director", "runtime", "rating", "watch_count", "path", "codec", "resolution", "file_size"];
+
+/// A CSV field, quoted when it holds a separator, a quote or a line break
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv(rows: &[Row]) -> String {
+    let text = |v: &Option<String>| v.as_deref().map(field).unwrap_or_default();
+    let number = |v: Option<String>| v.unwrap_or_default();
+    let mut out = COLUMNS.join(",") + "\n";
+    for row in rows {
+        let fields = [
+            field(&row.title),
+            number(row.year.map(|y| y.to_string())),
+            text(&row.genre),
+            text(&row.director),
+            text(&row.runtime),
+            number(row.rating.map(|r| r.to_string())),
+            number(row.watch_count.map(|c| c.to_string())),
+            field(&row.path),
+            text(&row.codec),
+            text(&row.resolution),
+            text(&row.file_size),
+        ];
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Why `write` did not write the file
+#[derive(Debug, PartialEq)]
+pub enum WriteError {
+    // The file is there and `overwrite` was not set
+    Exists,
+    Failed(String),
+}
+
+/// Write `rows` to `file`: JSON for a .json file, CSV otherwise. An existing file is only
+/// replaced with `overwrite`.
+pub fn write(file: &Path, rows: &[Row], overwrite: bool) -> Result<(), WriteError> {
+    let json = file.extension().is_some_and(|e| e.eq_ignore_ascii_case("json"));
+    let text = if json {
+        serde_json::to_string_pretty(rows).map_err(|e| WriteError::Failed(e.to_string()))?
+    } else {
+        csv(rows)
+    };
+    let failed = |e: std::io::Error| WriteError::Failed(format!("cannot write {}: {}", file.display(), e));
+    let mut out = match OpenOptions::new().write(true).create(true).truncate(true).create_new(!overwrite).open(file) {
+        Ok(out) => out,
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => return Err(WriteError::Exists),
+        Err(e) => return Err(failed(e)),
+    };
+    out.write_all(text.as_bytes()).map_err(failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::test_dir;
+
+    fn heat() -> Row {
+        Row {
+            title: "Heat".to_string(),
+            year: Some(1995),
+            genre: Some("Crime, Drama".to_string()),
+            rating: Some(8.3),
+            path: "/movies/Heat \"Director's\".mkv".to_string(),
+            codec: Some("h264".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn csv_quotes_what_needs_it() {
+        let text = csv(&[heat()]);
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some("title,year,genre,director,runtime,rating,watch_count,path,codec,resolution,file_size"));
+        assert_eq!(lines.next(), Some("Heat,1995,\"Crime, Drama\",,,8.3,,\"/movies/Heat \"\"Director's\"\".mkv\",h264,,"));
+    }
+
+    #[test]
+    fn existing_files_are_only_replaced_on_request() {
+        let dir = test_dir("export");
+        let file = dir.join("movies.csv");
+        std::fs::write(&file, "keep me").unwrap();
+        assert_eq!(write(&file, &[heat()], false), Err(WriteError::Exists));
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep me");
+        assert_eq!(write(&file, &[heat()], true), Ok(()));
+        assert!(std::fs::read_to_string(&file).unwrap().starts_with("title,year"));
+    }
+
+    #[test]
+    fn json_leaves_out_unknown_file_fields() {
+        let json = serde_json::to_value([heat()]).unwrap();
+        assert_eq!(json[0]["codec"], "h264");
+        assert!(json[0].get("resolution").is_none());
+        assert!(json[0]["director"].is_null());
+    }
+}
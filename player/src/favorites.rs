@@ -0,0 +1,91 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::persist;
+
+/// Starred movies, by path (~/.local/share/movieplayer/favorites.json)
+#[derive(Clone, Debug, Default)]
+pub struct Favorites {
+    paths: BTreeSet<PathBuf>,
+    // Where changes are saved; None keeps them in memory only
+    file: Option<PathBuf>,
+}
+
+fn favorites_path() -> Option<PathBuf> {
+    persist::data_dir().map(|dir| dir.join("favorites.json"))
+}
+
+impl Favorites {
+    pub fn load() -> Self {
+        let file = favorites_path();
+        Favorites {
+            paths: file.as_deref().and_then(persist::load_json).unwrap_or_default(),
+            file,
+        }
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.paths.contains(path)
+    }
+
+    /// Star or unstar `path` and save the list
+    pub fn toggle(&mut self, path: &Path) {
+        if !self.paths.remove(path) {
+            self.paths.insert(path.to_path_buf());
+        }
+        self.save();
+    }
+
+    /// Unstar a deleted file
+    pub fn remove(&mut self, path: &Path) {
+        if self.paths.remove(path) {
+            self.save();
+        }
+    }
+
+    /// Keep the star of a file renamed from `old` to `new`
+    pub fn rename(&mut self, old: &Path, new: PathBuf) {
+        if self.paths.remove(old) {
+            self.paths.insert(new);
+            self.save();
+        }
+    }
+
+    fn save(&self) {
+        if let Some(file) = &self.file
+            && let Err(e) = persist::save_json(file, &self.paths) {
+            log::warn!("Failed to save favorites to {}: {}", file.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggling_twice_unstars() {
+        let mut favorites = Favorites::default();
+        let path = Path::new("Heat.mkv");
+        assert!(!favorites.contains(path));
+        favorites.toggle(path);
+        assert!(favorites.contains(path));
+        assert!(!favorites.contains(Path::new("Ronin.mkv")));
+        favorites.toggle(path);
+        assert!(!favorites.contains(path));
+    }
+
+    #[test]
+    fn stars_follow_renames_and_deletes() {
+        let mut favorites = Favorites::default();
+        favorites.toggle(Path::new("heat.mkv"));
+        favorites.rename(Path::new("heat.mkv"), PathBuf::from("Heat (1995).mkv"));
+        assert!(!favorites.contains(Path::new("heat.mkv")));
+        assert!(favorites.contains(Path::new("Heat (1995).mkv")));
+        // Renaming an unstarred file does not star it
+        favorites.rename(Path::new("ronin.mkv"), PathBuf::from("Ronin (1998).mkv"));
+        assert!(!favorites.contains(Path::new("Ronin (1998).mkv")));
+        favorites.remove(Path::new("Heat (1995).mkv"));
+        assert!(!favorites.contains(Path::new("Heat (1995).mkv")));
+    }
+}
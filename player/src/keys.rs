@@ -0,0 +1,467 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+/// Something a key can do in the movie list
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    NavigateUp,
+    NavigateDown,
+    // Reorder the selected movie (Custom sort mode)
+    MoveUp,
+    MoveDown,
+    PageUp,
+    PageDown,
+    // Scroll a long plot in the info panel
+    ScrollInfoUp,
+    ScrollInfoDown,
+    // Jump to the top of the list, or to the "Random Movie" entry at the bottom
+    First,
+    Last,
+    Play,
+    // Play ignoring the position mpv saved last time
+    PlayFromStart,
+    ToggleAutoNext,
+    // Switch the idle auto-play (random movie after the timeout) on or off
+    ToggleIdleAutoplay,
+    // Change the volume the next title starts at
+    VolumeDown,
+    VolumeUp,
+    // Step the playback speed through 1x, 1.25x, 1.5x and 2x
+    CycleSpeed,
+    ToggleShuffle,
+    // Step through repeat off, the whole queue and one title
+    CycleRepeat,
+    // Subtitles on or off for the titles played next
+    ToggleSubtitles,
+    ToggleMiniMode,
+    // Switch between the configured colors and the high-contrast scheme
+    ToggleHighContrast,
+    TogglePaths,
+    // Move the divider between the list and the info panel
+    NarrowInfoPanel,
+    WidenInfoPanel,
+    ToggleContentFilter,
+    ToggleWatched,
+    ToggleFavorite,
+    // Give the selected movie a rating of your own
+    RateMovie,
+    // Open the selected movie's IMDb page in the browser
+    OpenImdb,
+    // Stream the selected movie's trailer (mpv with yt-dlp), or open it in the browser
+    PlayTrailer,
+    // Delete the selected movie's file from disk, after a y/n question (on Delete: "d" is the double feature)
+    DeleteMovie,
+    // Give the selected movie's file a new name
+    RenameMovie,
+    // Write the movies listed to a CSV or JSON file
+    ExportList,
+    // Add the selected movie to the play queue, and show the queue
+    Enqueue,
+    ShowQueue,
+    // Show only starred movies
+    FavoritesOnly,
+    FilterGenres,
+    // Step the minimum rating filter
+    RaiseMinRating,
+    LowerMinRating,
+    FilterYears,
+    // Collapse or expand the selected movie's group
+    ToggleGroup,
+    // Keep the selected movie's group at the top of the list
+    PinGroup,
+    // Clear the genre, rating and year filters
+    ResetFilters,
+    CycleSort,
+    // Group the list by folder, genre or decade
+    CycleGrouping,
+    // List without group headers
+    ToggleFlatList,
+    DoubleFeature,
+    // Library totals: runtime, genres, ratings and watch counts
+    ShowStats,
+    // Rescan the movie folders and fetch their metadata again
+    Refresh,
+    // Every action with its keys
+    ShowHelp,
+    OpenSearch,
+    // Start a title prefix: the characters typed next jump to the first title starting with
+    // them, even ones bound to actions
+    JumpToTitle,
+    // The `:` command line (sort, filter, queue and playlist commands, or any action by name)
+    OpenCommand,
+    Quit,
+}
+
+const DEFAULT_BINDINGS: &[(Action, &str)] = &[
+    (Action::NavigateUp, "Up"),
+    (Action::NavigateDown, "Down"),
+    (Action::MoveUp, "Shift+Up"),
+    (Action::MoveDown, "Shift+Down"),
+    (Action::PageUp, "PageUp"),
+    (Action::PageDown, "PageDown"),
+    (Action::ScrollInfoUp, "Alt+Up"),
+    (Action::ScrollInfoDown, "Alt+Down"),
+    (Action::First, "Home"),
+    (Action::Last, "End"),
+    (Action::Play, "Enter"),
+    (Action::PlayFromStart, "b"),
+    (Action::ToggleAutoNext, "n"),
+    (Action::ToggleIdleAutoplay, "t"),
+    (Action::VolumeDown, "["),
+    (Action::VolumeUp, "]"),
+    (Action::CycleSpeed, "S"),
+    (Action::ToggleShuffle, "s"),
+    (Action::CycleRepeat, "l"),
+    (Action::ToggleSubtitles, "c"),
+    (Action::ToggleMiniMode, "m"),
+    (Action::ToggleHighContrast, "H"),
+    (Action::TogglePaths, "p"),
+    (Action::NarrowInfoPanel, "<"),
+    (Action::WidenInfoPanel, ">"),
+    (Action::ToggleContentFilter, "k"),
+    (Action::ToggleWatched, "w"),
+    (Action::ToggleFavorite, "f"),
+    (Action::FavoritesOnly, "F"),
+    (Action::RateMovie, "R"),
+    (Action::OpenImdb, "I"),
+    (Action::PlayTrailer, "T"),
+    (Action::DeleteMovie, "Delete"),
+    (Action::RenameMovie, "N"),
+    (Action::ExportList, "E"),
+    (Action::Enqueue, "a"),
+    (Action::ShowQueue, "q"),
+    (Action::FilterGenres, "g"),
+    (Action::RaiseMinRating, "+"),
+    (Action::LowerMinRating, "-"),
+    (Action::FilterYears, "y"),
+    (Action::ToggleGroup, "z"),
+    (Action::PinGroup, "P"),
+    (Action::ResetFilters, "x"),
+    (Action::CycleSort, "o"),
+    (Action::CycleGrouping, "v"),
+    (Action::ToggleFlatList, "L"),
+    (Action::DoubleFeature, "d"),
+    (Action::ShowStats, "i"),
+    (Action::Refresh, "r"),
+    (Action::ShowHelp, "?"),
+    (Action::OpenSearch, "Space"),
+    (Action::JumpToTitle, "'"),
+    (Action::OpenCommand, ":"),
+    (Action::Quit, "Esc"),
+];
+
+/// Added by `vim_keys`, over the defaults using the same keys; g g jumps to the top
+const VIM_BINDINGS: &[(Action, &str)] = &[
+    (Action::NavigateDown, "j"),
+    (Action::NavigateUp, "k"),
+    (Action::Last, "G"),
+];
+
+/// Every action by category, as listed in the help overlay
+pub const HELP: &[(&str, &[(Action, &str)])] = &[
+    ("Navigation", &[
+        (Action::NavigateUp, "Move up"),
+        (Action::NavigateDown, "Move down"),
+        (Action::PageUp, "Page up"),
+        (Action::PageDown, "Page down"),
+        (Action::First, "Jump to the top"),
+        (Action::Last, "Jump to Random Movie"),
+        (Action::ScrollInfoUp, "Scroll the info panel up"),
+        (Action::ScrollInfoDown, "Scroll the info panel down"),
+        (Action::ToggleGroup, "Collapse or expand a group"),
+        (Action::PinGroup, "Pin a group to the top"),
+        (Action::OpenSearch, "Search"),
+        (Action::JumpToTitle, "Jump to a title by typing its start"),
+        (Action::OpenCommand, "Command line, e.g. :sort year"),
+    ]),
+    ("Playback", &[
+        (Action::Play, "Play"),
+        (Action::PlayFromStart, "Play from the beginning"),
+        (Action::DoubleFeature, "Surprise double feature"),
+        (Action::ToggleAutoNext, "Autoplay next on/off"),
+        (Action::ToggleIdleAutoplay, "Idle auto-play on/off"),
+        (Action::ToggleShuffle, "Shuffle on/off"),
+        (Action::CycleRepeat, "Repeat: off, all, one"),
+        (Action::ToggleSubtitles, "Subtitles on/off"),
+        (Action::VolumeDown, "Volume down"),
+        (Action::VolumeUp, "Volume up"),
+        (Action::CycleSpeed, "Playback speed"),
+    ]),
+    ("Queue", &[
+        (Action::Enqueue, "Add to the queue"),
+        (Action::ShowQueue, "Show the queue and playlists"),
+    ]),
+    ("Filter and sort", &[
+        (Action::FilterGenres, "Pick genres"),
+        (Action::RaiseMinRating, "Raise the minimum rating"),
+        (Action::LowerMinRating, "Lower the minimum rating"),
+        (Action::FilterYears, "Years or decade"),
+        (Action::FavoritesOnly, "Favorites only"),
+        (Action::ToggleContentFilter, "Content filter on/off"),
+        (Action::ResetFilters, "Clear the filters"),
+        (Action::CycleSort, "Change the sort order"),
+        (Action::CycleGrouping, "Group by folder, genre or decade"),
+        (Action::ToggleFlatList, "Flat list without group headers"),
+        (Action::MoveUp, "Move up in the custom order"),
+        (Action::MoveDown, "Move down in the custom order"),
+    ]),
+    ("Library", &[
+        (Action::ToggleWatched, "Mark watched/unwatched"),
+        (Action::ToggleFavorite, "Star as a favorite"),
+        (Action::RateMovie, "Rate the movie"),
+        (Action::OpenImdb, "Open the IMDb page"),
+        (Action::PlayTrailer, "Watch the trailer"),
+        (Action::DeleteMovie, "Delete the file from disk"),
+        (Action::RenameMovie, "Rename the file"),
+        (Action::ShowStats, "Library stats"),
+        (Action::ExportList, "Export the list to CSV or JSON"),
+        (Action::Refresh, "Rescan the folders"),
+    ]),
+    ("Screen", &[
+        (Action::ToggleMiniMode, "Mini mode on/off"),
+        (Action::ToggleHighContrast, "High contrast on/off"),
+        (Action::TogglePaths, "Show paths"),
+        (Action::NarrowInfoPanel, "Narrow the info panel"),
+        (Action::WidenInfoPanel, "Widen the info panel"),
+        (Action::ShowHelp, "This help"),
+        (Action::Quit, "Back out: search, filters, then quit"),
+    ]),
+];
+
+/// A key with the modifiers that have to be held
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Parse "j", "Space", "Enter", "Shift+Up", "Ctrl+f" and the like
+    fn parse(spec: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut parts: Vec<&str> = spec.split('+').collect();
+        // A lone "+" is the plus key itself
+        let key = if spec.ends_with("++") || spec == "+" {
+            parts.truncate(parts.len().saturating_sub(2));
+            "+"
+        } else {
+            parts.pop().unwrap_or("")
+        };
+        for part in parts {
+            modifiers |= match part.to_lowercase().as_str() {
+                "shift" => KeyModifiers::SHIFT,
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                _ => return Err(format!("unknown modifier \"{}\" in \"{}\"", part, spec)),
+            };
+        }
+        let code = match key.to_lowercase().as_str() {
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            _ if key.chars().count() == 1 => KeyCode::Char(key.chars().next().unwrap_or(' ')),
+            _ => return Err(format!("unknown key \"{}\"", spec)),
+        };
+        Ok(KeyBinding { code, modifiers }.normalized())
+    }
+
+    /// Characters carry their own case, so Shift is not part of a character binding
+    fn normalized(mut self) -> Self {
+        if let KeyCode::Char(_) = self.code {
+            self.modifiers.remove(KeyModifiers::SHIFT);
+        }
+        self
+    }
+
+    fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            other => format!("{:?}", other),
+        };
+        let mut label = String::new();
+        for (modifier, name) in [(KeyModifiers::CONTROL, "Ctrl+"), (KeyModifiers::ALT, "Alt+"), (KeyModifiers::SHIFT, "Shift+")] {
+            if self.modifiers.contains(modifier) {
+                label.push_str(name);
+            }
+        }
+        label + &key
+    }
+}
+
+/// Which action each key triggers in the list
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(KeyBinding, Action)>,
+    // Vim navigation: j/k/G bound, and an unbound g starting the g g sequence
+    vim: bool,
+}
+
+fn parse_bindings(specs: &[(Action, &str)]) -> Vec<(KeyBinding, Action)> {
+    specs.iter()
+        .map(|&(action, spec)| (KeyBinding::parse(spec).expect("valid default binding"), action))
+        .collect()
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap { bindings: parse_bindings(DEFAULT_BINDINGS), vim: false }
+    }
+}
+
+impl Keymap {
+    /// Apply the `[keys]` table from the config file on top of the defaults, with the vim keys
+    /// first when `vim` is set. Listing an action replaces its default keys, and a configured
+    /// key takes over from any default using it. Two configured actions sharing a key is an error.
+    pub fn from_config(keys: &HashMap<Action, Vec<String>>, vim: bool) -> Result<Self, String> {
+        let mut configured: Vec<(KeyBinding, Action)> = Vec::new();
+        // Sorted so that error messages are stable
+        let mut entries: Vec<(&Action, &Vec<String>)> = keys.iter().collect();
+        entries.sort_by_key(|(action, _)| format!("{:?}", action));
+        for (&action, specs) in entries {
+            for spec in specs {
+                let binding = KeyBinding::parse(spec)?;
+                if let Some((_, other)) = configured.iter().find(|(b, a)| *b == binding && *a != action) {
+                    return Err(format!("key \"{}\" is bound to both {:?} and {:?}", spec, other, action));
+                }
+                configured.push((binding, action));
+            }
+        }
+
+        let mut keymap = Keymap::default();
+        if vim {
+            let vim_bindings = parse_bindings(VIM_BINDINGS);
+            // g is kept free for the g g sequence
+            let g = KeyBinding { code: KeyCode::Char('g'), modifiers: KeyModifiers::NONE };
+            keymap.bindings.retain(|(binding, _)| *binding != g && !vim_bindings.iter().any(|(b, _)| b == binding));
+            keymap.bindings.extend(vim_bindings);
+            keymap.vim = true;
+        }
+        keymap.bindings.retain(|(binding, action)| {
+            !keys.contains_key(action) && !configured.iter().any(|(b, _)| b == binding)
+        });
+        keymap.bindings.extend(configured);
+        Ok(keymap)
+    }
+
+    /// Whether `key` is a g that starts (or completes) the vim g g sequence
+    pub fn is_vim_prefix(&self, key: &KeyEvent) -> bool {
+        self.vim && key.code == KeyCode::Char('g') && key.modifiers == KeyModifiers::NONE && self.action(key).is_none()
+    }
+
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        let pressed = KeyBinding { code: key.code, modifiers: key.modifiers }.normalized();
+        self.bindings.iter().find(|(binding, _)| *binding == pressed).map(|&(_, action)| action)
+    }
+
+    /// Every key bound to `action`, for the help overlay
+    pub fn labels(&self, action: Action) -> Vec<String> {
+        self.bindings.iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(binding, _)| binding.label())
+            .collect()
+    }
+
+    /// The first key bound to `action`, for on-screen hints
+    pub fn label(&self, action: Action) -> String {
+        self.bindings.iter()
+            .find(|(_, a)| *a == action)
+            .map(|(binding, _)| binding.label())
+            .unwrap_or_else(|| "-".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn defaults_match_the_builtin_keys() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.action(&press(KeyCode::Up, KeyModifiers::NONE)), Some(Action::NavigateUp));
+        assert_eq!(keymap.action(&press(KeyCode::Up, KeyModifiers::SHIFT)), Some(Action::MoveUp));
+        assert_eq!(keymap.action(&press(KeyCode::Char(' '), KeyModifiers::NONE)), Some(Action::OpenSearch));
+        assert_eq!(keymap.action(&press(KeyCode::Char('j'), KeyModifiers::NONE)), None);
+        assert_eq!(keymap.action(&press(KeyCode::Char('\''), KeyModifiers::NONE)), Some(Action::JumpToTitle));
+    }
+
+    #[test]
+    fn vim_bindings_take_over_conflicting_defaults() {
+        let keys = HashMap::from([
+            (Action::NavigateUp, vec!["Up".to_string(), "k".to_string()]),
+            (Action::NavigateDown, vec!["Down".to_string(), "j".to_string()]),
+            (Action::OpenSearch, vec!["/".to_string()]),
+        ]);
+        let keymap = Keymap::from_config(&keys, false).unwrap();
+        assert_eq!(keymap.action(&press(KeyCode::Char('k'), KeyModifiers::NONE)), Some(Action::NavigateUp));
+        assert_eq!(keymap.action(&press(KeyCode::Char('j'), KeyModifiers::NONE)), Some(Action::NavigateDown));
+        assert_eq!(keymap.action(&press(KeyCode::Char('/'), KeyModifiers::NONE)), Some(Action::OpenSearch));
+        // Space no longer opens search, and the content filter lost its key
+        assert_eq!(keymap.action(&press(KeyCode::Char(' '), KeyModifiers::NONE)), None);
+        assert_eq!(keymap.label(Action::ToggleContentFilter), "-");
+    }
+
+    #[test]
+    fn vim_keys_add_jk_and_free_g() {
+        let keymap = Keymap::from_config(&HashMap::new(), true).unwrap();
+        assert_eq!(keymap.action(&press(KeyCode::Char('j'), KeyModifiers::NONE)), Some(Action::NavigateDown));
+        assert_eq!(keymap.action(&press(KeyCode::Char('k'), KeyModifiers::NONE)), Some(Action::NavigateUp));
+        assert_eq!(keymap.action(&press(KeyCode::Char('G'), KeyModifiers::SHIFT)), Some(Action::Last));
+        assert!(keymap.is_vim_prefix(&press(KeyCode::Char('g'), KeyModifiers::NONE)));
+        assert_eq!(keymap.label(Action::NavigateDown), "↓");
+        // A configured g wins over the sequence
+        let keys = HashMap::from([(Action::FilterGenres, vec!["g".to_string()])]);
+        let keymap = Keymap::from_config(&keys, true).unwrap();
+        assert!(!keymap.is_vim_prefix(&press(KeyCode::Char('g'), KeyModifiers::NONE)));
+        assert!(!Keymap::default().is_vim_prefix(&press(KeyCode::Char('g'), KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn conflicting_bindings_are_rejected() {
+        let keys = HashMap::from([
+            (Action::Play, vec!["x".to_string()]),
+            (Action::Quit, vec!["x".to_string()]),
+        ]);
+        assert!(Keymap::from_config(&keys, false).is_err());
+        let keys = HashMap::from([(Action::Play, vec!["Hyper+x".to_string()])]);
+        assert!(Keymap::from_config(&keys, false).is_err());
+    }
+
+    #[test]
+    fn help_lists_every_action_once() {
+        for &(action, _) in DEFAULT_BINDINGS {
+            let listed = HELP.iter().flat_map(|(_, entries)| entries.iter()).filter(|(a, _)| *a == action).count();
+            assert_eq!(listed, 1, "{:?}", action);
+        }
+    }
+
+    #[test]
+    fn parses_modifiers_and_names() {
+        assert_eq!(KeyBinding::parse("Ctrl+f").unwrap(), KeyBinding { code: KeyCode::Char('f'), modifiers: KeyModifiers::CONTROL });
+        assert_eq!(KeyBinding::parse("Shift+J").unwrap(), KeyBinding { code: KeyCode::Char('J'), modifiers: KeyModifiers::NONE });
+        assert_eq!(KeyBinding::parse("escape").unwrap().code, KeyCode::Esc);
+        assert_eq!(KeyBinding::parse("Del").unwrap().code, KeyCode::Delete);
+        assert_eq!(KeyBinding::parse("+").unwrap().code, KeyCode::Char('+'));
+        assert!(KeyBinding::parse("F13x").is_err());
+    }
+}
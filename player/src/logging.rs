@@ -0,0 +1,65 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::persist;
+
+// A log grown past this is started afresh at launch
+const MAX_LOG_SIZE: u64 = 1 << 20;
+
+/// Appends every record to ~/.cache/movieplayer/log, where it cannot garble the TUI
+struct FileLogger {
+    file: Mutex<File>,
+    level: LevelFilter,
+}
+
+pub fn log_path() -> Option<PathBuf> {
+    persist::cache_dir().map(|dir| dir.join("log"))
+}
+
+/// Send the `log` macros to the log file: info and up, or everything with `verbose`
+pub fn init(verbose: bool) {
+    let Some(path) = log_path() else {
+        return;
+    };
+    let too_big = fs::metadata(&path).is_ok_and(|m| m.len() > MAX_LOG_SIZE);
+    let file = path.parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| OpenOptions::new().create(true).append(!too_big).write(true).truncate(too_big).open(&path));
+    let file = match file {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("Cannot open the log file {}: {}", path.display(), e);
+            return;
+        }
+    };
+    let level = if verbose { LevelFilter::Debug } else { LevelFilter::Info };
+    if log::set_logger(Box::leak(Box::new(FileLogger { file: Mutex::new(file), level }))).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!("{} {:<5} {}\n", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), record.level(), record.args());
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
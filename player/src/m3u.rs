@@ -0,0 +1,102 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Expand a leading `~/` in a path typed into the app
+pub fn expand_home(path: &str) -> PathBuf {
+    let path = path.trim();
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Undo the %XX escapes of a file:// URI ("My%20Movie.mkv"); a stray '%' is kept as it is
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// The file entries of an m3u playlist; relative entries are relative to `base`
+fn parse(text: &str, base: &Path) -> Vec<PathBuf> {
+    text.lines()
+        .map(|line| line.trim().trim_start_matches('\u{feff}'))
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.strip_prefix("file://") {
+            Some(uri) => base.join(percent_decode(uri)),
+            None => base.join(line),
+        })
+        .collect()
+}
+
+/// An extended m3u listing `paths`, one absolute path per entry
+fn render(paths: &[PathBuf]) -> String {
+    let mut out = String::from("#EXTM3U\n");
+    for path in paths {
+        let title = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        out.push_str(&format!("#EXTINF:-1,{}\n{}\n", title, path.display()));
+    }
+    out
+}
+
+pub fn import(file: &Path) -> Result<Vec<PathBuf>, String> {
+    let text = fs::read_to_string(file).map_err(|e| format!("cannot read {}: {}", file.display(), e))?;
+    Ok(parse(&text, file.parent().unwrap_or(Path::new("."))))
+}
+
+pub fn export(file: &Path, paths: &[PathBuf]) -> Result<(), String> {
+    let absolute: Vec<PathBuf> = paths.iter().map(|p| std::path::absolute(p).unwrap_or_else(|_| p.clone())).collect();
+    fs::write(file, render(&absolute)).map_err(|e| format!("cannot write {}: {}", file.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_entries_and_skips_directives() {
+        let text = "#EXTM3U\n#EXTINF:7200,Alien\n/movies/Alien.mkv\n\nsub/Heat.mp4\r\nfile:///movies/Ran.avi\n";
+        assert_eq!(parse(text, Path::new("/lists")), vec![
+            PathBuf::from("/movies/Alien.mkv"),
+            PathBuf::from("/lists/sub/Heat.mp4"),
+            PathBuf::from("/movies/Ran.avi"),
+        ]);
+    }
+
+    #[test]
+    fn file_uris_are_percent_decoded() {
+        let text = "file:///movies/My%20Movie.mkv\nfile:///movies/Am%C3%A9lie%2B.mkv\n/movies/100%25.mkv\nfile:///movies/50%off.mkv\n";
+        assert_eq!(parse(text, Path::new("/lists")), vec![
+            PathBuf::from("/movies/My Movie.mkv"),
+            PathBuf::from("/movies/Amélie+.mkv"),
+            PathBuf::from("/movies/100%25.mkv"),
+            PathBuf::from("/movies/50%off.mkv"),
+        ]);
+    }
+
+    #[test]
+    fn render_round_trips() {
+        let paths = vec![PathBuf::from("/movies/Alien (1979).mkv"), PathBuf::from("/movies/Heat.mp4")];
+        let text = render(&paths);
+        assert!(text.starts_with("#EXTM3U\n#EXTINF:-1,Alien (1979)\n"));
+        assert_eq!(parse(&text, Path::new("/elsewhere")), paths);
+    }
+}
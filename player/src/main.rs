@@ -1,30 +1,149 @@
+use std::borrow::Cow;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 
-use reqwest::blocking::Client as HttpClient;
+use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use std::time::{Instant, Duration};
+use std::time::{Instant, Duration, SystemTime};
 use ratatui::{DefaultTerminal, Frame, 
-            widgets::{Block, Borders, List, ListItem, Paragraph, Wrap, Clear}, 
+            widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap, Clear}, 
             layout::{Layout, Constraint, Flex, Rect, Position}, 
-            style::{Style, Color, Modifier}, 
+            style::{Style, Modifier}, 
             text::{Line, Span}};
-use crossterm::event::{Event, KeyCode, KeyEventKind, poll};
-use rand::Rng;
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind, poll};
 use rand::seq::SliceRandom;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex, OnceLock};
+use chrono::{DateTime, Local};
+use ratatui_image::picker::Picker;
+
+mod api;
+mod backend;
+mod chapters;
+mod command;
+mod config;
+mod content_rating;
+mod delete;
+mod doctor;
+mod episodes;
+mod export;
+mod favorites;
+mod keys;
+mod logging;
+mod m3u;
+mod metadata_cache;
+mod mpv;
+#[cfg(unix)]
+mod mpv_ipc;
+mod now_playing;
+mod persist;
+mod playlists;
+mod posters;
+mod probe_cache;
+mod ratings;
+mod rename;
+mod search;
+mod sort;
+mod stats;
+mod subtitles;
+#[cfg(test)]
+mod test_dir;
+mod theme;
+mod trailer;
+mod ui_state;
+mod watched;
+mod watcher;
+mod years;
+
+use backend::{Player, PlayerExit};
+use chapters::ChapterSkip;
+use config::Config;
+use keys::{Action, Keymap};
+use content_rating::ContentFilter;
+use episodes::EpisodeInfo;
+use favorites::Favorites;
+use mpv::StartSpec;
+use posters::Posters;
+use ratings::UserRatings;
+use search::SearchScope;
+use sort::{MovieComparator, SortMode};
+use stats::LibraryStats;
+use theme::{Theme, ThemePreset};
+use ui_state::UiState;
+use watched::WatchedMarks;
+use years::YearRange;
 
 static AUTO_PLAY_NEXT: AtomicBool = AtomicBool::new(true);
 static SHUFFLE_QUEUE: AtomicBool = AtomicBool::new(false);
+static MINI_MODE: AtomicBool = AtomicBool::new(false);
+static IDLE_AUTOPLAY: AtomicBool = AtomicBool::new(true);
+static SUBTITLES: AtomicBool = AtomicBool::new(false);
+static HIGH_CONTRAST: AtomicBool = AtomicBool::new(false);
+
+
+// Movies root used when neither --movies-dir, MOVIES_DIR nor the config file gives one
+const DEFAULT_MOVIES_DIR: &str = "../movies";
+const DEFAULT_AUTOPLAY_TIMEOUT: u64 = 30;
+// ffprobe processes running at once while prewarming the info cache
+const PREWARM_THREADS: usize = 4;
+
+// File extensions listed as movies, unless the config file replaces them
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mkv", "avi", "mov", "webm", "m4v", "ts", "m2ts", "mpg", "mpeg", "flv", "wmv", "ogv"];
+const SUBTITLE_EXTENSIONS: &[&str] = &["srt", "ass", "ssa", "sub", "idx", "vtt"];
+
+// Below either dimension the layout falls back to mini mode automatically
+const MINI_MODE_MAX_WIDTH: u16 = 60;
+const MINI_MODE_MAX_HEIGHT: u16 = 12;
+
+// Share of the width the info panel gets next to the list, by default and at most/least,
+// and how far each </> press moves the divider
+const DEFAULT_INFO_PERCENT: u16 = 30;
+const MIN_INFO_PERCENT: u16 = 15;
+const MAX_INFO_PERCENT: u16 = 60;
+const INFO_PERCENT_STEP: u16 = 5;
+
+// Rows of the info panel a poster takes; shorter panels show the text only
+const POSTER_HEIGHT: u16 = 14;
 
+// How long a status message such as "Refreshed" stays in the taskbar
+const STATUS_MESSAGE_TIME: Duration = Duration::from_secs(4);
 
-const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mkv", "avi", "mov", "webm", "m4v"];
+// Keystrokes further apart than this start a new type-ahead prefix
+const TYPE_AHEAD_RESET: Duration = Duration::from_millis(800);
 
-#[derive(Clone, Debug, Default)]
+// The search re-runs once typing pauses this long, not on every keystroke
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(120);
+
+// A count typed before a movement ("5↓") is dropped when no movement follows within this time
+const COUNT_RESET: Duration = Duration::from_secs(2);
+// Longest count, in digits
+const MAX_COUNT_DIGITS: usize = 3;
+
+// How far each +/- press moves the minimum rating filter
+const RATING_STEP: f64 = 0.5;
+// Volume range the players accept, and how far each [ / ] press moves it
+const MAX_VOLUME: u8 = 130;
+const VOLUME_STEP: u8 = 5;
+// Playback speeds the speed key steps through, and the range a configured speed is kept in
+const SPEEDS: &[f64] = &[1.0, 1.25, 1.5, 2.0];
+const MIN_SPEED: f64 = 0.25;
+const MAX_SPEED: f64 = 4.0;
+// Random pick weight of a movie whose watch count is unknown, as likely as one watched once
+const UNKNOWN_WATCH_WEIGHT: f64 = 0.5;
+
+// Offered in the export popup
+const DEFAULT_EXPORT_FILE: &str = "~/movies.csv";
+
+// Header of the virtual group of started but unfinished movies at the top of the list
+const CONTINUE_WATCHING_GROUP: &str = "Continue Watching";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 struct MovieInfo {
     // Fields pulled from the movies DB
     title: Option<String>,
@@ -35,24 +154,173 @@ struct MovieInfo {
     runtime: Option<String>,
     rating: Option<f64>,
     watch_count: Option<i32>,
-    _imdb_id: Option<String>,
+    // Age/content rating such as "PG-13" or "16"
+    content_rating: Option<String>,
+    // Poster image from the movies DB, drawn in terminals with graphics support
+    poster_url: Option<String>,
+    // Trailer link (e.g. YouTube), when the movies DB has one; otherwise trailers are searched for
+    trailer_url: Option<String>,
+    // Identifiers the API indexes the movie by, used to count watches (and the IMDb id for its page)
+    #[serde(alias = "_imdb_id")]
+    imdb_id: Option<String>,
+    file_key: Option<String>,
 
     // Fallback file-level metadata (kept for compatibility)
     file_size: Option<String>,
+    // The size in bytes behind file_size, for the size sort
+    file_bytes: Option<u64>,
     codec: Option<String>,
     resolution: Option<String>,
+    // Chapter markers from ffprobe, used for intro/recap skipping
+    chapters: Vec<chapters::Chapter>,
+    // Subtitle files next to the video, looked up on every scan rather than cached
+    #[serde(skip)]
+    subtitles: Vec<PathBuf>,
+
+    // Other local files matched to the same API entry
+    duplicates: Vec<PathBuf>,
 }
 
 #[derive(Clone)]
 struct MovieEntry {
     path: PathBuf,
     group_name: String,
+    // Alternate files for the same film when duplicates are collapsed into one entry
+    versions: Vec<PathBuf>,
+}
+
+impl MovieInfo {
+    fn is_watched(&self) -> bool {
+        self.watch_count.unwrap_or(0) > 0
+    }
+
+    /// The individual genres of a comma separated genre field like "Crime, Drama"
+    fn genres(&self) -> impl Iterator<Item = &str> {
+        self.genre.as_deref().unwrap_or("").split(',').map(|s| s.trim()).filter(|s| !s.is_empty())
+    }
+
+    /// What two movies have in common (same director or a shared genre), for pairing them up
+    fn related_by(&self, other: &MovieInfo) -> Option<String> {
+        if let (Some(a), Some(b)) = (&self.director, &other.director)
+            && a.eq_ignore_ascii_case(b) {
+            return Some(format!("Director: {}", a));
+        }
+        let shared = self.genres().find(|g| other.genres().any(|o| o.eq_ignore_ascii_case(g)))?;
+        Some(format!("Genre: {}", shared))
+    }
+}
+
+/// How already-watched movies look in the list (WATCHED_STYLE)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum WatchedStyle {
+    // A ✓ after the name
+    #[default]
+    Marker,
+    // Dark gray with a ✓, the closest terminals get to struck-through
+    Dim,
+    // Left out of the list entirely
+    Hidden,
+}
+
+impl WatchedStyle {
+    fn from_env() -> Self {
+        match env::var("WATCHED_STYLE").unwrap_or_default().to_lowercase().as_str() {
+            "dim" => WatchedStyle::Dim,
+            "hidden" => WatchedStyle::Hidden,
+            _ => WatchedStyle::Marker,
+        }
+    }
+}
+
+/// What to do when one API entry matches several local files (e.g. different qualities)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DuplicatePolicy {
+    // Every file stays in the list and shares the metadata
+    Share,
+    // Only the highest-quality file is listed
+    Best,
+    // One list entry, with the other files offered as versions
+    Collapse,
+}
+
+impl DuplicatePolicy {
+    fn from_env() -> Self {
+        match env::var("DUPLICATE_MATCHES").unwrap_or_default().to_lowercase().as_str() {
+            "best" => DuplicatePolicy::Best,
+            "collapse" => DuplicatePolicy::Collapse,
+            _ => DuplicatePolicy::Share,
+        }
+    }
 }
 
+/// What the keyboard is currently driving; every key is dispatched on this
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum InputMode {
     Normal,
-    #[allow(dead_code)]
-    Editing,
+    // Typing into the search popup
+    Search,
+    // Typing a `:` command
+    Command,
+    // Picking a version of a collapsed entry, with the highlighted row
+    VersionMenu(usize),
+    // Asking whether to switch off the kids-mode filter
+    ConfirmFilterOff,
+    // Asking whether to quit (confirm_exit)
+    ConfirmExit,
+    // Asking whether to delete the selected movie's file
+    ConfirmDelete,
+    // Confirming the two picks of a surprise double feature
+    DoubleFeature(usize, usize),
+    // Genre picker, with the highlighted row
+    GenrePicker(usize),
+    // Typing a year range or decade
+    YearFilter,
+    // Typing a rating for the selected movie
+    Rating,
+    // Editing the selected movie's file name
+    RenameFile,
+    // Typing the file to export the list to
+    ExportList,
+    // Play queue popup, with the highlighted row
+    Queue(usize),
+    // Typing a name to save the queue under
+    PlaylistName,
+    // Typing the path of an m3u file to load into the queue, or to write it to
+    M3uImport,
+    M3uExport,
+    // Saved playlist picker, with the highlighted row
+    Playlists(usize),
+    // Full-screen library statistics
+    Stats,
+    // Full-screen list of every key
+    Help,
+}
+
+impl InputMode {
+    fn label(self) -> &'static str {
+        match self {
+            InputMode::Normal => "NORMAL",
+            InputMode::Search => "SEARCH",
+            InputMode::Command => "COMMAND",
+            InputMode::VersionMenu(_) => "VERSION",
+            InputMode::ConfirmFilterOff => "CONFIRM",
+            InputMode::ConfirmExit => "QUIT?",
+            InputMode::ConfirmDelete => "DELETE?",
+            InputMode::DoubleFeature(..) => "DOUBLE FEATURE",
+            InputMode::GenrePicker(_) => "GENRES",
+            InputMode::YearFilter => "YEARS",
+            InputMode::Rating => "RATING",
+            InputMode::RenameFile => "RENAME",
+            InputMode::ExportList => "EXPORT",
+            InputMode::Queue(_) => "QUEUE",
+            InputMode::PlaylistName => "SAVE PLAYLIST",
+            InputMode::M3uImport => "IMPORT M3U",
+            InputMode::M3uExport => "EXPORT M3U",
+            InputMode::Playlists(_) => "PLAYLISTS",
+            InputMode::Stats => "STATS",
+            InputMode::Help => "HELP",
+        }
+    }
 }
 
 struct AppState {
@@ -60,11 +328,284 @@ struct AppState {
     selected: usize,
     movie_info_cache: HashMap<PathBuf, MovieInfo>,
     scroll_offset: usize,
-    show_popup: bool,
     user_input: String,
-    #[allow(dead_code)]
     input_mode: InputMode,
+    // Modes the current one was opened from, innermost last; Esc returns to them in turn
+    mode_stack: Vec<InputMode>,
     character_index: usize,
+    // Remaining queue from the last playback, used for the "Up next" hint
+    play_queue: Option<PlayQueue>,
+    // Show paths relative to the library root instead of bare file names
+    show_full_paths: bool,
+    // When set, Esc has to be pressed twice within this window to exit
+    exit_esc_window: Option<Duration>,
+    last_esc: Option<Instant>,
+    // Ask y/n before quitting instead
+    confirm_exit: bool,
+    // Parental filter hiding movies above a content rating
+    content_filter: ContentFilter,
+    sort_mode: SortMode,
+    custom_order: Vec<PathBuf>,
+    watched_style: WatchedStyle,
+    // Indices into `movies` in display order for the active sort mode
+    order: Vec<usize>,
+    // Search results (best match first) replacing `order` while a query is active
+    search_hits: Option<Vec<usize>>,
+    // The query behind `search_hits`, kept for highlighting once the input is reused
+    search_query: String,
+    // Typed into the search popup since the results were last updated
+    search_pending: bool,
+    // Regex mode of the search popup (Ctrl+R), the pattern the results came from, and why the
+    // input does not compile
+    search_regex: bool,
+    search_pattern: Option<regex::Regex>,
+    search_error: Option<String>,
+    search_scope: SearchScope,
+    metadata_cached_at: Option<DateTime<Local>>,
+    // The last metadata fetch gave up waiting for the API
+    api_timed_out: bool,
+    // Metadata is still being fetched in the background
+    metadata_loading: bool,
+    movie_roots: Vec<PathBuf>,
+    keymap: Keymap,
+    // Whether mpv resumes titles, so saved positions are worth showing
+    resume_enabled: bool,
+    // Started but unfinished movies, most recently played first, listed above the groups
+    in_progress: Vec<usize>,
+    // When mpv saved a position for each movie, read with the metadata
+    resume_times: HashMap<PathBuf, SystemTime>,
+    watched_marks: WatchedMarks,
+    ratings: UserRatings,
+    // Genres picked in the genre filter; a movie matching any of them is shown
+    genre_filter: Vec<String>,
+    // Lowest rating shown, adjusted with +/-
+    min_rating: Option<f64>,
+    // Whether movies without a rating stay visible under min_rating
+    include_unrated: bool,
+    // Weight the random pick toward movies watched fewer times
+    favor_unwatched: bool,
+    // Pick only unwatched movies at random while any are left
+    random_unwatched_only: bool,
+    // Delete subtitle and AppleDouble files with a movie, and tell the API about the deletion
+    delete_sidecars: bool,
+    delete_from_api: bool,
+    year_range: Option<YearRange>,
+    // Why the last year range typed into the popup was rejected
+    year_input_error: Option<String>,
+    // Why the last rating typed into the popup was rejected
+    rating_input_error: Option<String>,
+    // Why the last `:` command could not be run
+    command_error: Option<String>,
+    // Why the file could not be given the name typed into the rename popup
+    rename_error: Option<String>,
+    // Why the list could not be written to the file typed into the export popup
+    export_error: Option<String>,
+    // The file typed into the export popup exists and the next Enter replaces it
+    export_overwrite: bool,
+    // Groups folded down to their header
+    collapsed: HashSet<String>,
+    // Title prefix typed in quick succession, and when it was last extended
+    type_ahead: String,
+    type_ahead_at: Option<Instant>,
+    // When a first g of the vim g g sequence was pressed
+    vim_g_at: Option<Instant>,
+    // Digits typed before a movement to repeat it, and when the last one was typed
+    count: String,
+    count_at: Option<Instant>,
+    // Where the list and info panel were drawn, and the movie on each visible list row
+    // (None for headers, movies.len() for "Random Movie"), for mouse clicks
+    list_area: Rect,
+    info_area: Option<Rect>,
+    list_rows: Vec<Option<usize>>,
+    // Rows of the list that fit on screen, as of the last render
+    page_size: usize,
+    favorites: Favorites,
+    // Show only starred movies
+    favorites_only: bool,
+    // No group headers: the name sort lists every movie alphabetically
+    flat_list: bool,
+    // Groups listed above all others, in pin order
+    pinned_groups: Vec<String>,
+    // Movies lined up by hand; Play plays these instead of the library
+    user_queue: Vec<MovieEntry>,
+    // Result of the last playlist save or load, shown in the queue popup
+    queue_notice: Option<String>,
+    // Saved playlist names while the picker is open
+    playlist_names: Vec<String>,
+    // Lines scrolled off the top of the info panel, and the selection they apply to
+    info_scroll_offset: u16,
+    info_scroll_for: usize,
+    // Totals for the stats screen, gathered when it opens
+    library_stats: LibraryStats,
+    // Volume the next title starts at (None: the player's default)
+    volume: Option<u8>,
+    // Speed the next title plays at (None: normal speed)
+    speed: Option<f64>,
+    repeat: RepeatMode,
+    // Width of the info panel in percent of the screen
+    info_percent: u16,
+    theme: Theme,
+    // Short notice for the taskbar, and when it was set
+    status_message: Option<(String, Instant)>,
+    // Poster images in the info panel, when the terminal can draw them
+    posters: Option<Posters>,
+}
+
+/// When a playback is long enough to count as a watch
+#[derive(Clone, Copy, Debug)]
+struct WatchThreshold {
+    // Time the player was open
+    secs: u64,
+    // Share of the film mpv reported as reached
+    percent: u8,
+}
+
+impl Default for WatchThreshold {
+    fn default() -> Self {
+        WatchThreshold { secs: 60, percent: 90 }
+    }
+}
+
+impl WatchThreshold {
+    fn reached(self, open_for: Duration, furthest: Option<f64>) -> bool {
+        open_for >= Duration::from_secs(self.secs)
+            || furthest.is_some_and(|share| share * 100.0 >= f64::from(self.percent))
+    }
+}
+
+/// How each title is handed to the player
+#[derive(Default)]
+struct PlaybackOptions {
+    player: Player,
+    // Where mpv starts each title, from START_AT
+    start_at: Option<StartSpec>,
+    // Intro/recap chapters to skip for opted-in groups
+    chapter_skip: ChapterSkip,
+    // Extra arguments from the config file
+    player_args: Vec<String>,
+    // Whether player_args replace the player's built-in arguments
+    replace_default_args: bool,
+    // Let mpv save the position on quit and resume from it next time
+    resume: bool,
+    // Ignore the saved position for the title picked next (set per playback)
+    from_start: bool,
+    // Starting volume in percent; None leaves it to the player
+    volume: Option<u8>,
+    // Playback speed as a factor; None plays at normal speed
+    speed: Option<f64>,
+    // Loop the queue or the current title until playback is stopped
+    repeat: RepeatMode,
+    // Preferred subtitle and audio languages from the config file
+    slang: Option<String>,
+    alang: Option<String>,
+    // Show mpv's progress in the terminal while it plays, with pause and seek keys
+    now_playing: bool,
+    // How much of a title has to be played before its watch count goes up
+    watch_threshold: WatchThreshold,
+    // Colors of the list and of the now playing view
+    theme: Theme,
+    // The terminal's graphics protocol, asked once at startup; None draws no posters
+    picker: Option<Picker>,
+}
+
+/// State that outlives a single run of the list UI (it is rebuilt after every playback)
+#[derive(Default)]
+struct Session {
+    play_queue: Option<PlayQueue>,
+    // File chosen in the version submenu for the movie about to play
+    version_choice: Option<PathBuf>,
+    show_full_paths: bool,
+    content_filter: ContentFilter,
+    sort_mode: SortMode,
+    // Hand-arranged order of movie paths used by the Custom sort mode
+    custom_order: Vec<PathBuf>,
+    playback: PlaybackOptions,
+    autoplay_scope: AutoplayScope,
+    // Seconds of inactivity before a random movie starts; None waits forever
+    autoplay_timeout: Option<u64>,
+    keymap: Keymap,
+    // Movies visible in the list (display order) when playback was requested
+    visible: Vec<usize>,
+    // Two titles confirmed as a double feature, played back to back
+    double_feature: Option<(usize, usize)>,
+    // Set when the API was unreachable and metadata came from the cache fetched at this time
+    metadata_cached_at: Option<DateTime<Local>>,
+    // The last metadata fetch gave up waiting for the API
+    api_timed_out: bool,
+    // Background metadata fetch, until its result has been picked up
+    metadata_rx: Option<Receiver<MetadataUpdate>>,
+    // Watched marks being sent to the API, and the ones it took
+    watched_sync: Option<Receiver<Vec<(PathBuf, bool)>>>,
+    // Ratings being sent to the API, and the ones it took
+    ratings_sync: Option<Receiver<Vec<(PathBuf, f64)>>>,
+    // API calls made on worker threads for changes to the files (renames, deletes); each
+    // reports its error, if any
+    api_jobs: Vec<Receiver<Result<(), String>>>,
+    // Fall back to the metadata cache when the API is unreachable (off with --no-cache)
+    use_cache: bool,
+    // Settled changes in the movie folders, when watch_folders is on
+    folder_changes: Option<Receiver<()>>,
+    // Select, play and scroll the list with the mouse
+    mouse: bool,
+    // Width of the info panel in percent, from the config file and moved with </>
+    info_percent: u16,
+    // The config file's settings for the restored toggles, saved with them on exit
+    config_seen: ui_state::ConfigSeen,
+    // Ask before quitting from the list
+    confirm_exit: bool,
+    // Library rebuilt with fresh metadata, for main to take over
+    library_update: Option<Library>,
+    // File-level info from the startup ffprobe pass (prewarm_probe), and the results still to come
+    probed: HashMap<PathBuf, MovieInfo>,
+    // Saved mpv positions, from the metadata worker and updated after playback
+    resume_times: HashMap<PathBuf, SystemTime>,
+    probe_rx: Option<Receiver<(PathBuf, MovieInfo)>>,
+    movie_roots: Vec<PathBuf>,
+    // Watched flags set by hand, over the API's watch counts
+    watched_marks: WatchedMarks,
+    // Ratings set by hand, over the API's ratings
+    ratings: UserRatings,
+    genre_filter: Vec<String>,
+    min_rating: Option<f64>,
+    include_unrated: bool,
+    favor_unwatched: bool,
+    random_unwatched_only: bool,
+    delete_sidecars: bool,
+    delete_from_api: bool,
+    year_range: Option<YearRange>,
+    collapsed: HashSet<String>,
+    flat_list: bool,
+    pinned_groups: Vec<String>,
+    favorites: Favorites,
+    favorites_only: bool,
+    user_queue: Vec<MovieEntry>,
+    // Movie selected last and the list's scroll position, restored when the list reopens and
+    // saved on exit for the next launch
+    last_selected: Option<PathBuf>,
+    scroll_offset: usize,
+    // Trailer to stream with mpv once the list has closed, with the title of its movie
+    trailer: Option<(String, String)>,
+    // Shown in the status line when the list reopens, such as a player that failed
+    status: Option<String>,
+}
+
+/// Open `url` in the default browser without waiting for it (xdg-open, or open on macOS)
+fn open_in_browser(url: &str) -> Result<(), String> {
+    let opener = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+    let mut child = Command::new(opener)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("{}: {}", opener, e))?;
+    // Reap it in the background so it does not linger as a zombie
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled { "ON" } else { "OFF" }
 }
 
 fn toggle_auto_play_next() {
@@ -75,6 +616,22 @@ fn check_auto_play_next() -> bool {
     AUTO_PLAY_NEXT.load(Ordering::SeqCst)
 }
 
+fn toggle_idle_autoplay() {
+    IDLE_AUTOPLAY.fetch_xor(true, Ordering::SeqCst);
+}
+
+fn check_idle_autoplay() -> bool {
+    IDLE_AUTOPLAY.load(Ordering::SeqCst)
+}
+
+fn toggle_subtitles() {
+    SUBTITLES.fetch_xor(true, Ordering::SeqCst);
+}
+
+fn check_subtitles() -> bool {
+    SUBTITLES.load(Ordering::SeqCst)
+}
+
 fn toggle_shuffle_queue() {
     SHUFFLE_QUEUE.fetch_xor(true, Ordering::SeqCst);
 }
@@ -83,32 +640,210 @@ fn check_shuffle_queue() -> bool {
     SHUFFLE_QUEUE.load(Ordering::SeqCst)
 }
 
+fn toggle_mini_mode() {
+    MINI_MODE.fetch_xor(true, Ordering::SeqCst);
+}
+
+fn check_mini_mode() -> bool {
+    MINI_MODE.load(Ordering::SeqCst)
+}
+
+fn toggle_high_contrast() {
+    HIGH_CONTRAST.fetch_xor(true, Ordering::SeqCst);
+}
+
+fn check_high_contrast() -> bool {
+    HIGH_CONTRAST.load(Ordering::SeqCst)
+}
+
+/// The configured colors, or the high-contrast scheme while it is switched on
+fn current_theme(configured: Theme) -> Theme {
+    if check_high_contrast() { Theme::preset(ThemePreset::HighContrast) } else { configured }
+}
+
+/// Values given for `flag` on the command line, as `--flag <value>` or `--flag=<value>`
+fn cli_values(flag: &str) -> Vec<String> {
+    let args: Vec<String> = env::args().collect();
+    let prefix = format!("{}=", flag);
+    args.iter()
+        .enumerate()
+        .filter_map(|(i, arg)| match arg.strip_prefix(&prefix) {
+            Some(value) => Some(value.to_string()),
+            None if arg == flag => args.get(i + 1).cloned(),
+            None => None,
+        })
+        .collect()
+}
+
+/// Movie roots from `--movies-dir <path>` / `--movies-dir=<path>` (repeatable), then MOVIES_DIR,
+/// then the config file, then ../movies. Each value may list several roots separated by commas.
+fn movie_roots(config: &Config) -> Vec<PathBuf> {
+    let from_args = cli_values("--movies-dir");
+    let values = if from_args.is_empty() {
+        env::var("MOVIES_DIR").ok().into_iter().collect()
+    } else {
+        from_args
+    };
+    let roots: Vec<PathBuf> = values.iter()
+        .flat_map(|v| v.split(','))
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .collect();
+    if roots.is_empty() && !config.movies_dirs.is_empty() {
+        config.movies_dirs.clone()
+    } else if roots.is_empty() {
+        vec![PathBuf::from(DEFAULT_MOVIES_DIR)]
+    } else {
+        roots
+    }
+}
+
+/// The keys the API may store `path` under (file_key and file_paths variants)
+fn api_keys(path: &Path, roots: &[PathBuf]) -> Vec<String> {
+    // Stored keys use forward slashes whatever the OS
+    let rel = api::normalize_key(&relative_to_root(path, roots).to_string_lossy());
+    let mut keys = vec![format!("movies/{}", rel), rel.clone(), format!("./movies/{}", rel)];
+    // Some databases keep only the file name
+    if let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string())
+        && !keys.contains(&name) {
+        keys.push(name);
+    }
+    keys
+}
+
+/// `path` relative to whichever movie root contains it
+fn relative_to_root<'a>(path: &'a Path, roots: &[PathBuf]) -> &'a Path {
+    roots.iter().find_map(|root| path.strip_prefix(root).ok()).unwrap_or(path)
+}
+
+/// Double-press window for exiting with Esc, from EXIT_ESC_WINDOW_MS (unset or 0 exits instantly)
+fn exit_esc_window() -> Option<Duration> {
+    env::var("EXIT_ESC_WINDOW_MS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&ms| ms > 0)
+        .map(Duration::from_millis)
+}
+
+// Folder groups listed first, in this order (`group_order` in the config file), set once at startup
+static GROUP_ORDER: OnceLock<Vec<String>> = OnceLock::new();
+
+// Video extensions from the config file (lowercase, without the dot), set once at startup
+static CONFIGURED_VIDEO_EXTENSIONS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Use `extensions` from the config file in addition to VIDEO_EXTENSIONS, or instead of them
+fn set_video_extensions(extensions: &[String], replace: bool) {
+    let defaults = if replace { &[][..] } else { VIDEO_EXTENSIONS };
+    let mut all: Vec<String> = defaults.iter().map(|e| e.to_string()).collect();
+    for ext in extensions {
+        let ext = ext.trim().trim_start_matches('.').to_lowercase();
+        if !ext.is_empty() && !all.contains(&ext) {
+            all.push(ext);
+        }
+    }
+    let _ = CONFIGURED_VIDEO_EXTENSIONS.set(all);
+}
+
 fn is_video(path: &Path) -> bool {
-    path.extension()
+    let Some(ext) = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()) else {
+        return false;
+    };
+    match CONFIGURED_VIDEO_EXTENSIONS.get() {
+        Some(extensions) => extensions.contains(&ext),
+        None => VIDEO_EXTENSIONS.contains(&ext.as_str()),
+    }
+}
+
+/// What a file found in the library folder is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FileKind {
+    Video,
+    Subtitle,
+    // AppleDouble "._" files, .DS_Store and other hidden files
+    Metadata,
+    Unsupported,
+}
+
+fn classify_file(path: &Path) -> FileKind {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let ext = path.extension()
         .and_then(|e| e.to_str())
-        .map(|e| VIDEO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
-        .unwrap_or(false)
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+    if name.starts_with('.') {
+        FileKind::Metadata
+    } else if is_video(path) {
+        FileKind::Video
+    } else if SUBTITLE_EXTENSIONS.contains(&ext.as_str()) {
+        FileKind::Subtitle
+    } else {
+        FileKind::Unsupported
+    }
+}
+
+/// The scanned movies with their metadata
+struct Library {
+    movies: Vec<MovieEntry>,
+    info_map: HashMap<PathBuf, MovieInfo>,
 }
 
-fn load_movies() -> std::io::Result<(Vec<MovieEntry>, HashMap<PathBuf, MovieInfo>)> {
-    let movies_dir = Path::new("../movies");
+/// Metadata fetched for a scanned library, possibly on a background thread
+struct MetadataUpdate {
+    info_map: HashMap<PathBuf, MovieInfo>,
+    cached_at: Option<DateTime<Local>>,
+    // The API did not answer within api_timeout_secs
+    timed_out: bool,
+    // The live API answered, so changes made offline can be sent
+    fetched: bool,
+    // A fresh scan of the movie folders, replacing the listed movies (set by a refresh)
+    movies: Option<Vec<MovieEntry>>,
+    // When mpv last saved a position for each movie that has one
+    resume_times: HashMap<PathBuf, SystemTime>,
+}
 
-    // Recursively collect all video files
-    let mut movies: Vec<MovieEntry> = Vec::new();
-    
+/// Names for the movie roots in group labels: the folder name, with parent folders added while
+/// roots would share it (/mnt/a/Movies and /mnt/b/Movies become a/Movies and b/Movies)
+fn root_labels(roots: &[PathBuf]) -> Vec<String> {
+    let names: Vec<Vec<String>> = roots.iter()
+        .map(|root| root.components()
+            .filter_map(|c| match c {
+                std::path::Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect())
+        .collect();
+    let label = |i: usize, depth: usize| match names[i].len() {
+        0 => roots[i].display().to_string(),
+        len => names[i][len - depth.min(len)..].join("/"),
+    };
+    let mut depths = vec![1; roots.len()];
+    loop {
+        let labels: Vec<String> = depths.iter().enumerate().map(|(i, &depth)| label(i, depth)).collect();
+        let mut grew = false;
+        for (i, depth) in depths.iter_mut().enumerate() {
+            let shared = labels.iter().enumerate().any(|(j, other)| j != i && *other == labels[i]);
+            if shared && *depth < names[i].len() {
+                *depth += 1;
+                grew = true;
+            }
+        }
+        if !grew {
+            return labels;
+        }
+    }
+}
+
+/// Walk every movie root, with groups in order ("Root" first) and movies by file name
+fn scan_movies(roots: &[PathBuf]) -> std::io::Result<Vec<MovieEntry>> {
     fn collect_movies(dir: &Path, base_dir: &Path, movies: &mut Vec<MovieEntry>) -> std::io::Result<()> {
         if dir.is_dir() {
             for entry in fs::read_dir(dir)? {
                 let entry = entry?;
                 let path = entry.path();
                 
-                if path.is_file() && is_video(&path) {
-                    // Skip AppleDouble metadata files that start with "._"
-                    if let Some(fname) = path.file_name().and_then(|n| n.to_str()) {
-                        if fname.starts_with("._") {
-                            continue;
-                        }
-                    }
+                // AppleDouble metadata files that start with "._" are classified as metadata and skipped
+                if path.is_file() && classify_file(&path) == FileKind::Video {
                     // Get the parent directory name relative to the base movies directory
                     let group_name = if let Some(parent) = path.parent() {
                         if parent == base_dir {
@@ -126,6 +861,7 @@ fn load_movies() -> std::io::Result<(Vec<MovieEntry>, HashMap<PathBuf, MovieInfo
                     movies.push(MovieEntry {
                         path,
                         group_name,
+                        versions: Vec::new(),
                     });
                 } else if path.is_dir() {
                     // Recursively search subdirectories
@@ -136,103 +872,237 @@ fn load_movies() -> std::io::Result<(Vec<MovieEntry>, HashMap<PathBuf, MovieInfo
         Ok(())
     }
     
-    collect_movies(movies_dir, movies_dir, &mut movies)?;
+    // Recursively collect all video files, per root
+    let mut per_root: Vec<Vec<MovieEntry>> = Vec::new();
+    for root in roots {
+        let mut found = Vec::new();
+        collect_movies(root, root, &mut found)?;
+        per_root.push(found);
+    }
+
+    // Folder names used in more than one root get the root's name as a prefix, e.g. "nas/Action"
+    let mut group_roots: HashMap<String, usize> = HashMap::new();
+    for found in &per_root {
+        let names: HashSet<&String> = found.iter().map(|m| &m.group_name).collect();
+        for name in names {
+            *group_roots.entry(name.clone()).or_default() += 1;
+        }
+    }
+    let mut movies: Vec<MovieEntry> = Vec::new();
+    // The same file reached through two roots (e.g. nested roots) is listed once
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    for (label, found) in root_labels(roots).into_iter().zip(per_root) {
+        for mut movie in found {
+            if !seen.insert(fs::canonicalize(&movie.path).unwrap_or_else(|_| movie.path.clone())) {
+                continue;
+            }
+            if group_roots.get(&movie.group_name).is_some_and(|&n| n > 1) {
+                movie.group_name = format!("{}/{}", label, movie.group_name);
+            }
+            movies.push(movie);
+        }
+    }
     
     // Group movies by group_name, then sort within groups
     let mut groups: HashMap<String, Vec<MovieEntry>> = HashMap::new();
     for movie in movies {
         groups.entry(movie.group_name.clone())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(movie);
     }
     
-    // Sort groups (but put "Root" first), then sort movies within each group
+    // Groups in the configured order, the rest by name with "Root" first; then sort movies within each group
     let mut group_names: Vec<String> = groups.keys().cloned().collect();
-    group_names.sort();
-    if let Some(root_idx) = group_names.iter().position(|n| n == "Root") {
-        group_names.remove(root_idx);
-        group_names.insert(0, "Root".to_string());
-    }
+    sort::order_groups(&mut group_names, GROUP_ORDER.get().map_or(&[], |order| order.as_slice()));
     
     let mut result: Vec<MovieEntry> = Vec::new();
     for group_name in group_names {
-        let mut group_movies = groups.remove(&group_name).unwrap();
-        group_movies.sort_by(|a, b| {
-            a.path.file_name()
-                .and_then(|n| n.to_str())
-                .cmp(&b.path.file_name().and_then(|n| n.to_str()))
-        });
-        result.extend(group_movies);
+        result.extend(groups.remove(&group_name).unwrap());
     }
-    
+
+    // No metadata yet, so this orders each group by file name
+    let comparator = MovieComparator::parse("");
+    for group in result.chunk_by_mut(|a, b| a.group_name == b.group_name) {
+        comparator.sort(group, &HashMap::new());
+    }
+
+    Ok(result)
+}
+
+/// Fetch metadata for `movies` from the API, falling back to the cached copy when allowed, and
+/// find their subtitle files
+fn fetch_metadata(movies: &[MovieEntry], roots: &[PathBuf], use_cache: bool) -> MetadataUpdate {
+
     // Try to fetch all movies from the FastAPI `/movies/` endpoint and map file keys/paths to metadata.
     let mut info_map: HashMap<PathBuf, MovieInfo> = HashMap::new();
-    let api_base = env::var("API_URL").unwrap_or_else(|_| "http://127.0.0.1:8000".to_string());
-    let client = HttpClient::new();
-    let movies_url = format!("{}/movies/?limit=1000", api_base.trim_end_matches('/'));
+    let client = api::client();
+    let movies_url = format!("{}/movies/?limit=1000", api::api_base());
+    let mut fetched = false;
+    let mut timed_out = false;
 
-    match client.get(&movies_url).send() {
+    match api::send_with_retry(|| client.get(&movies_url), true) {
+        Ok(resp) if !resp.status().is_success() => {
+            log::warn!("API {} failed: {}", movies_url, api::error_summary(resp));
+        }
         Ok(resp) => match resp.json::<Vec<JsonValue>>() {
             Ok(api_movies) => {
-                // Build a map: file_path_or_key -> movie JSON value
-                let mut by_path: HashMap<String, &JsonValue> = HashMap::new();
-                for mv in &api_movies {
-                    if let Some(fk) = mv.get("file_key").and_then(|v| v.as_str()) {
-                        by_path.insert(fk.to_string(), mv);
-                    }
-                    if let Some(paths) = mv.get("file_paths").and_then(|v| v.as_array()) {
-                        for p in paths {
-                            if let Some(pstr) = p.as_str() {
-                                by_path.insert(pstr.to_string(), mv);
-                            }
-                        }
-                    }
-                }
+                // Which movie JSON value each stored file key or path belongs to
+                let index = api::KeyIndex::build(&api_movies);
+                // Local files matched per API entry, to detect one entry covering several files
+                let mut matched_files: HashMap<usize, Vec<PathBuf>> = HashMap::new();
 
                 // For each local file, attempt to find matching metadata
-                for movie in &result {
-                    let rel = movie.path.strip_prefix(movies_dir)
-                        .map(|p| p.to_string_lossy().to_string())
-                        .unwrap_or_else(|_| movie.path.to_string_lossy().to_string());
-                    let candidates = vec![format!("movies/{}", rel), rel.clone(), format!("./movies/{}", rel)];
-                    let mut found: Option<&JsonValue> = None;
-                    for c in &candidates {
-                        if let Some(mv) = by_path.get(c) {
-                            found = Some(*mv);
-                            break;
-                        }
-                    }
-                    if let Some(mv) = found {
-                        let info = MovieInfo {
-                            title: mv.get("title").and_then(|v| v.as_str().map(|s| s.to_string())),
-                            year: mv.get("year").and_then(|v| v.as_i64().map(|n| n as i32)),
-                            genre: mv.get("genre").and_then(|v| v.as_str().map(|s| s.to_string())),
-                            director: mv.get("director").and_then(|v| v.as_str().map(|s| s.to_string())),
-                            plot: mv.get("plot").and_then(|v| v.as_str().map(|s| s.to_string())),
-                            runtime: mv.get("runtime").and_then(|v| v.as_str().map(|s| s.to_string())),
-                            rating: mv.get("rating").and_then(|v| v.as_f64()),
-                            watch_count: mv.get("watch_count").and_then(|v| v.as_i64().map(|n| n as i32)),
-                            _imdb_id: mv.get("imdb_id").and_then(|v| v.as_str().map(|s| s.to_string())),
-                            file_size: None,
-                            codec: None,
-                            resolution: None,
-                        };
+                for movie in movies {
+                    let candidates = api_keys(&movie.path, roots);
+                    if let Some(api_idx) = index.find(&candidates) {
+                        matched_files.entry(api_idx).or_default().push(movie.path.clone());
+                        let info = api::movie_info_from_json(&api_movies[api_idx]);
                         info_map.insert(movie.path.clone(), info);
                     } else {
-                        eprintln!("API: no metadata for file; tried keys: {}", candidates.join(" | "));
+                        log::debug!("API: no metadata for file; tried keys: {}", candidates.join(" | "));
+                    }
+                }
+
+                // Record which files share an API entry; apply_metadata acts on these groups
+                for files in matched_files.into_values().filter(|files| files.len() > 1) {
+                    for path in &files {
+                        if let Some(info) = info_map.get_mut(path) {
+                            info.duplicates = files.iter().filter(|p| *p != path).cloned().collect();
+                        }
                     }
                 }
+                metadata_cache::save(&info_map);
+                fetched = true;
             }
             Err(e) => {
-                eprintln!("Failed to parse /movies/ JSON: {}", e);
+                log::warn!("Failed to parse /movies/ JSON: {}", e);
             }
         },
         Err(e) => {
-            eprintln!("Failed to call API {}: {}", movies_url, e);
+            timed_out = e.is_timeout();
+            log::warn!("Failed to call API {}: {}", movies_url, e);
+        }
+    }
+
+    // Fall back to the last successful fetch
+    let mut cached_at = None;
+    if !fetched && use_cache
+        && let Some((cached, fetched_at)) = metadata_cache::load() {
+        log::info!("Using cached metadata from {}", fetched_at.format("%Y-%m-%d %H:%M"));
+        let listed: HashSet<&PathBuf> = movies.iter().map(|m| &m.path).collect();
+        info_map = cached.into_iter()
+            .filter(|(path, _)| listed.contains(path))
+            .collect();
+        cached_at = Some(fetched_at);
+    }
+
+    // Reading every movie folder is slow on network shares, so it is done here on the worker
+    let paths: Vec<PathBuf> = movies.iter().map(|m| m.path.clone()).collect();
+    let mut subtitle_files = subtitles::find_all(&paths);
+    for (path, info) in info_map.iter_mut() {
+        info.subtitles = subtitle_files.remove(path).unwrap_or_default();
+        // The API has no file sizes; read them once here for the size sort
+        if info.file_bytes.is_none() {
+            info.file_bytes = fs::metadata(path).ok().map(|meta| meta.len());
+        }
+    }
+
+    // Looking up mpv's watch-later files stats every movie, so it is done here too
+    let resume_times = movies.iter()
+        .filter_map(|m| Some((m.path.clone(), mpv::resume_saved_at(&m.path)?)))
+        .collect();
+
+    MetadataUpdate { info_map, cached_at, timed_out, fetched, movies: None, resume_times }
+}
+
+/// Rescan the library and fetch its metadata on a worker thread; the channel closes
+/// without a result if the scan fails
+fn refresh_library(roots: Vec<PathBuf>, use_cache: bool) -> Receiver<MetadataUpdate> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let Ok(movies) = scan_movies(&roots) else {
+            return;
+        };
+        let mut update = fetch_metadata(&movies, &roots, use_cache);
+        update.movies = Some(movies);
+        let _ = tx.send(update);
+    });
+    rx
+}
+
+/// Apply the duplicate policy and SORT_KEYS once metadata is known
+fn apply_metadata(movies: &mut Vec<MovieEntry>, info_map: &mut HashMap<PathBuf, MovieInfo>) {
+    // Files sharing an API entry, each group in library order
+    let mut groups: Vec<Vec<PathBuf>> = Vec::new();
+    for movie in movies.iter() {
+        let Some(info) = info_map.get(&movie.path) else {
+            continue;
+        };
+        let files: Vec<PathBuf> = movies.iter()
+            .filter(|m| m.path == movie.path || info.duplicates.contains(&m.path))
+            .map(|m| m.path.clone())
+            .collect();
+        if files.len() > 1 && !groups.contains(&files) {
+            groups.push(files);
+        }
+    }
+    let policy = DuplicatePolicy::from_env();
+    for files in groups {
+        apply_duplicate_policy(policy, &files, movies, info_map);
+    }
+
+    // Sort movies within each group by the configured keys (e.g. SORT_KEYS="rating:desc,year:desc,title"),
+    // always falling back to file name so the order is deterministic
+    let comparator = MovieComparator::parse(&env::var("SORT_KEYS").unwrap_or_default());
+    for group in movies.chunk_by_mut(|a, b| a.group_name == b.group_name) {
+        comparator.sort(group, info_map);
+    }
+}
+
+/// Rough quality rank for a file: resolution hinted by the file name, then file size
+fn quality_score(path: &Path) -> (u32, u64) {
+    let name = path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let resolution = if name.contains("2160p") || name.contains("4k") || name.contains("uhd") {
+        2160
+    } else if name.contains("1080p") {
+        1080
+    } else if name.contains("720p") {
+        720
+    } else if name.contains("480p") {
+        480
+    } else {
+        0
+    };
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    (resolution, size)
+}
+
+/// Resolve a set of local files that all matched the same API entry according to `policy`.
+fn apply_duplicate_policy(policy: DuplicatePolicy, files: &[PathBuf], movies: &mut Vec<MovieEntry>, info_map: &mut HashMap<PathBuf, MovieInfo>) {
+    // Best quality first
+    let mut ranked = files.to_vec();
+    ranked.sort_by_key(|p| std::cmp::Reverse(quality_score(p)));
+    let best = ranked[0].clone();
+
+    for path in files {
+        if let Some(info) = info_map.get_mut(path) {
+            info.duplicates = files.iter().filter(|p| *p != path).cloned().collect();
         }
     }
 
-    Ok((result, info_map))
+    match policy {
+        DuplicatePolicy::Share => {}
+        DuplicatePolicy::Best | DuplicatePolicy::Collapse => {
+            movies.retain(|m| m.path == best || !files.contains(&m.path));
+            if policy == DuplicatePolicy::Collapse
+                && let Some(entry) = movies.iter_mut().find(|m| m.path == best) {
+                entry.versions = ranked[1..].to_vec();
+            }
+        }
+    }
 }
 
 fn format_duration(seconds: f64) -> String {
@@ -264,40 +1134,72 @@ fn format_file_size(bytes: u64) -> String {
     }
 }
 
+/// Probe `paths` with a few worker threads, sending each result as it completes
+fn prewarm_probes(paths: Vec<PathBuf>) -> Receiver<(PathBuf, MovieInfo)> {
+    let (tx, rx) = mpsc::channel();
+    let pending = Arc::new(Mutex::new(paths));
+    for _ in 0..PREWARM_THREADS {
+        let pending = Arc::clone(&pending);
+        let tx = tx.clone();
+        std::thread::spawn(move || {
+            while let Some(path) = pending.lock().ok().and_then(|mut paths| paths.pop()) {
+                let info = get_movie_info(&path);
+                // The list has closed for good
+                if tx.send((path, info)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    rx
+}
+
+/// File-level info for a movie the API does not know: the probe and its subtitle files
 fn get_movie_info(path: &Path) -> MovieInfo {
-    // Try to get metadata using ffprobe
-    let output = Command::new("ffprobe")
-        .args([
-            "-v", "error",
-            "-show_entries", "format=duration,size:stream=codec_name,width,height",
-            "-of", "json",
-            path.to_str().unwrap_or(""),
-        ])
-        .output();
+    MovieInfo { subtitles: subtitles::find(path), ..probe_movie_info(path) }
+}
+
+fn probe_movie_info(path: &Path) -> MovieInfo {
+    // Probed before and unchanged since
+    if let Some(info) = probe_cache::get(path) {
+        return info;
+    }
+
+    // Try to get metadata using ffprobe; without it only the file size is known
+    let output = backend::ffprobe_installed().then(|| {
+        Command::new("ffprobe")
+            .args([
+                "-v", "error",
+                "-show_entries", "format=duration,size:stream=codec_name,width,height",
+                "-show_chapters",
+                "-of", "json",
+                path.to_str().unwrap_or(""),
+            ])
+            .output()
+    });
     
     match output {
-        Ok(output) if output.status.success() => {
+        Some(Ok(output)) if output.status.success() => {
             let json_str = String::from_utf8_lossy(&output.stdout);
             let mut runtime = None;
-            let mut file_size = None;
+            let mut file_bytes = None;
             let mut codec = None;
             let mut resolution = None;
+            let mut chapters = Vec::new();
             
             // Parse JSON to extract information
             if let Ok(json) = serde_json::from_str::<serde_json::Value>(&json_str) {
                 // Get duration from format
                 if let Some(format) = json.get("format") {
                     if let Some(duration_str) = format.get("duration")
-                        .and_then(|d| d.as_str()) {
-                        if let Ok(duration_secs) = duration_str.parse::<f64>() {
-                            runtime = Some(format_duration(duration_secs));
-                        }
+                        .and_then(|d| d.as_str())
+                        && let Ok(duration_secs) = duration_str.parse::<f64>() {
+                        runtime = Some(format_duration(duration_secs));
                     }
                     if let Some(size_str) = format.get("size")
-                        .and_then(|s| s.as_str()) {
-                        if let Ok(size_bytes) = size_str.parse::<u64>() {
-                            file_size = Some(format_file_size(size_bytes));
-                        }
+                        .and_then(|s| s.as_str())
+                        && let Ok(size_bytes) = size_str.parse::<u64>() {
+                        file_bytes = Some(size_bytes);
                     }
                 }
                 
@@ -306,27 +1208,27 @@ fn get_movie_info(path: &Path) -> MovieInfo {
                     .and_then(|s| s.as_array()) {
                     for stream in streams {
                         if stream.get("codec_type").and_then(|t| t.as_str()) == Some("video") {
-                            if codec.is_none() {
-                                if let Some(codec_name) = stream.get("codec_name")
+                            if codec.is_none()
+                                && let Some(codec_name) = stream.get("codec_name")
                                     .and_then(|c| c.as_str()) {
-                                    codec = Some(codec_name.to_string());
-                                }
+                                codec = Some(codec_name.to_string());
                             }
-                            if resolution.is_none() {
-                                if let (Some(w), Some(h)) = (
+                            if resolution.is_none()
+                                && let (Some(w), Some(h)) = (
                                     stream.get("width").and_then(|w| w.as_u64()),
                                     stream.get("height").and_then(|h| h.as_u64()),
                                 ) {
-                                    resolution = Some(format!("{}x{}", w, h));
-                                }
+                                resolution = Some(format!("{}x{}", w, h));
                             }
                             break;
                         }
                     }
                 }
+
+                chapters = chapters::from_ffprobe_json(&json);
             }
             
-            MovieInfo {
+            let info = MovieInfo {
                 title: None,
                 year: None,
                 genre: None,
@@ -335,17 +1237,27 @@ fn get_movie_info(path: &Path) -> MovieInfo {
                 runtime,
                 rating: None,
                 watch_count: None,
-                file_size,
+                content_rating: None,
+                poster_url: None,
+                trailer_url: None,
+                file_size: file_bytes.map(format_file_size),
+                file_bytes,
                 codec,
                 resolution,
-                _imdb_id: None,
-            }
+                chapters,
+                subtitles: Vec::new(),
+                imdb_id: None,
+                file_key: None,
+                duplicates: Vec::new(),
+            };
+            probe_cache::insert(path, &info);
+            info
         }
         _ => {
             // Fallback: try to get file size at least
-            let file_size = fs::metadata(path)
+            let file_bytes = fs::metadata(path)
                 .ok()
-                .map(|m| format_file_size(m.len()));
+                .map(|m| m.len());
             
             MovieInfo {
                 title: None,
@@ -356,130 +1268,418 @@ fn get_movie_info(path: &Path) -> MovieInfo {
                 runtime: None,
                 rating: None,
                 watch_count: None,
-                file_size,
+                content_rating: None,
+                poster_url: None,
+                trailer_url: None,
+                file_size: file_bytes.map(format_file_size),
+                file_bytes,
                 codec: None,
                 resolution: None,
-                _imdb_id: None,
+                chapters: Vec::new(),
+                subtitles: Vec::new(),
+                imdb_id: None,
+                file_key: None,
+                duplicates: Vec::new(),
             }
         }
     }
 }
 
-fn play_movies_from_index(movies: &[MovieEntry], start_index: usize, shuffle_order: bool) -> std::io::Result<()> {
-    if movies.is_empty() {
-        return Ok(());
+/// Whether the play queue starts over when it runs out, or one film plays on a loop
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RepeatMode {
+    #[default]
+    Off,
+    // Back to the first title of the queue after the last
+    All,
+    // The same title again and again
+    One,
+}
+
+impl RepeatMode {
+    fn next(self) -> Self {
+        match self {
+            RepeatMode::Off => RepeatMode::All,
+            RepeatMode::All => RepeatMode::One,
+            RepeatMode::One => RepeatMode::Off,
+        }
     }
 
-    // If shuffle_order is true, preserve the selected movie as first and shuffle the rest.
-    let movies_to_play: Vec<MovieEntry> = if shuffle_order {
-        if start_index >= movies.len() {
-            // Fallback: shuffle everything if the start index is out of bounds
-            let mut shuffled: Vec<MovieEntry> = movies.to_vec();
-            let mut rng = rand::thread_rng();
-            shuffled.shuffle(&mut rng);
-            shuffled
-        } else {
-            let mut rng = rand::thread_rng();
-            // Collect indices of all movies except the selected one
-            let mut other_idxs: Vec<usize> = (0..movies.len()).filter(|&i| i != start_index).collect();
-            other_idxs.shuffle(&mut rng);
+    fn label(self) -> &'static str {
+        match self {
+            RepeatMode::Off => "off",
+            RepeatMode::All => "all",
+            RepeatMode::One => "one",
+        }
+    }
+}
 
-            // Start with the selected movie, then append the shuffled others
-            let mut ordered: Vec<MovieEntry> = Vec::with_capacity(movies.len());
-            ordered.push(movies[start_index].clone());
-            for i in other_idxs {
-                ordered.push(movies[i].clone());
-            }
-            ordered
+/// How far autoplay continues past the selected movie
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum AutoplayScope {
+    // Through the whole library
+    #[default]
+    Library,
+    // Only within the selected movie's group
+    Group,
+    // Only through the movies the active filters/search leave visible
+    Filter,
+}
+
+impl AutoplayScope {
+    fn from_env() -> Self {
+        match env::var("AUTOPLAY_SCOPE").unwrap_or_default().to_lowercase().as_str() {
+            "group" => AutoplayScope::Group,
+            "filter" => AutoplayScope::Filter,
+            _ => AutoplayScope::Library,
         }
-    } else {
-        // For normal playback, keep original order but rotate to start_index
-        let mut rotated = movies[start_index..].to_vec();
-        rotated.extend_from_slice(&movies[..start_index]);
-        rotated
+    }
+}
+
+/// Build the order (as indices into `movies`) in which playback should run.
+/// `pool` lists the playable movies in display order; anything outside it is never queued.
+fn build_play_order(movies: &[MovieEntry], pool: &[usize], start_index: usize, shuffle_order: bool, scope: AutoplayScope) -> Vec<usize> {
+    // Group scope keeps only the selected movie's group
+    let pool: Vec<usize> = match (scope, movies.get(start_index)) {
+        (AutoplayScope::Group, Some(start)) => pool.iter()
+            .copied()
+            .filter(|&i| movies[i].group_name == start.group_name)
+            .collect(),
+        _ => pool.to_vec(),
     };
+    if pool.is_empty() {
+        return Vec::new();
+    }
+    let start_pos = pool.iter().position(|&i| i == start_index);
 
-    // Play movies in order (either shuffled or rotated)
-    for movie in movies_to_play {
-        println!("Playing {}", movie.path.display());
-
-        // Increment watch count via API if available
-        let api_base = env::var("API_URL").unwrap_or_else(|_| "http://127.0.0.1:8000".to_string());
-        let http = HttpClient::new();
-        // compute relative key variants similar to load_movies
-        let movies_dir = Path::new("../movies");
-        let rel = movie.path.strip_prefix(movies_dir)
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|_| movie.path.to_string_lossy().to_string());
-        let candidates = vec![format!("movies/{}", rel), rel.clone(), format!("./movies/{}", rel)];
-        // Try incrementing by imdb_id from cached info if present
-        if std::env::var("API_URL").is_ok() {
-            // prefer imdb_id if the movie_info cache has it
-            // (we don't have access to the cache here; attempt by path)
-            let endpoint = format!("{}/movies/increment_watch/", api_base.trim_end_matches('/'));
-            for c in &candidates {
-                let _ = http.post(&endpoint).query(&[("path", c)]).send();
-            }
-        }
-
-        let status = Command::new("mpv")
-            .args([
-                "--fullscreen",
-                "--no-terminal",
-                "--no-sub",
-                // "--sub-auto=no",
-                // "--sid=-1",
-                movie.path.to_str().unwrap(),
-            ])
-            .status()
-            .expect("failed to start mpv");
+    // If shuffle_order is true, preserve the selected movie as first and shuffle the rest.
+    if shuffle_order {
+        let mut rng = rand::thread_rng();
+        match start_pos {
+            None => {
+                // Fallback: shuffle everything if the start index is not playable
+                let mut shuffled = pool.to_vec();
+                shuffled.shuffle(&mut rng);
+                shuffled
+            }
+            Some(pos) => {
+                // Collect all movies except the selected one
+                let mut others: Vec<usize> = pool.iter().enumerate().filter(|&(i, _)| i != pos).map(|(_, &m)| m).collect();
+                others.shuffle(&mut rng);
 
-        let exit_code = status.code().unwrap_or(1);
-        
-        if exit_code != 0 {
-            return Ok(());
-        }
-        if !check_auto_play_next() {
-            return Ok(());
+                // Start with the selected movie, then append the shuffled others
+                let mut ordered: Vec<usize> = Vec::with_capacity(pool.len());
+                ordered.push(start_index);
+                ordered.extend(others);
+                ordered
+            }
         }
+    } else {
+        // For normal playback, keep original order but rotate to start_index
+        let pos = start_pos.unwrap_or(0);
+        pool[pos..].iter().chain(&pool[..pos]).copied().collect()
     }
-    
-    Ok(())
 }
 
-/// helper function to create a centered rect using up certain percentage of the available rect `r`
-/// Gotten from ratatui examples
-fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
-    let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
-    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
-    let [area] = vertical.areas(area);
-    let [area] = horizontal.areas(area);
-    area
+/// The active play order, kept across returns to the list so the next title can be shown.
+#[derive(Clone)]
+struct PlayQueue {
+    order: Vec<usize>,
+    // Position in `order` of the next title to play
+    position: usize,
+    // Double features always play on, with an intermission between titles
+    double_feature: bool,
 }
 
-impl AppState {
-    fn move_cursor_left(&mut self) {
-        let cursor_moved_left = self.character_index.saturating_sub(1);
-        self.character_index = self.clamp_cursor(cursor_moved_left);
+impl PlayQueue {
+    fn new(order: Vec<usize>) -> Self {
+        PlayQueue { order, position: 0, double_feature: false }
     }
 
-    fn move_cursor_right(&mut self) {
-        let cursor_moved_right = self.character_index.saturating_add(1);
-        self.character_index = self.clamp_cursor(cursor_moved_right);
+    fn double_feature(first: usize, second: usize) -> Self {
+        PlayQueue { order: vec![first, second], position: 0, double_feature: true }
     }
 
-    fn enter_char(&mut self, new_char: char) {
-        self.user_input.insert(self.character_index, new_char);
-        self.move_cursor_right();
+    fn next_index(&self) -> Option<usize> {
+        self.order.get(self.position).copied()
     }
 
-    fn delete_char(&mut self) {
-        let is_not_cursor_leftmost = self.character_index != 0;
-        if is_not_cursor_leftmost {
-            let current_index = self.character_index;
-            let from_left_to_current_index = current_index - 1;
+    fn is_finished(&self) -> bool {
+        self.position >= self.order.len()
+    }
 
-            let before_char_to_delete = self.user_input.chars().take(from_left_to_current_index);
+    /// Re-point the queue at the same files after the movie list was rebuilt
+    fn remap(&mut self, old: &[MovieEntry], new: &[MovieEntry]) {
+        let find = |i: usize| new.iter().position(|m| m.path == old[i].path);
+        let played = self.order[..self.position].iter().filter(|&&i| find(i).is_some()).count();
+        self.order = self.order.iter().filter_map(|&i| find(i)).collect();
+        self.position = played;
+    }
+}
+
+/// Play the queue from its position; returns a message for the status line when the player failed
+fn play_movies_from_index(movies: &[MovieEntry], info_map: &HashMap<PathBuf, MovieInfo>, queue: &mut PlayQueue, options: &PlaybackOptions) -> std::io::Result<Option<String>> {
+    // Only the title the user picked can be restarted; the rest of the queue resumes as usual
+    let mut from_start = options.from_start;
+    // Posters already cached for the list go on the title card
+    let mut posters = options.picker.clone().map(Posters::new);
+    // Play movies in order (either shuffled or rotated)
+    while let Some(movie_idx) = queue.next_index() {
+        let movie = &movies[movie_idx];
+        let info = info_map.get(&movie.path);
+        if queue.double_feature && queue.position > 0 {
+            let length = now_playing::intermission_length(env::var("INTERMISSION_SECS").ok().as_deref());
+            now_playing::intermission(&display_title(movie, info), length, current_theme(options.theme))?;
+        }
+        queue.position += 1;
+        // How it is played, for the title card
+        let mut notes = Vec::new();
+
+        let player = options.player;
+        let mut args = if options.replace_default_args { Vec::new() } else { player.base_args(check_subtitles()) };
+        args.extend(options.player_args.iter().cloned());
+        if let Some(volume) = options.volume {
+            args.push(player.volume_arg(volume));
+        }
+        if let Some(speed) = options.speed {
+            args.push(player.speed_arg(speed));
+        }
+        args.extend(player.language_args(options.slang.as_deref(), options.alang.as_deref()));
+        // Load the subtitle files next to the movie by name instead of relying on the player's own search
+        if check_subtitles() {
+            args.extend(player.subtitle_file_args(&subtitles::find(&movie.path)));
+        }
+        // mpv reports its position over a socket so the terminal can show the progress
+        let socket = (player == Player::Mpv && options.now_playing).then(progress_socket).flatten();
+        if let Some(socket) = &socket {
+            // A socket left by a crashed player would be mistaken for the new one
+            let _ = fs::remove_file(socket);
+            args.push(format!("--input-ipc-server={}", socket.display()));
+        }
+        // mpv names its watch-later files after the path it was given, so always give it the same one
+        let resumable = player == Player::Mpv && options.resume;
+        let path = if resumable { mpv::resume_path(&movie.path) } else { movie.path.clone() };
+        let resuming = resumable && !std::mem::take(&mut from_start) && options.start_at.is_none() && mpv::has_resume_position(&path);
+        if resumable {
+            args.push("--save-position-on-quit".to_string());
+            if resuming {
+                notes.push("Resuming from the saved position".to_string());
+            } else {
+                args.push("--resume-playback=no".to_string());
+            }
+        }
+        // An explicit start position or a saved one wins over chapter skipping
+        let skip_plan = if !resuming && options.start_at.is_none() && options.chapter_skip.applies_to(&movie.group_name) {
+            options.chapter_skip.plan(&chapters::probe(&movie.path))
+        } else {
+            None
+        };
+        match skip_plan.and_then(|plan| player.skip_args(&plan, &path)) {
+            Some(skip_args) => {
+                notes.push("Skipping intro/recap chapters".to_string());
+                args.extend(skip_args);
+            }
+            None => {
+                if let Some(start) = options.start_at {
+                    match player.start_arg(start) {
+                        Some(arg) => args.push(arg),
+                        None => notes.push(format!("{} cannot start at {}; playing from the beginning", player, start)),
+                    }
+                }
+                args.push(path.to_string_lossy().to_string());
+            }
+        }
+
+        let title = display_title(movie, info);
+        let details: Vec<String> = [
+            info.and_then(|i| i.year).map(|y| y.to_string()),
+            info.and_then(|i| i.runtime.clone()),
+            Some(movie.group_name.clone()).filter(|g| g != "Root"),
+        ].into_iter().flatten().collect();
+        let card = now_playing::Card { title: &title, details: details.join(" · "), notes: &notes, player: player.binary() };
+        let poster = posters.as_ref().and_then(|p| p.cached(info.and_then(|i| i.poster_url.as_deref()), &movie.path));
+        now_playing::announce(&card, posters.as_mut().zip(poster.as_deref()), current_theme(options.theme))?;
+
+        let started = Instant::now();
+        let mut child = Command::new(player.binary())
+            .args(&args)
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => std::io::Error::new(
+                    e.kind(),
+                    format!("{} not found on PATH (install it, or choose another player with --player or `player` in the config file)", player),
+                ),
+                _ => e,
+            })?;
+        let (status, furthest) = wait_for_player(&mut child, socket.as_deref(), &title, current_theme(options.theme))?;
+        if let Some(socket) = &socket {
+            let _ = fs::remove_file(socket);
+        }
+
+        let exit = player.exit(status.code());
+        // Count the watch under the key the API matched this file with; files it does not know are skipped,
+        // and so are failed or short playbacks
+        if !matches!(exit, PlayerExit::Failed(_))
+            && options.watch_threshold.reached(started.elapsed(), furthest)
+            && api::is_configured()
+            && let Some(info) = info
+            && (info.file_key.is_some() || info.imdb_id.is_some())
+            && let Err(e) = api::increment_watch(&api::client(), info.file_key.as_deref(), info.imdb_id.as_deref()) {
+            log::warn!("Could not count the watch: {}", e);
+        }
+        match exit {
+            PlayerExit::Finished => {}
+            PlayerExit::Interrupted => return Ok(None),
+            PlayerExit::Failed(code) => {
+                log::warn!("{} could not play {} (exit code {})", player, movie.path.display(), code);
+                return Ok(Some(format!("{} could not play {} (exit code {})", player, title, code)));
+            }
+        }
+        // Repeating plays on until the player is stopped
+        match options.repeat {
+            RepeatMode::One => queue.position -= 1,
+            RepeatMode::All if queue.is_finished() => queue.position = 0,
+            RepeatMode::All => {}
+            RepeatMode::Off if !check_auto_play_next() && !queue.double_feature => return Ok(None),
+            RepeatMode::Off => {}
+        }
+    }
+    
+    Ok(None)
+}
+
+/// Where mpv is asked to report its position for the progress view
+#[cfg(unix)]
+fn progress_socket() -> Option<PathBuf> {
+    Some(mpv_ipc::socket_path())
+}
+
+// mpv's IPC server is a Unix socket, so elsewhere the player runs without the progress view
+#[cfg(not(unix))]
+fn progress_socket() -> Option<PathBuf> {
+    None
+}
+
+/// Wait for the player to exit, following it in the progress view when mpv opened its socket.
+/// Returns the exit status and the furthest share of the film mpv reported.
+#[cfg(unix)]
+fn wait_for_player(child: &mut std::process::Child, socket: Option<&Path>, title: &str, theme: Theme) -> std::io::Result<(std::process::ExitStatus, Option<f64>)> {
+    // Without a socket (or an mpv that never opened it) just wait for the player
+    match socket.map(|socket| mpv_ipc::MpvIpc::connect(socket, child)) {
+        Some(Ok(mut ipc)) => now_playing::run(child, &mut ipc, title, theme),
+        _ => Ok((child.wait()?, None)),
+    }
+}
+
+#[cfg(not(unix))]
+fn wait_for_player(child: &mut std::process::Child, _socket: Option<&Path>, _title: &str, _theme: Theme) -> std::io::Result<(std::process::ExitStatus, Option<f64>)> {
+    Ok((child.wait()?, None))
+}
+
+/// Stream a trailer with mpv, which fetches it through yt-dlp, and wait until it ends; returns
+/// a message for the status line when mpv failed
+fn play_trailer(target: &str, title: &str, options: &PlaybackOptions) -> std::io::Result<Option<String>> {
+    let player = Player::Mpv;
+    let card = now_playing::Card { title, details: "Trailer".to_string(), notes: &[], player: player.binary() };
+    now_playing::announce(&card, None, current_theme(options.theme))?;
+    let mut args = if options.replace_default_args { Vec::new() } else { player.base_args(false) };
+    args.extend(options.player_args.iter().cloned());
+    if let Some(volume) = options.volume {
+        args.push(player.volume_arg(volume));
+    }
+    // The URL comes from the API: never let it pass for an mpv option
+    args.push("--".to_string());
+    args.push(target.to_string());
+    let status = Command::new(player.binary()).args(&args).status()?;
+    if let PlayerExit::Failed(code) = player.exit(status.code()) {
+        log::warn!("{} could not play the trailer {} (exit code {})", player, target, code);
+        return Ok(Some(format!("{} could not play the trailer (exit code {})", player, code)));
+    }
+    Ok(None)
+}
+
+/// Shorten `text` to at most `max_chars`, marking the cut with "…".
+/// Paths are cut from the start since the end is what tells them apart.
+fn truncate_with_ellipsis(text: &str, max_chars: usize, keep_end: bool) -> String {
+    let len = text.chars().count();
+    if len <= max_chars {
+        return text.to_string();
+    }
+    if max_chars == 0 {
+        return String::new();
+    }
+    let keep = max_chars - 1;
+    if keep_end {
+        let tail: String = text.chars().skip(len - keep).collect();
+        format!("…{}", tail)
+    } else {
+        let head: String = text.chars().take(keep).collect();
+        format!("{}…", head)
+    }
+}
+
+/// `info` with the user's own rating for `path` over the API's, if there is one
+fn with_user_rating(ratings: &UserRatings, path: &Path, mut info: MovieInfo) -> MovieInfo {
+    if let Some(rating) = ratings.get(path) {
+        info.rating = Some(rating);
+    }
+    info
+}
+
+/// Title to show for a movie: the DB title if known, otherwise the file stem
+fn display_title(movie: &MovieEntry, info: Option<&MovieInfo>) -> String {
+    info.and_then(|i| i.title.clone())
+        .or_else(|| movie.path.file_stem().and_then(|s| s.to_str().map(|s| s.to_string())))
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// A 0-10 rating as five stars to the nearest half: "★★★½☆"
+fn rating_stars(rating: Option<f64>, theme: Theme) -> Vec<Span<'static>> {
+    let halves = rating.map_or(0, |r| r.clamp(0.0, 10.0).round() as usize);
+    let (full, half) = (halves / 2, halves % 2 == 1);
+    let empty = 5 - full - usize::from(half);
+    let mut spans = vec![Span::styled("★".repeat(full), Style::default().fg(theme.accent))];
+    if half {
+        spans.push(Span::styled("½", Style::default().fg(theme.accent)));
+    }
+    spans.push(Span::styled("☆".repeat(empty), Style::default().fg(theme.muted)));
+    spans
+}
+
+/// helper function to create a centered rect using up certain percentage of the available rect `r`
+/// Gotten from ratatui examples
+fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
+
+impl AppState {
+    fn move_cursor_left(&mut self) {
+        let cursor_moved_left = self.character_index.saturating_sub(1);
+        self.character_index = self.clamp_cursor(cursor_moved_left);
+    }
+
+    fn move_cursor_right(&mut self) {
+        let cursor_moved_right = self.character_index.saturating_add(1);
+        self.character_index = self.clamp_cursor(cursor_moved_right);
+    }
+
+    fn enter_char(&mut self, new_char: char) {
+        self.user_input.insert(self.character_index, new_char);
+        self.move_cursor_right();
+    }
+
+    fn delete_char(&mut self) {
+        let is_not_cursor_leftmost = self.character_index != 0;
+        if is_not_cursor_leftmost {
+            let current_index = self.character_index;
+            let from_left_to_current_index = current_index - 1;
+
+            let before_char_to_delete = self.user_input.chars().take(from_left_to_current_index);
             let after_char_to_delete = self.user_input.chars().skip(current_index);
 
             self.user_input = before_char_to_delete.chain(after_char_to_delete).collect();
@@ -499,446 +1699,3396 @@ impl AppState {
         self.user_input.clear();
         self.reset_cursor();
     }
-}
 
+    /// Apply a line-editing key to `user_input`
+    fn edit_input(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char(c) => self.enter_char(c),
+            KeyCode::Backspace => self.delete_char(),
+            KeyCode::Left => self.move_cursor_left(),
+            KeyCode::Right => self.move_cursor_right(),
+            KeyCode::Home => self.reset_cursor(),
+            KeyCode::End => self.character_index = self.user_input.chars().count(),
+            _ => {}
+        }
+    }
 
+    /// Whether a popup currently owns the keyboard
+    fn show_popup(&self) -> bool {
+        self.input_mode != InputMode::Normal
+    }
 
-fn main() -> color_eyre::Result<()> {
-    color_eyre::install()?;
-    
-    let (movies, movie_info_cache) = load_movies()?;
-    if movies.is_empty() {
-        eprintln!("No movies found in movies/");
-        return Ok(());
+    /// A hand-set watched mark if there is one, otherwise whether the API counted a watch
+    fn is_watched(&self, idx: usize) -> bool {
+        let path = &self.movies[idx].path;
+        self.watched_marks.get(path)
+            .unwrap_or_else(|| self.movie_info_cache.get(path).is_some_and(|i| i.is_watched()))
     }
-    
-    let selected_index = RefCell::new(None);
-    let shuffle_queue = &SHUFFLE_QUEUE;
-    let should_exit = RefCell::new(false);
 
-    loop {
-        let info_map_ref = &movie_info_cache;
-        ratatui::run(|terminal| app(terminal, &movies, info_map_ref, &selected_index, shuffle_queue, &should_exit))?;
+    /// Distinct genres across the library, for the genre picker
+    fn available_genres(&self) -> Vec<String> {
+        let mut genres: Vec<String> = Vec::new();
+        for info in self.movie_info_cache.values() {
+            for genre in info.genres() {
+                if !genres.iter().any(|g| g.eq_ignore_ascii_case(genre)) {
+                    genres.push(genre.to_string());
+                }
+            }
+        }
+        genres.sort_by_key(|g| g.to_lowercase());
+        genres
+    }
 
-        // If the UI signaled to exit (Esc pressed), break the main loop and quit
-        if *should_exit.borrow() {
-            break;
+    /// Add `genre` to the genre filter, or take it out if it is already there
+    fn toggle_genre(&mut self, genre: &str) {
+        match self.genre_filter.iter().position(|g| g.eq_ignore_ascii_case(genre)) {
+            Some(pos) => {
+                self.genre_filter.remove(pos);
+            }
+            None => self.genre_filter.push(genre.to_string()),
         }
+        self.ensure_selection_visible();
+    }
 
-        let start_index = selected_index.borrow_mut().take();
-        let shuffle = shuffle_queue.load(Ordering::SeqCst);
+    /// Raise or lower the minimum rating by one step; lowering it to zero turns the filter off
+    fn adjust_min_rating(&mut self, up: bool) {
+        let current = self.min_rating.unwrap_or(0.0);
+        let next = if up { current + RATING_STEP } else { current - RATING_STEP };
+        self.min_rating = Some(next.min(10.0)).filter(|&r| r > 0.0);
+        self.ensure_selection_visible();
+    }
 
-        if let Some(start_index) = start_index {
-            play_movies_from_index(&movies, start_index, shuffle)?;
+    /// Apply the year range typed into the popup; an empty input removes the range
+    fn apply_year_input(&mut self) -> bool {
+        let input = self.user_input.trim();
+        let range = if input.is_empty() { Ok(None) } else { YearRange::parse(input).map(Some) };
+        match range {
+            Ok(range) => {
+                self.year_range = range;
+                self.year_input_error = None;
+                self.ensure_selection_visible();
+                true
+            }
+            Err(e) => {
+                self.year_input_error = Some(e);
+                false
+            }
         }
     }
-    
-    Ok(())
-}
 
-fn app(terminal: &mut DefaultTerminal, movies: &[MovieEntry], movie_info_map: &HashMap<PathBuf, MovieInfo>, selected_index: &RefCell<Option<usize>>, shuffle_queue: &AtomicBool, should_exit: &RefCell<bool>) -> std::io::Result<()> {
-    let mut state = AppState {
-        movies: movies.to_vec(),
-        selected: 0,
-        movie_info_cache: movie_info_map.clone(),
-        scroll_offset: 0,
-        show_popup: false,
-        user_input: String::new(),
-        input_mode: InputMode::Normal,
-        character_index: 0,
-    };
+    /// Whether any filter reset_filters drops is on
+    fn filters_active(&self) -> bool {
+        self.year_range.is_some() || self.min_rating.is_some() || !self.genre_filter.is_empty() || self.favorites_only
+    }
 
-    let mut last_input_time = Instant::now();
-    const TIMEOUT_SECONDS: u64 = 30;
+    /// Drop the genre, rating, year and favorites filters (the content filter has its own key)
+    fn reset_filters(&mut self) {
+        self.genre_filter.clear();
+        self.min_rating = None;
+        self.year_range = None;
+        self.favorites_only = false;
+        self.ensure_selection_visible();
+    }
 
-    loop {
-        let elapsed = last_input_time.elapsed();
-        terminal.draw(|frame| render(frame, &mut state, elapsed, TIMEOUT_SECONDS))?;
+    /// Open `mode` over the current one; `back` (or Esc) returns to where it was opened
+    fn open(&mut self, mode: InputMode) {
+        self.mode_stack.push(self.input_mode);
+        self.input_mode = mode;
+    }
+
+    /// Close the current mode and return to the one underneath
+    fn back(&mut self) {
+        self.input_mode = match self.mode_stack.pop() {
+            // The queue may have been replaced meanwhile (e.g. by loading a playlist)
+            Some(InputMode::Queue(row)) => InputMode::Queue(row.min(self.user_queue.len().saturating_sub(1))),
+            Some(mode) => mode,
+            None => InputMode::Normal,
+        };
+    }
+
+    /// Esc: drop whatever the current mode was typing or showing, then go back one layer
+    fn cancel(&mut self) {
+        match self.input_mode {
+            InputMode::Search => {
+                self.clear_input();
+                self.clear_search();
+            }
+            InputMode::YearFilter => {
+                self.year_input_error = None;
+                self.clear_input();
+            }
+            InputMode::Rating => {
+                self.rating_input_error = None;
+                self.clear_input();
+            }
+            InputMode::Command => {
+                self.command_error = None;
+                self.clear_input();
+            }
+            InputMode::RenameFile => {
+                self.rename_error = None;
+                self.clear_input();
+            }
+            InputMode::ExportList => {
+                self.export_error = None;
+                self.export_overwrite = false;
+                self.clear_input();
+            }
+            InputMode::PlaylistName | InputMode::M3uImport | InputMode::M3uExport => {
+                self.queue_notice = None;
+                self.clear_input();
+            }
+            _ => {}
+        }
+        self.back();
+    }
+
+    /// Flip the watched flag of the selected movie, through the API when it is reachable
+    fn toggle_watched(&mut self) {
+        if self.selected >= self.movies.len() {
+            return;
+        }
+        let watched = !self.is_watched(self.selected);
+        let path = self.movies[self.selected].path.clone();
+        // Sent to the API by sync_watched_marks, off the UI thread
+        self.watched_marks.set(path, watched, false);
+        let mark = if watched { "Marked as watched" } else { "Marked as unwatched" };
+        self.set_status(if api::is_configured() { mark.to_string() } else { format!("{} (no API: kept locally)", mark) });
+        self.ensure_selection_visible();
+    }
+
+    /// Show `message` in the taskbar for STATUS_MESSAGE_TIME
+    fn set_status(&mut self, message: impl Into<String>) {
+        self.status_message = Some((message.into(), Instant::now()));
+    }
+
+    /// Give the selected movie's file the name typed into the popup, carrying its metadata and
+    /// favorite star over to the new path until the next refresh resolves it again
+    fn apply_rename_input(&mut self) -> bool {
+        let Some(movie) = self.movies.get(self.selected) else {
+            return true;
+        };
+        let old = movie.path.clone();
+        let name = self.user_input.trim().to_string();
+        if old.file_name().and_then(|n| n.to_str()) == Some(name.as_str()) {
+            return true;
+        }
+        if !is_video(&old.with_file_name(&name)) {
+            self.rename_error = Some("keep a video file extension".to_string());
+            return false;
+        }
+        // mpv's saved position is keyed by the resolved path, which is gone after the rename
+        let old_resume = mpv::resume_path(&old);
+        let new = match rename::rename(&old, &name) {
+            Ok(new) => new,
+            Err(e) => {
+                self.rename_error = Some(e);
+                return false;
+            }
+        };
+        self.movies[self.selected].path = new.clone();
+        if let Some(info) = self.movie_info_cache.remove(&old) {
+            self.movie_info_cache.insert(new.clone(), info);
+        }
+        self.favorites.rename(&old, new.clone());
+        self.watched_marks.rename(&old, new.clone());
+        self.ratings.rename(&old, new.clone());
+        if sort::rename_in_order(&mut self.custom_order, &old, &new) {
+            sort::save_custom_order(&self.custom_order);
+        }
+        mpv::move_resume_position(&old_resume, &mpv::resume_path(&new));
+        if let Some(at) = self.resume_times.remove(&old) {
+            self.resume_times.insert(new.clone(), at);
+        }
+        for entry in self.user_queue.iter_mut().filter(|m| m.path == old) {
+            entry.path = new.clone();
+        }
+        self.rename_error = None;
+        self.set_status(format!("Renamed to {}", name));
+        self.refresh_order();
+        true
+    }
+
+    /// Write the movies as listed (sorted, filtered or searched) to `file`, as CSV or JSON by
+    /// its extension; an existing file is only replaced with `overwrite`
+    fn export_list(&mut self, file: &str, overwrite: bool) -> Result<(), export::WriteError> {
+        let file = m3u::expand_home(file);
+        let rows: Vec<export::Row> = self.visible_order().into_iter()
+            .map(|i| {
+                let movie = &self.movies[i];
+                let info = self.movie_info_cache.get(&movie.path);
+                let field = |f: fn(&MovieInfo) -> &Option<String>| info.and_then(|i| f(i).clone());
+                export::Row {
+                    title: display_title(movie, info),
+                    year: info.and_then(|i| i.year),
+                    genre: field(|i| &i.genre),
+                    director: field(|i| &i.director),
+                    runtime: field(|i| &i.runtime),
+                    rating: info.and_then(|i| i.rating),
+                    watch_count: info.and_then(|i| i.watch_count),
+                    path: movie.path.display().to_string(),
+                    codec: field(|i| &i.codec),
+                    resolution: field(|i| &i.resolution),
+                    file_size: field(|i| &i.file_size),
+                }
+            })
+            .collect();
+        export::write(&file, &rows, overwrite)?;
+        self.set_status(format!("Exported {} movies to {}", rows.len(), file.display()));
+        Ok(())
+    }
+
+    /// Rate the selected movie with the number typed into the popup, through the API when it is reachable
+    fn apply_rating_input(&mut self) -> bool {
+        if self.selected >= self.movies.len() {
+            return true;
+        }
+        let rating = match ratings::parse(&self.user_input) {
+            Ok(rating) => rating,
+            Err(e) => {
+                self.rating_input_error = Some(e);
+                return false;
+            }
+        };
+        let path = self.movies[self.selected].path.clone();
+        // Sent to the API by sync_ratings, off the UI thread
+        self.ratings.set(path, rating, false);
+        self.set_status(if api::is_configured() { format!("Rated {}", rating) } else { format!("Rated {} (no API: kept locally)", rating) });
+        self.rating_input_error = None;
+        self.apply_ratings();
+        true
+    }
+
+    /// Show the ratings given by hand in place of the API's
+    fn apply_ratings(&mut self) {
+        // Movies without info yet get theirs from probing first, with the rating put on then
+        for (path, rating) in self.ratings.iter() {
+            if let Some(info) = self.movie_info_cache.get_mut(path) {
+                info.rating = Some(rating);
+            }
+        }
+    }
+
+    /// Whether the movie at `idx` is shown under the active filters
+    fn is_visible(&self, idx: usize) -> bool {
+        let info = self.movie_info_cache.get(&self.movies[idx].path);
+        if self.watched_style == WatchedStyle::Hidden && self.is_watched(idx) {
+            return false;
+        }
+        if self.favorites_only && !self.favorites.contains(&self.movies[idx].path) {
+            return false;
+        }
+        if !self.genre_filter.is_empty()
+            && !info.is_some_and(|i| i.genres().any(|g| self.genre_filter.iter().any(|f| f.eq_ignore_ascii_case(g)))) {
+            return false;
+        }
+        if let Some(min) = self.min_rating {
+            match info.and_then(|i| i.rating) {
+                Some(rating) if rating < min => return false,
+                None if !self.include_unrated => return false,
+                _ => {}
+            }
+        }
+        // Movies with an unknown year are hidden while a year range is set
+        if let Some(range) = self.year_range
+            && !info.and_then(|i| i.year).is_some_and(|year| range.contains(year)) {
+            return false;
+        }
+        self.content_filter.allows(info)
+    }
+
+    /// The order the list shows: search results while searching, otherwise the sort order
+    fn list_order(&self) -> &[usize] {
+        self.search_hits.as_deref().unwrap_or(&self.order)
+    }
+
+    /// Re-run the search for the current input and jump to the best match
+    fn update_search(&mut self) {
+        self.search_pending = false;
+        if self.user_input.is_empty() {
+            self.clear_search();
+            return;
+        }
+        let candidates: Vec<(usize, Vec<String>)> = self.order.iter()
+            .copied()
+            .filter(|&i| self.is_visible(i))
+            .map(|i| {
+                let movie = &self.movies[i];
+                let info = self.movie_info_cache.get(&movie.path);
+                let file_name = movie.path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
+                let field = |f: fn(&MovieInfo) -> &Option<String>| info.and_then(|i| f(i).clone());
+                let texts: Vec<String> = match self.search_scope {
+                    SearchScope::Title => vec![display_title(movie, info), file_name],
+                    SearchScope::Director => field(|i| &i.director).into_iter().collect(),
+                    SearchScope::Genre => field(|i| &i.genre).into_iter().collect(),
+                    SearchScope::All => [Some(display_title(movie, info)), Some(file_name), field(|i| &i.director), field(|i| &i.genre), field(|i| &i.plot)]
+                        .into_iter()
+                        .flatten()
+                        .collect(),
+                };
+                (i, texts)
+            })
+            .collect();
+        let hits: Vec<usize> = if self.search_regex {
+            // An unfinished pattern leaves the last results in place
+            match search::compile(&self.user_input) {
+                Ok(pattern) => {
+                    let hits = search::matching(&pattern, &candidates);
+                    self.search_pattern = Some(pattern);
+                    self.search_error = None;
+                    hits
+                }
+                Err(_) => {
+                    self.search_error = Some("invalid pattern".to_string());
+                    return;
+                }
+            }
+        } else {
+            self.search_pattern = None;
+            search::rank(&self.user_input, &candidates).into_iter().map(|h| h.index).collect()
+        };
+        self.selected = hits.first().copied().unwrap_or(self.movies.len());
+        self.search_hits = Some(hits);
+        self.search_query = self.user_input.clone();
+    }
+
+    /// Catch the results up with what was typed before acting on them
+    fn flush_search(&mut self) {
+        if self.search_pending {
+            self.update_search();
+        }
+    }
+
+    fn clear_search(&mut self) {
+        self.search_pending = false;
+        self.search_hits = None;
+        self.search_pattern = None;
+        self.search_error = None;
+        self.ensure_selection_visible();
+    }
+
+    fn select_next(&mut self) {
+        // From "Random Movie" (not part of the order) the list wraps to the top
+        let start = self.list_order().iter().position(|&i| i == self.selected).map_or(0, |p| p + 1);
+        // Past the last visible movie comes the "Random Movie" entry
+        self.selected = self.list_order()[start..].iter()
+            .copied()
+            .find(|&i| self.is_selectable(i))
+            .unwrap_or(self.movies.len());
+    }
+
+    fn select_prev(&mut self) {
+        let order = self.list_order();
+        let end = order.iter().position(|&i| i == self.selected).unwrap_or(order.len());
+        // Before the first visible movie wraps around to the "Random Movie" entry
+        self.selected = order[..end].iter()
+            .rev()
+            .copied()
+            .find(|&i| self.is_selectable(i))
+            .unwrap_or(self.movies.len());
+    }
+
+    /// Move `steps` entries up or down, wrapping around like single steps do
+    fn select_steps(&mut self, steps: usize, down: bool) {
+        // A full lap (every selectable movie plus "Random Movie") ends where it started
+        let lap = self.list_order().iter().filter(|&&i| self.is_selectable(i)).count() + 1;
+        for _ in 0..steps % lap {
+            if down { self.select_next() } else { self.select_prev() }
+        }
+    }
+
+    /// Move a screenful up or down without wrapping; past the last movie comes "Random Movie"
+    fn select_page(&mut self, down: bool) {
+        let order: Vec<usize> = self.list_order().iter().copied().filter(|&i| self.is_selectable(i)).collect();
+        let pos = order.iter().position(|&i| i == self.selected).unwrap_or(order.len());
+        let step = self.page_size.max(1);
+        let target = if down { pos + step } else { pos.saturating_sub(step) };
+        self.selected = order.get(target).copied().unwrap_or(self.movies.len());
+    }
+
+    fn select_first(&mut self) {
+        self.selected = self.list_order().iter()
+            .copied()
+            .find(|&i| self.is_selectable(i))
+            .unwrap_or(self.movies.len());
+    }
+
+    /// Whether the group of the movie at `idx` is folded away (only in the grouped list)
+    fn in_collapsed_group(&self, idx: usize) -> bool {
+        self.shows_groups() && self.collapsed.contains(self.group_label(idx).as_ref())
+    }
+
+    /// The first visible movie of the group of `idx`; it stands for the header of a collapsed group
+    fn group_head(&self, idx: usize) -> Option<usize> {
+        let group = self.group_label(idx);
+        self.order.iter().copied().find(|&i| self.group_label(i) == group && self.is_visible(i))
+    }
+
+    /// Whether the cursor can rest on the movie at `idx`: visible, and not folded into a header
+    fn is_selectable(&self, idx: usize) -> bool {
+        self.is_visible(idx) && (!self.in_collapsed_group(idx) || self.group_head(idx) == Some(idx))
+    }
+
+    /// What a mouse event does in the list: the wheel moves the selection (or scrolls the info
+    /// panel under the pointer), a click selects a row and a click on the selected row plays it
+    fn mouse_action(&mut self, mouse: MouseEvent) -> Option<Action> {
+        let at = Position { x: mouse.column, y: mouse.row };
+        let over_info = self.info_area.is_some_and(|area| area.contains(at));
+        match mouse.kind {
+            MouseEventKind::ScrollDown if over_info => Some(Action::ScrollInfoDown),
+            MouseEventKind::ScrollUp if over_info => Some(Action::ScrollInfoUp),
+            MouseEventKind::ScrollDown => Some(Action::NavigateDown),
+            MouseEventKind::ScrollUp => Some(Action::NavigateUp),
+            MouseEventKind::Down(MouseButton::Left) if self.list_area.contains(at) => {
+                // The first row sits below the top border
+                let row = at.y.checked_sub(self.list_area.y + 1)? as usize;
+                let target = (*self.list_rows.get(row)?)?;
+                if target == self.selected {
+                    Some(Action::Play)
+                } else {
+                    self.selected = target;
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether digits typed for a count are waiting for their movement
+    fn count_active(&self) -> bool {
+        !self.count.is_empty() && self.count_at.is_some_and(|at| at.elapsed() < COUNT_RESET)
+    }
+
+    /// Whether the type-ahead prefix is still being typed
+    fn type_ahead_active(&self) -> bool {
+        self.type_ahead_at.is_some_and(|at| at.elapsed() < TYPE_AHEAD_RESET)
+    }
+
+    /// Jump to the next title starting with the typed prefix. A character not bound to an action
+    /// starts the prefix (the JumpToTitle key starts an empty one, for titles starting with bound
+    /// letters) and any character typed shortly after extends it. Returns whether the key was
+    /// used for this.
+    fn type_ahead(&mut self, key: &KeyEvent, action: Option<Action>) -> bool {
+        let KeyCode::Char(c) = key.code else {
+            return false;
+        };
+        if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+            return false;
+        }
+        if !self.type_ahead_active() {
+            if action.is_some() || c == ' ' {
+                return false;
+            }
+            self.type_ahead.clear();
+        }
+        self.type_ahead.extend(c.to_lowercase());
+        self.type_ahead_at = Some(Instant::now());
+
+        let order = self.list_order();
+        let pos = order.iter().position(|&i| i == self.selected);
+        // A new prefix looks past the current title; a longer one may still match it
+        let start = match pos {
+            Some(p) if self.type_ahead.chars().count() == 1 => p + 1,
+            Some(p) => p,
+            None => 0,
+        };
+        let starts_with = |idx: usize| {
+            let movie = &self.movies[idx];
+            let file_name = movie.path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            let title = display_title(movie, self.movie_info_cache.get(&movie.path));
+            [file_name, title.as_str()].iter().any(|name| name.to_lowercase().starts_with(&self.type_ahead))
+        };
+        // Wrap around to the top when nothing further down matches
+        let found = order[start..].iter()
+            .chain(&order[..start])
+            .copied()
+            .find(|&i| self.is_selectable(i) && starts_with(i));
+        if let Some(idx) = found {
+            self.selected = idx;
+        }
+        true
+    }
+
+    /// Turn the hand-built queue into the play queue, skipping files no longer in the list or on
+    /// disk; when none are left the queue stays as it is
+    fn take_user_queue(&mut self) -> Option<PlayQueue> {
+        let order: Vec<usize> = self.user_queue.iter()
+            .filter(|entry| entry.path.exists())
+            .filter_map(|entry| self.position_of(&entry.path))
+            .collect();
+        if order.is_empty() {
+            self.set_status("None of the queued files exist");
+            return None;
+        }
+        self.user_queue.clear();
+        Some(PlayQueue::new(order))
+    }
+
+    /// The list entry playing `path`, either as its main file or as one of its versions
+    fn position_of(&self, path: &Path) -> Option<usize> {
+        self.movies.iter().position(|m| m.path == path || m.versions.iter().any(|v| v == path))
+    }
+
+    /// Save the queue under the name typed into the popup
+    fn save_playlist(&mut self) -> bool {
+        let paths: Vec<PathBuf> = self.user_queue.iter().map(|m| m.path.clone()).collect();
+        match playlists::save(&self.user_input, &paths) {
+            Ok(name) => {
+                self.queue_notice = Some(format!("Saved as \"{}\"", name));
+                true
+            }
+            Err(e) => {
+                self.queue_notice = Some(e);
+                false
+            }
+        }
+    }
+
+    /// Replace the queue with a saved playlist, skipping files that are no longer in the library
+    fn load_playlist(&mut self, name: &str) {
+        let paths = match playlists::load(name) {
+            Ok(paths) => paths,
+            Err(e) => {
+                self.queue_notice = Some(e);
+                return;
+            }
+        };
+        self.fill_queue(&format!("\"{}\"", name), &paths, false);
+    }
+
+    /// Replace the queue with the entries of an m3u file, matched by path or else by file name
+    fn import_m3u(&mut self) -> bool {
+        let file = m3u::expand_home(&self.user_input);
+        match m3u::import(&file) {
+            Ok(paths) => {
+                let label = file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                self.fill_queue(&label, &paths, true);
+                true
+            }
+            Err(e) => {
+                self.queue_notice = Some(e);
+                false
+            }
+        }
+    }
+
+    fn export_m3u(&mut self) -> bool {
+        let file = m3u::expand_home(&self.user_input);
+        let paths: Vec<PathBuf> = self.user_queue.iter().map(|m| m.path.clone()).collect();
+        match m3u::export(&file, &paths) {
+            Ok(()) => {
+                self.queue_notice = Some(format!("Wrote {}", file.display()));
+                true
+            }
+            Err(e) => {
+                self.queue_notice = Some(e);
+                false
+            }
+        }
+    }
+
+    /// Replace the queue with the movies playing `paths`, noting the ones not in the library
+    fn fill_queue(&mut self, source: &str, paths: &[PathBuf], match_file_name: bool) {
+        let by_file_name = |path: &PathBuf| {
+            let name = path.file_name()?;
+            self.movies.iter().position(|m| {
+                std::iter::once(&m.path).chain(&m.versions).any(|p| p.file_name() == Some(name))
+            })
+        };
+        let (found, missing): (Vec<_>, Vec<_>) = paths.iter()
+            .map(|path| (path, self.position_of(path).or_else(|| if match_file_name { by_file_name(path) } else { None })))
+            .partition(|(_, idx)| idx.is_some());
+        self.user_queue = found.into_iter().filter_map(|(_, idx)| idx).map(|idx| self.movies[idx].clone()).collect();
+        self.queue_notice = Some(if missing.is_empty() {
+            format!("Loaded {}", source)
+        } else {
+            let names: Vec<String> = missing.iter()
+                .map(|(path, _)| path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| path.display().to_string()))
+                .collect();
+            format!("Loaded {}, skipped {} missing: {}", source, missing.len(), names.join(", "))
+        });
+    }
+
+    /// Collapse or expand the group of the selected movie
+    fn toggle_group(&mut self) {
+        if self.selected >= self.movies.len() || !self.shows_groups() {
+            return;
+        }
+        let group = self.group_label(self.selected).to_string();
+        if !self.collapsed.remove(&group) {
+            self.collapsed.insert(group);
+        }
+        self.ensure_selection_visible();
+    }
+
+    /// Pin the selected movie's group above the others, or unpin it; the group and whether
+    /// it is pinned now
+    fn toggle_pin(&mut self) -> Option<(String, bool)> {
+        if self.selected >= self.movies.len() || !self.shows_groups() {
+            return None;
+        }
+        let group = self.group_label(self.selected).to_string();
+        let pinned = sort::toggle_pin(&mut self.pinned_groups, &group);
+        self.refresh_order();
+        Some((group, pinned))
+    }
+
+    /// Move the selection off a hidden movie, to the next visible one
+    fn ensure_selection_visible(&mut self) {
+        if self.selected < self.movies.len() && !self.is_visible(self.selected) {
+            let pos = self.order.iter().position(|&i| i == self.selected).unwrap_or(0);
+            self.selected = self.order[pos..].iter()
+                .chain(&self.order[..pos])
+                .copied()
+                .find(|&i| self.is_visible(i))
+                .unwrap_or(self.movies.len());
+        }
+        // Inside a collapsed group the cursor sits on the header
+        if self.selected < self.movies.len() && !self.is_selectable(self.selected) {
+            self.selected = self.group_head(self.selected).unwrap_or(self.movies.len());
+        }
+    }
+
+    /// Recompute the display order after the sort mode or custom order changed
+    fn refresh_order(&mut self) {
+        self.order = if self.flat_list && self.sort_mode == SortMode::Name {
+            sort::alphabetical(&self.movies, &self.movie_info_cache)
+        } else {
+            sort::display_order(&self.movies, self.sort_mode, &self.custom_order, &self.movie_info_cache)
+        };
+        // Movies with a saved position move out of their group into "Continue Watching"
+        self.in_progress.clear();
+        if self.resume_enabled && self.sort_mode.is_grouped() && !self.flat_list {
+            let mut started: Vec<(usize, SystemTime)> = self.order.iter()
+                .filter_map(|&i| self.resume_times.get(&self.movies[i].path).map(|&at| (i, at)))
+                .collect();
+            started.sort_by_key(|&(_, at)| std::cmp::Reverse(at));
+            self.in_progress = started.into_iter().map(|(i, _)| i).collect();
+            self.order.retain(|i| !self.in_progress.contains(i));
+            self.order.splice(0..0, self.in_progress.iter().copied());
+        }
+        // Pinned groups go above everything else, in pin order
+        if self.sort_mode.is_grouped() && !self.flat_list {
+            let mut order = std::mem::take(&mut self.order);
+            sort::pin_groups(&mut order, &self.pinned_groups, |i| self.group_label(i));
+            self.order = order;
+        }
+    }
+
+    /// Whether the list is drawn with group headers (not in flat sort modes, the flat list or search results)
+    fn shows_groups(&self) -> bool {
+        self.sort_mode.is_grouped() && !self.flat_list && self.search_hits.is_none()
+    }
+
+    /// The group header a movie is listed under
+    fn group_label(&self, idx: usize) -> Cow<'_, str> {
+        let info = || self.movie_info_cache.get(&self.movies[idx].path);
+        if self.in_progress.contains(&idx) {
+            Cow::Borrowed(CONTINUE_WATCHING_GROUP)
+        } else if self.sort_mode == SortMode::Genre {
+            Cow::Borrowed(sort::genre_group(info()))
+        } else if self.sort_mode == SortMode::Decade {
+            Cow::Owned(sort::decade_group(info()))
+        } else {
+            Cow::Borrowed(&self.movies[idx].group_name)
+        }
+    }
+
+    /// Move the selected movie one place up or down in the custom order
+    fn move_in_custom_order(&mut self, up: bool) {
+        if self.selected >= self.movies.len() {
+            return;
+        }
+        // Trade places with the movie shown above or below, skipping the ones filtered out
+        let visible = self.visible_order();
+        let Some(pos) = visible.iter().position(|&i| i == self.selected) else {
+            return;
+        };
+        let target = if up { pos.checked_sub(1) } else { Some(pos + 1) };
+        let Some(&target) = target.and_then(|t| visible.get(t)) else {
+            return;
+        };
+        if sort::swap_in_order(&mut self.custom_order, &self.movies[self.selected].path, &self.movies[target].path) {
+            sort::save_custom_order(&self.custom_order);
+            self.refresh_order();
+        }
+    }
+
+    /// Visible movies in display order
+    fn visible_order(&self) -> Vec<usize> {
+        self.list_order().iter().copied().filter(|&i| self.is_visible(i)).collect()
+    }
+
+    /// A random visible movie for the Random Movie entry and the idle auto-play
+    fn random_visible(&self) -> Option<usize> {
+        let mut visible = self.visible_order();
+        if self.random_unwatched_only && visible.iter().any(|&idx| !self.is_watched(idx)) {
+            visible.retain(|&idx| !self.is_watched(idx));
+        }
+        let mut rng = rand::thread_rng();
+        if !self.favor_unwatched {
+            return visible.choose(&mut rng).copied();
+        }
+        visible.choose_weighted(&mut rng, |&idx| self.random_weight(idx)).ok().copied()
+    }
+
+    /// How likely a movie is to be the random pick: 1/(watch count + 1)
+    fn random_weight(&self, idx: usize) -> f64 {
+        let path = &self.movies[idx].path;
+        let count = self.movie_info_cache.get(path).and_then(|i| i.watch_count);
+        // A mark made with the watched key wins over the API's count
+        let count = match (self.watched_marks.get(path), count) {
+            (Some(false), _) => Some(0),
+            (Some(true), count) => Some(count.unwrap_or(0).max(1)),
+            (None, count) => count,
+        };
+        match count {
+            Some(count) => 1.0 / (count.max(0) as f64 + 1.0),
+            None => UNKNOWN_WATCH_WEIGHT,
+        }
+    }
+
+    /// Pick a random movie and a second one related to it, or any other movie if nothing is related
+    fn pick_double_feature(&self) -> Option<(usize, usize)> {
+        let visible = self.visible_order();
+        let mut rng = rand::thread_rng();
+        let first = *visible.choose(&mut rng)?;
+        let others: Vec<usize> = visible.into_iter().filter(|&i| i != first).collect();
+        let first_info = self.movie_info_cache.get(&self.movies[first].path);
+        let related: Vec<usize> = others.iter()
+            .copied()
+            .filter(|&i| match (first_info, self.movie_info_cache.get(&self.movies[i].path)) {
+                (Some(a), Some(b)) => a.related_by(b).is_some(),
+                _ => false,
+            })
+            .collect();
+        let second = *related.choose(&mut rng).or_else(|| others.choose(&mut rng))?;
+        Some((first, second))
+    }
+
+    /// Switch to the library with fetched metadata applied, keeping the selection on the same file
+    fn apply_metadata_update(&mut self, update: MetadataUpdate) -> Library {
+        let selected_path = self.movies.get(self.selected).map(|m| m.path.clone());
+        let refreshed = update.movies.is_some();
+        let mut movies = update.movies.unwrap_or_else(|| self.movies.clone());
+        let mut info_map = update.info_map;
+        apply_metadata(&mut movies, &mut info_map);
+        // Keep file-level info already probed for movies the API does not know
+        for (path, info) in self.movie_info_cache.drain() {
+            info_map.entry(path).or_insert(info);
+        }
+
+        if let Some(queue) = &mut self.play_queue {
+            queue.remap(&self.movies, &movies);
+        }
+        self.selected = selected_path
+            .and_then(|p| movies.iter().position(|m| m.path == p))
+            .unwrap_or(movies.len());
+        self.movies = movies;
+        self.movie_info_cache = info_map;
+        self.resume_times = update.resume_times;
+        self.apply_ratings();
+        self.metadata_cached_at = update.cached_at;
+        self.api_timed_out = update.timed_out;
+        self.metadata_loading = false;
+        self.refresh_order();
+        self.ensure_selection_visible();
+        if self.search_hits.is_some() {
+            self.update_search();
+        }
+        let api_status = match update.cached_at {
+            _ if update.fetched => None,
+            Some(fetched_at) => Some(format!("API unreachable: using the cache from {}", fetched_at.format("%Y-%m-%d %H:%M"))),
+            None => Some("API unreachable: showing files only".to_string()),
+        };
+        match (refreshed, api_status) {
+            (true, Some(api_status)) => self.set_status(format!("Refreshed ({} movies) | {}", self.movies.len(), api_status)),
+            (true, None) => self.set_status(format!("Refreshed ({} movies)", self.movies.len())),
+            (false, Some(api_status)) => self.set_status(api_status),
+            (false, None) => {}
+        }
+
+        Library {
+            movies: self.movies.clone(),
+            info_map: self.movie_info_cache.clone(),
+        }
+    }
+
+    /// Delete the selected movie's file after the y/n question and take it off the list, which
+    /// moves on to the next movie. Of collapsed duplicates only the file shown goes; the next
+    /// version takes its place. Returns the deleted path with the new library.
+    fn delete_selected(&mut self) -> Option<(PathBuf, Library)> {
+        let idx = self.selected;
+        let path = self.movies.get(idx)?.path.clone();
+        let removed = match delete::delete(&path, self.delete_sidecars) {
+            Ok(removed) => removed,
+            Err(e) => {
+                self.set_status(e);
+                return None;
+            }
+        };
+        let mut movies = self.movies.clone();
+        let next = if movies[idx].versions.is_empty() {
+            movies.remove(idx);
+            let order = self.list_order();
+            order.iter().position(|&i| i == idx)
+                .and_then(|p| order.get(p + 1).or_else(|| order.get(p.checked_sub(1)?)))
+                .map(|&i| self.movies[i].path.clone())
+        } else {
+            let entry = &mut movies[idx];
+            entry.path = entry.versions.remove(0);
+            if let Some(info) = self.movie_info_cache.get(&path).cloned() {
+                self.movie_info_cache.entry(entry.path.clone()).or_insert(info);
+            }
+            Some(entry.path.clone())
+        };
+        self.movie_info_cache.remove(&path);
+        self.user_queue.retain(|m| m.path != path);
+        self.favorites.remove(&path);
+        self.watched_marks.remove(&path);
+        self.ratings.remove(&path);
+        self.resume_times.remove(&path);
+        let before = self.custom_order.len();
+        self.custom_order.retain(|p| *p != path);
+        if self.custom_order.len() < before {
+            sort::save_custom_order(&self.custom_order);
+        }
+        if let Some(queue) = &mut self.play_queue {
+            queue.remap(&self.movies, &movies);
+        }
+        // Search results keep their ranking, minus the deleted movie
+        if let Some(hits) = &mut self.search_hits
+            && movies.len() < self.movies.len() {
+            hits.retain(|&i| i != idx);
+            hits.iter_mut().filter(|i| **i > idx).for_each(|i| *i -= 1);
+        }
+        self.movies = movies;
+        self.selected = next.and_then(|p| self.movies.iter().position(|m| m.path == p)).unwrap_or(self.movies.len());
+        self.refresh_order();
+        self.ensure_selection_visible();
+
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let mut status = format!("Deleted {}", name);
+        if !removed.is_empty() {
+            status.push_str(&format!(" and {} subtitle/companion file(s)", removed.len()));
+        }
+        self.set_status(status);
+        Some((path, Library {
+            movies: self.movies.clone(),
+            info_map: self.movie_info_cache.clone(),
+        }))
+    }
+
+    /// True while a first Esc press is waiting for its confirming second press
+    fn exit_pending(&self) -> bool {
+        match (self.exit_esc_window, self.last_esc) {
+            (Some(window), Some(pressed)) => pressed.elapsed() < window,
+            _ => false,
+        }
+    }
+}
+
+
+
+fn main() -> color_eyre::Result<()> {
+    color_eyre::install()?;
+    logging::init(env::args().any(|a| a == "--verbose" || a == "-v"));
+
+    let config = config::load().map_err(|e| color_eyre::eyre::eyre!(e))?;
+    let keymap = Keymap::from_config(&config.keys, config.vim_keys.unwrap_or(false))
+        .map_err(|e| color_eyre::eyre::eyre!("Invalid [keys] in config: {}", e))?;
+    let theme = Theme::from_config(config.theme.unwrap_or_default(), &config.colors)
+        .map_err(|e| color_eyre::eyre::eyre!("Invalid [colors] in config: {}", e))?;
+    if let Some(url) = &config.api_url {
+        api::set_configured_base(url);
+    }
+    if let Some(secs) = config.api_timeout_secs.filter(|&secs| secs > 0) {
+        api::set_timeout(Duration::from_secs(secs));
+    }
+    let default_retry = api::RetryPolicy::default();
+    api::set_retry_policy(api::RetryPolicy {
+        retries: config.api_retries.unwrap_or(default_retry.retries),
+        base_delay: config.api_retry_delay_ms.map_or(default_retry.base_delay, Duration::from_millis),
+    });
+    set_video_extensions(&config.video_extensions, config.replace_video_extensions);
+    let _ = GROUP_ORDER.set(config.group_order.clone());
+    let movie_roots = movie_roots(&config);
+    // --player wins over the config file
+    let player = match cli_values("--player").last() {
+        Some(name) => Player::parse(name).map_err(|e| color_eyre::eyre::eyre!("Invalid --player: {}", e))?,
+        None => config.player.unwrap_or_default(),
+    };
+
+    // `player doctor` checks the setup headlessly and exits
+    if env::args().nth(1).as_deref() == Some("doctor") {
+        std::process::exit(doctor::run(&movie_roots, player));
+    }
+
+    if !player.is_installed() {
+        return Err(color_eyre::eyre::eyre!(
+            "{} not found on PATH (install it, or choose another player with --player or `player` in the config file)",
+            player
+        ));
+    }
+
+    if let Some(missing) = movie_roots.iter().find(|root| !root.is_dir()) {
+        return Err(color_eyre::eyre::eyre!(
+            "Movies directory {} does not exist (pass --movies-dir or set MOVIES_DIR)",
+            missing.display()
+        ));
+    }
+    
+    // --autoplay-timeout wins over the config file; 0 turns the idle auto-play off
+    let autoplay_timeout = match cli_values("--autoplay-timeout").last() {
+        Some(secs) => secs.trim().parse::<u64>().map_err(|_| color_eyre::eyre::eyre!("Invalid --autoplay-timeout: {} (expected seconds)", secs))?,
+        None => config.autoplay_timeout.unwrap_or(DEFAULT_AUTOPLAY_TIMEOUT),
+    };
+    let autoplay_timeout = (autoplay_timeout > 0).then_some(autoplay_timeout);
+
+    // --no-cache always uses the live API, never the cached metadata
+    let use_cache = !env::args().any(|a| a == "--no-cache");
+    let mut movies = scan_movies(&movie_roots)?;
+    if movies.is_empty() {
+        let roots: Vec<String> = movie_roots.iter().map(|r| r.display().to_string()).collect();
+        eprintln!("No movies found in {}", roots.join(", "));
+        return Ok(());
+    }
+    // The list opens straight away with file info probed on earlier runs; metadata streams in from a background fetch
+    let mut movie_info_cache: HashMap<PathBuf, MovieInfo> = movies.iter()
+        .filter_map(|m| Some((m.path.clone(), probe_cache::get(&m.path)?)))
+        .collect();
+    let (metadata_tx, metadata_rx) = mpsc::channel();
+    let scanned = movies.clone();
+    let scanned_roots = movie_roots.clone();
+    std::thread::spawn(move || {
+        let _ = metadata_tx.send(fetch_metadata(&scanned, &scanned_roots, use_cache));
+    });
+    let folder_changes = if config.watch_folders.unwrap_or(false) { watcher::watch(&movie_roots) } else { None };
+    // Probe in list order: workers take paths from the end
+    let probe_rx = (config.prewarm_probe.unwrap_or(false) && backend::ffprobe_installed())
+        .then(|| prewarm_probes(movies.iter().rev().map(|m| m.path.clone()).collect()));
+    
+    let selected_index = RefCell::new(None);
+    let shuffle_queue = &SHUFFLE_QUEUE;
+    let should_exit = RefCell::new(false);
+    // START_AT accepts seconds ("90", "1:30"), a percentage ("10%") or a chapter ("#2")
+    let start_at = match env::var("START_AT").ok().filter(|v| !v.trim().is_empty()) {
+        Some(spec) => Some(StartSpec::parse(&spec).map_err(|e| color_eyre::eyre::eyre!("Invalid START_AT: {}", e))?),
+        None => None,
+    };
+
+    // Toggles, sort and filters as they were left last time win over the config file, unless
+    // its setting was edited since
+    let saved = UiState::load();
+    let config_seen = ui_state::ConfigSeen {
+        shuffle: config.shuffle,
+        autoplay_next: config.autoplay_next,
+        idle_autoplay: config.idle_autoplay,
+        subtitles: config.subtitles,
+        high_contrast: config.high_contrast,
+        repeat: config.repeat,
+        info_percent: config.info_panel_percent,
+    };
+    let seen = &saved.config;
+    for (toggle, value) in [
+        (&SHUFFLE_QUEUE, ui_state::restore(saved.shuffle, config.shuffle, seen.shuffle)),
+        (&AUTO_PLAY_NEXT, ui_state::restore(saved.autoplay_next, config.autoplay_next, seen.autoplay_next)),
+        (&IDLE_AUTOPLAY, ui_state::restore(saved.idle_autoplay, config.idle_autoplay, seen.idle_autoplay)),
+        (&SUBTITLES, ui_state::restore(saved.subtitles, config.subtitles, seen.subtitles)),
+        (&HIGH_CONTRAST, ui_state::restore(saved.high_contrast, config.high_contrast, seen.high_contrast)),
+    ] {
+        if let Some(value) = value {
+            toggle.store(value, Ordering::SeqCst);
+        }
+    }
+
+    let mut session = Session {
+        content_filter: ContentFilter::from_env(),
+        playback: PlaybackOptions {
+            player,
+            start_at,
+            chapter_skip: ChapterSkip::from_env(),
+            player_args: config.player_args,
+            replace_default_args: config.replace_default_args,
+            resume: config.resume.unwrap_or(true),
+            from_start: false,
+            volume: config.volume.map(|v| v.min(u32::from(MAX_VOLUME)) as u8),
+            speed: config.speed.map(|s| s.clamp(MIN_SPEED, MAX_SPEED)).filter(|&s| s != 1.0),
+            repeat: ui_state::restore(saved.repeat, config.repeat, seen.repeat).unwrap_or_default(),
+            now_playing: config.now_playing.unwrap_or(true),
+            watch_threshold: WatchThreshold {
+                secs: config.watched_after_secs.unwrap_or(WatchThreshold::default().secs),
+                percent: config.watched_at_percent.unwrap_or(WatchThreshold::default().percent).min(100),
+            },
+            slang: config.slang.filter(|s| !s.trim().is_empty()),
+            alang: config.alang.filter(|s| !s.trim().is_empty()),
+            theme,
+            picker: if config.posters.unwrap_or(true) { posters::detect() } else { None },
+        },
+        autoplay_scope: AutoplayScope::from_env(),
+        keymap,
+        autoplay_timeout,
+        metadata_rx: Some(metadata_rx),
+        watched_sync: None,
+        ratings_sync: None,
+        api_jobs: Vec::new(),
+        use_cache,
+        folder_changes,
+        mouse: config.mouse.unwrap_or(true),
+        info_percent: ui_state::restore(saved.info_percent, config.info_panel_percent, seen.info_percent).unwrap_or(DEFAULT_INFO_PERCENT).clamp(MIN_INFO_PERCENT, MAX_INFO_PERCENT),
+        config_seen,
+        confirm_exit: config.confirm_exit.unwrap_or(false),
+        probe_rx,
+        movie_roots,
+        custom_order: sort::load_custom_order(&movies),
+        watched_marks: WatchedMarks::load(),
+        ratings: UserRatings::load(),
+        favorites: Favorites::load(),
+        include_unrated: config.include_unrated.unwrap_or(true),
+        favor_unwatched: config.favor_unwatched.unwrap_or(true),
+        random_unwatched_only: config.random_unwatched_only.unwrap_or(false),
+        delete_sidecars: config.delete_sidecars.unwrap_or(false),
+        delete_from_api: config.delete_from_api.unwrap_or(true),
+        last_selected: saved.selected,
+        scroll_offset: saved.scroll_offset,
+        sort_mode: saved.sort_mode,
+        genre_filter: saved.genre_filter,
+        min_rating: saved.min_rating,
+        year_range: saved.year_range,
+        favorites_only: saved.favorites_only,
+        flat_list: saved.flat_list,
+        pinned_groups: saved.pinned_groups,
+        ..Default::default()
+    };
+
+    loop {
+        let info_map_ref = &movie_info_cache;
+        ratatui::run(|terminal| app(terminal, &movies, info_map_ref, &selected_index, shuffle_queue, &should_exit, &mut session))?;
+        probe_cache::save();
+
+        // Metadata that arrived while the list was open replaces the scanned library
+        if let Some(library) = session.library_update.take() {
+            movies = library.movies;
+            movie_info_cache = library.info_map;
+        }
+
+        // If the UI signaled to exit (Esc pressed), break the main loop and quit
+        if *should_exit.borrow() {
+            UiState {
+                selected: session.last_selected.clone(),
+                scroll_offset: session.scroll_offset,
+                sort_mode: session.sort_mode,
+                genre_filter: session.genre_filter.clone(),
+                min_rating: session.min_rating,
+                year_range: session.year_range,
+                favorites_only: session.favorites_only,
+                flat_list: session.flat_list,
+                pinned_groups: session.pinned_groups.clone(),
+                shuffle: Some(check_shuffle_queue()),
+                autoplay_next: Some(check_auto_play_next()),
+                idle_autoplay: Some(check_idle_autoplay()),
+                subtitles: Some(check_subtitles()),
+                high_contrast: Some(check_high_contrast()),
+                repeat: Some(session.playback.repeat),
+                info_percent: Some(session.info_percent),
+                config: session.config_seen.clone(),
+            }.save();
+            break;
+        }
+
+        if let Some((target, title)) = session.trailer.take() {
+            session.status = play_trailer(&target, &title, &session.playback)?;
+            continue;
+        }
+
+        let start_index = selected_index.borrow_mut().take();
+        let shuffle = shuffle_queue.load(Ordering::SeqCst);
+
+        // A version picked from the submenu becomes the entry's primary file
+        if let (Some(start_index), Some(path)) = (start_index, session.version_choice.take()) {
+            let entry = &mut movies[start_index];
+            if let Some(pos) = entry.versions.iter().position(|v| *v == path) {
+                entry.versions[pos] = std::mem::replace(&mut entry.path, path);
+            }
+        }
+
+        if let Some(start_index) = start_index {
+            // Picking the "up next" title continues the pending queue, anything else starts a new one
+            let mut queue = match (session.double_feature.take(), session.play_queue.take()) {
+                (Some((first, second)), _) => PlayQueue::double_feature(first, second),
+                (None, Some(queue)) if queue.next_index() == Some(start_index) => queue,
+                _ => {
+                    // Follow the on-screen order; titles hidden by the content filter are never queued
+                    let pool: Vec<usize> = if session.autoplay_scope == AutoplayScope::Filter {
+                        session.visible.clone()
+                    } else {
+                        sort::display_order(&movies, session.sort_mode, &session.custom_order, &movie_info_cache)
+                            .into_iter()
+                            .filter(|&i| session.content_filter.allows(movie_info_cache.get(&movies[i].path)))
+                            .collect()
+                    };
+                    PlayQueue::new(build_play_order(&movies, &pool, start_index, shuffle, session.autoplay_scope))
+                }
+            };
+            session.status = play_movies_from_index(&movies, &movie_info_cache, &mut queue, &session.playback)?;
+            // mpv saved (or dropped) the positions of the titles it played
+            for &i in queue.order.iter().take(queue.position) {
+                update_resume_time(&mut session.resume_times, &movies[i].path);
+            }
+            session.playback.from_start = false;
+            session.play_queue = (!queue.is_finished()).then_some(queue);
+        }
+    }
+    Ok(())
+}
+
+/// Read the saved position of `path` again after it was played
+fn update_resume_time(resume_times: &mut HashMap<PathBuf, SystemTime>, path: &Path) {
+    match mpv::resume_saved_at(path) {
+        Some(at) => resume_times.insert(path.to_path_buf(), at),
+        None => resume_times.remove(path),
+    };
+}
+
+/// Mouse reporting while the list is open, handed back to the terminal when dropped
+struct MouseCapture;
+
+impl MouseCapture {
+    fn enable() -> Self {
+        let _ = crossterm::execute!(std::io::stdout(), EnableMouseCapture);
+        MouseCapture
+    }
+}
+
+impl Drop for MouseCapture {
+    fn drop(&mut self) {
+        let _ = crossterm::execute!(std::io::stdout(), DisableMouseCapture);
+    }
+}
+
+/// Send the watched marks waiting for the API on a worker thread, unless a round is still out
+fn sync_watched_marks(state: &AppState, session: &mut Session) {
+    if !api::is_configured() || session.watched_sync.is_some() {
+        return;
+    }
+    let roots = state.movie_roots.clone();
+    session.watched_sync = state.watched_marks.sync_in_background(move |path, watched| {
+        api::set_watched(&api::client(), &api_keys(path, &roots), watched).is_ok()
+    });
+}
+
+/// Send the ratings waiting for the API on a worker thread, unless a round is still out
+fn sync_ratings(state: &AppState, session: &mut Session) {
+    if !api::is_configured() || session.ratings_sync.is_some() {
+        return;
+    }
+    let roots = state.movie_roots.clone();
+    session.ratings_sync = state.ratings.sync_in_background(move |path, rating| {
+        api::set_rating(&api::client(), &api_keys(path, &roots), rating).is_ok()
+    });
+}
+
+/// Make an API call on a worker thread so a slow or unreachable API does not hold up the
+/// list; its error shows in the status line when it comes back
+fn api_in_background(session: &mut Session, job: impl FnOnce() -> Result<(), String> + Send + 'static) {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(job());
+    });
+    session.api_jobs.push(rx);
+}
+
+/// Carry out a `:` command; commands that are also key actions come back to run as if their
+/// key was pressed
+fn run_command(state: &mut AppState, session: &mut Session, command: command::Command) -> Option<Action> {
+    use command::Command;
+    match command {
+        Command::Action(action) => return Some(action),
+        Command::ClearFilters => return Some(Action::ResetFilters),
+        Command::Sort(mode) => {
+            state.sort_mode = mode;
+            session.sort_mode = mode;
+            state.refresh_order();
+            state.set_status(format!("Sort: {}", mode.label()));
+        }
+        Command::Genres(wanted) => {
+            let available = state.available_genres();
+            let (known, unknown): (Vec<_>, Vec<_>) = wanted.iter()
+                .map(|w| (w, available.iter().find(|g| g.eq_ignore_ascii_case(w))))
+                .partition(|(_, g)| g.is_some());
+            if !unknown.is_empty() {
+                let names: Vec<&str> = unknown.iter().map(|(w, _)| w.as_str()).collect();
+                state.set_status(format!("No movie has the genre {}", names.join(", ")));
+                return None;
+            }
+            state.genre_filter = known.into_iter().filter_map(|(_, g)| g.cloned()).collect();
+            session.genre_filter = state.genre_filter.clone();
+            state.ensure_selection_visible();
+        }
+        Command::Years(range) => {
+            state.year_range = Some(range);
+            session.year_range = state.year_range;
+            state.ensure_selection_visible();
+        }
+        Command::MinRating(rating) => {
+            state.min_rating = Some(rating).filter(|&r| r > 0.0);
+            session.min_rating = state.min_rating;
+            state.ensure_selection_visible();
+        }
+        Command::ClearQueue => {
+            state.user_queue.clear();
+            session.user_queue.clear();
+            state.set_status("Queue cleared");
+        }
+        Command::SavePlaylist(_) if state.user_queue.is_empty() => state.set_status("The queue is empty"),
+        Command::SavePlaylist(name) => {
+            let paths: Vec<PathBuf> = state.user_queue.iter().map(|m| m.path.clone()).collect();
+            match playlists::save(&name, &paths) {
+                Ok(name) => state.set_status(format!("Saved the queue as \"{}\"", name)),
+                Err(e) => state.set_status(e),
+            }
+        }
+        Command::Export(file) => match state.export_list(&file, false) {
+            Ok(()) => {}
+            Err(export::WriteError::Exists) => state.set_status(format!("{} already exists: export it from the export popup to replace it", file)),
+            Err(export::WriteError::Failed(e)) => state.set_status(e),
+        },
+        Command::LoadPlaylist(name) => {
+            // The queue popup shows what was loaded and what was missing
+            state.load_playlist(&name);
+            session.user_queue = state.user_queue.clone();
+            state.open(InputMode::Queue(0));
+        }
+    }
+    None
+}
+
+fn app(terminal: &mut DefaultTerminal, movies: &[MovieEntry], movie_info_map: &HashMap<PathBuf, MovieInfo>, selected_index: &RefCell<Option<usize>>, shuffle_queue: &AtomicBool, should_exit: &RefCell<bool>, session: &mut Session) -> std::io::Result<()> {
+    let mut state = AppState {
+        movies: movies.to_vec(),
+        selected: 0,
+        movie_info_cache: movie_info_map.clone(),
+        scroll_offset: session.scroll_offset,
+        user_input: String::new(),
+        input_mode: InputMode::Normal,
+        mode_stack: Vec::new(),
+        character_index: 0,
+        play_queue: session.play_queue.clone(),
+        show_full_paths: session.show_full_paths,
+        exit_esc_window: exit_esc_window(),
+        last_esc: None,
+        confirm_exit: session.confirm_exit,
+        content_filter: session.content_filter.clone(),
+        sort_mode: session.sort_mode,
+        custom_order: session.custom_order.clone(),
+        watched_style: WatchedStyle::from_env(),
+        order: Vec::new(),
+        search_hits: None,
+        search_query: String::new(),
+        search_pending: false,
+        search_regex: false,
+        search_pattern: None,
+        search_error: None,
+        search_scope: SearchScope::default(),
+        metadata_cached_at: session.metadata_cached_at,
+        api_timed_out: session.api_timed_out,
+        metadata_loading: session.metadata_rx.is_some(),
+        movie_roots: session.movie_roots.clone(),
+        keymap: session.keymap.clone(),
+        resume_enabled: session.playback.player == Player::Mpv && session.playback.resume,
+        in_progress: Vec::new(),
+        resume_times: session.resume_times.clone(),
+        watched_marks: session.watched_marks.clone(),
+        ratings: session.ratings.clone(),
+        genre_filter: session.genre_filter.clone(),
+        min_rating: session.min_rating,
+        include_unrated: session.include_unrated,
+        favor_unwatched: session.favor_unwatched,
+        random_unwatched_only: session.random_unwatched_only,
+        delete_sidecars: session.delete_sidecars,
+        delete_from_api: session.delete_from_api,
+        year_range: session.year_range,
+        year_input_error: None,
+        rating_input_error: None,
+        command_error: None,
+        rename_error: None,
+        export_error: None,
+        export_overwrite: false,
+        collapsed: session.collapsed.clone(),
+        type_ahead: String::new(),
+        type_ahead_at: None,
+        vim_g_at: None,
+        count: String::new(),
+        count_at: None,
+        list_area: Rect::default(),
+        info_area: None,
+        list_rows: Vec::new(),
+        page_size: 0,
+        favorites: session.favorites.clone(),
+        favorites_only: session.favorites_only,
+        flat_list: session.flat_list,
+        pinned_groups: session.pinned_groups.clone(),
+        user_queue: session.user_queue.clone(),
+        queue_notice: None,
+        playlist_names: Vec::new(),
+        info_scroll_offset: 0,
+        info_scroll_for: 0,
+        library_stats: LibraryStats::default(),
+        volume: session.playback.volume,
+        speed: session.playback.speed,
+        repeat: session.playback.repeat,
+        info_percent: session.info_percent,
+        theme: session.playback.theme,
+        status_message: session.status.take().map(|message| (message, Instant::now())),
+        posters: session.playback.picker.clone().map(Posters::new),
+    };
+    // Probe results from earlier visits fill the gaps the API left
+    for (path, info) in &session.probed {
+        state.movie_info_cache.entry(path.clone()).or_insert_with(|| info.clone());
+    }
+    state.apply_ratings();
+    state.refresh_order();
+    // Back on the movie selected last, if it is still in the library; playing another version
+    // of it made that version the entry's file
+    if let Some(path) = &session.last_selected
+        && let Some(index) = state.movies.iter().position(|m| m.path == *path || m.versions.contains(path)) {
+        state.selected = index;
+    }
+    let _mouse = session.mouse.then(MouseCapture::enable);
+    state.ensure_selection_visible();
+
+    let mut last_input_time = Instant::now();
+
+    loop {
+        if state.status_message.as_ref().is_some_and(|(_, at)| at.elapsed() >= STATUS_MESSAGE_TIME) {
+            state.status_message = None;
+        }
+        if let Some(movie) = state.movies.get(state.selected)
+            && session.last_selected.as_ref() != Some(&movie.path) {
+            session.last_selected = Some(movie.path.clone());
+        }
+        session.scroll_offset = state.scroll_offset;
+
+        // Pick up the background metadata fetch once it is done
+        if let Some(rx) = &session.metadata_rx {
+            match rx.try_recv() {
+                Ok(update) => {
+                    session.metadata_rx = None;
+                    session.metadata_cached_at = update.cached_at;
+                    session.api_timed_out = update.timed_out;
+                    let fetched = update.fetched;
+                    session.library_update = Some(state.apply_metadata_update(update));
+                    session.resume_times = state.resume_times.clone();
+                    session.ratings = state.ratings.clone();
+                    session.play_queue = state.play_queue.clone();
+                    // Marks and ratings set while the API was away can be sent now
+                    if fetched {
+                        sync_watched_marks(&state, session);
+                        sync_ratings(&state, session);
+                    }
+                }
+                Err(TryRecvError::Disconnected) => {
+                    session.metadata_rx = None;
+                    state.metadata_loading = false;
+                }
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+
+        // Take note of the watched marks the API accepted; a round that got through starts the
+        // next one for marks set meanwhile
+        if let Some(rx) = &session.watched_sync {
+            match rx.try_recv() {
+                Ok(sent) => {
+                    session.watched_sync = None;
+                    state.watched_marks.mark_synced(&sent);
+                    session.watched_marks = state.watched_marks.clone();
+                    if !sent.is_empty() && state.watched_marks.has_pending() {
+                        sync_watched_marks(&state, session);
+                    }
+                }
+                Err(TryRecvError::Disconnected) => session.watched_sync = None,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+
+        // The same for ratings
+        if let Some(rx) = &session.ratings_sync {
+            match rx.try_recv() {
+                Ok(sent) => {
+                    session.ratings_sync = None;
+                    state.ratings.mark_synced(&sent);
+                    session.ratings = state.ratings.clone();
+                    if !sent.is_empty() && state.ratings.has_pending() {
+                        sync_ratings(&state, session);
+                    }
+                }
+                Err(TryRecvError::Disconnected) => session.ratings_sync = None,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+
+        session.api_jobs.retain(|rx| match rx.try_recv() {
+            Ok(result) => {
+                if let Err(e) = result {
+                    state.set_status(e);
+                }
+                false
+            }
+            Err(TryRecvError::Disconnected) => false,
+            Err(TryRecvError::Empty) => true,
+        });
+
+        // Rescan once the movie folders have settled after a change
+        if session.metadata_rx.is_none()
+            && let Some(rx) = &session.folder_changes
+            && rx.try_iter().count() > 0 {
+            session.metadata_rx = Some(refresh_library(session.movie_roots.clone(), session.use_cache));
+            state.metadata_loading = true;
+        }
+
+        if let Some(rx) = &session.probe_rx {
+            loop {
+                match rx.try_recv() {
+                    Ok((path, info)) => {
+                        state.movie_info_cache.entry(path.clone()).or_insert_with(|| with_user_rating(&state.ratings, &path, info.clone()));
+                        session.probed.insert(path, info);
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        session.probe_rx = None;
+                        probe_cache::save();
+                        break;
+                    }
+                }
+            }
+        }
+
+        let elapsed = last_input_time.elapsed();
+        if state.search_pending && elapsed >= SEARCH_DEBOUNCE {
+            state.update_search();
+        }
+        // The idle timer only runs while the toggle is on
+        let timeout_seconds = session.autoplay_timeout.filter(|_| check_idle_autoplay());
+        terminal.draw(|frame| render(frame, &mut state, elapsed, timeout_seconds))?;
+        
+        // Check if the idle timeout has passed since last input
+        if let Some(timeout) = timeout_seconds
+            && elapsed >= Duration::from_secs(timeout) {
+            // Auto-select random movie and shuffle queue (nothing to pick if every movie is filtered out)
+            if let Some(random_index) = state.random_visible() {
+                *selected_index.borrow_mut() = Some(random_index);
+                session.visible = state.visible_order();
+                shuffle_queue.store(true, Ordering::SeqCst);
+                return Ok(());
+            }
+            last_input_time = Instant::now();
+            continue;
+        }
+        
+        // Poll for events with a short timeout (100ms) to allow checking elapsed time
+        let mut poll_timeout = match timeout_seconds {
+            Some(timeout) => (Duration::from_secs(timeout) - elapsed).min(Duration::from_millis(100)),
+            None => Duration::from_millis(100),
+        };
+        if state.search_pending {
+            poll_timeout = poll_timeout.min(SEARCH_DEBOUNCE.saturating_sub(elapsed));
+        }
+        
+        if poll(poll_timeout)? {
+            // Mouse clicks and the wheel stand in for keys in the list
+            let (key, mouse_action) = match crossterm::event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => (key, None),
+                Event::Mouse(mouse) if state.input_mode == InputMode::Normal => {
+                    last_input_time = Instant::now();
+                    match state.mouse_action(mouse) {
+                        Some(action) => (KeyEvent::new(KeyCode::Null, KeyModifiers::NONE), Some(action)),
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+
+            // Reset the timer on any user input
+            last_input_time = Instant::now();
+
+            // Enter in the search popup closes it and plays the highlighted result
+            let action = if mouse_action.is_some() {
+                mouse_action
+            } else if state.input_mode == InputMode::Search && key.code == KeyCode::Enter {
+                state.flush_search();
+                state.back();
+                Some(Action::Play)
+            } else if state.input_mode == InputMode::Command && key.code == KeyCode::Enter {
+                // A command that parses closes the line; a mistake keeps it open to fix
+                match command::parse(&state.user_input) {
+                    Ok(command) => {
+                        state.back();
+                        state.clear_input();
+                        match run_command(&mut state, session, command) {
+                            Some(action) => Some(action),
+                            None => continue,
+                        }
+                    }
+                    Err(e) => {
+                        state.command_error = Some(e);
+                        continue;
+                    }
+                }
+            } else {
+                state.keymap.action(&key)
+            };
+
+            // Digits in the list build a count for the next movement
+            if state.input_mode == InputMode::Normal && !state.type_ahead_active() && action.is_none()
+                && let KeyCode::Char(c) = key.code
+                && c.is_ascii_digit()
+                && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+                if !state.count_active() {
+                    state.count.clear();
+                }
+                if state.count.len() < MAX_COUNT_DIGITS {
+                    state.count.push(c);
+                }
+                state.count_at = Some(Instant::now());
+                continue;
+            }
+            let typed_count = if state.count_active() { std::mem::take(&mut state.count) } else { String::new() };
+            state.count.clear();
+            // Digits followed by a title's next letter were the start of that title after all
+            if !typed_count.is_empty() && action.is_none() && matches!(key.code, KeyCode::Char(c) if c != ' ') {
+                state.type_ahead = typed_count.clone();
+                state.type_ahead_at = Some(Instant::now());
+            }
+            let count: usize = typed_count.parse().unwrap_or(1).max(1);
+
+            // Vim keys: a second g shortly after the first jumps to the top
+            let action = if state.input_mode == InputMode::Normal && !state.type_ahead_active() && state.keymap.is_vim_prefix(&key) {
+                if state.vim_g_at.take().is_some_and(|at| at.elapsed() < TYPE_AHEAD_RESET) {
+                    Some(Action::First)
+                } else {
+                    state.vim_g_at = Some(Instant::now());
+                    continue;
+                }
+            } else {
+                state.vim_g_at = None;
+                action
+            };
+
+            if state.input_mode == InputMode::Normal && state.type_ahead(&key, action) {
+                continue;
+            }
+
+            // Esc backs out of whatever is open, one layer at a time
+            if key.code == KeyCode::Esc && state.input_mode != InputMode::Normal {
+                if matches!(state.input_mode, InputMode::VersionMenu(_)) {
+                    session.playback.from_start = false;
+                }
+                state.cancel();
+                continue;
+            }
+
+            match state.input_mode {
+                InputMode::Search => match key.code {
+                    KeyCode::Up => {
+                        state.flush_search();
+                        state.select_prev();
+                    }
+                    KeyCode::Down => {
+                        state.flush_search();
+                        state.select_next();
+                    }
+                    KeyCode::Tab => {
+                        state.search_scope = state.search_scope.next();
+                        state.update_search();
+                    }
+                    KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        state.search_regex = !state.search_regex;
+                        state.search_error = None;
+                        state.update_search();
+                    }
+                    code => {
+                        // Re-searched from the loop once typing pauses
+                        state.edit_input(code);
+                        state.search_pending = true;
+                    }
+                },
+                InputMode::Command => {
+                    state.command_error = None;
+                    state.edit_input(key.code);
+                }
+                InputMode::ConfirmFilterOff => {
+                    // Only an explicit "y" lifts the kids-mode filter
+                    if key.code == KeyCode::Char('y') {
+                        state.content_filter.enabled = false;
+                        session.content_filter.enabled = false;
+                    }
+                    state.back();
+                }
+                InputMode::ConfirmExit => {
+                    // Only an explicit "y" quits; any other key goes back to the list
+                    if key.code == KeyCode::Char('y') {
+                        *should_exit.borrow_mut() = true;
+                        return Ok(());
+                    }
+                    state.back();
+                }
+                InputMode::ConfirmDelete => {
+                    // Only an explicit "y" deletes; any other key keeps the file
+                    state.back();
+                    if key.code == KeyCode::Char('y')
+                        && let Some((path, library)) = state.delete_selected() {
+                        session.library_update = Some(library);
+                        session.user_queue = state.user_queue.clone();
+                        session.play_queue = state.play_queue.clone();
+                        session.favorites = state.favorites.clone();
+                        session.watched_marks = state.watched_marks.clone();
+                        session.ratings = state.ratings.clone();
+                        session.resume_times = state.resume_times.clone();
+                        session.custom_order = state.custom_order.clone();
+                        if state.delete_from_api && api::is_configured() {
+                            let roots = session.movie_roots.clone();
+                            api_in_background(session, move || {
+                                api::remove_file(&api::client(), &api_keys(&path, &roots))
+                                    .map_err(|e| format!("Deleted, but the API still lists the file: {}", e))
+                            });
+                        }
+                    }
+                }
+                InputMode::DoubleFeature(first, second) => match key.code {
+                    KeyCode::Enter => {
+                        session.double_feature = Some((first, second));
+                        *selected_index.borrow_mut() = Some(first);
+                        session.visible = state.visible_order();
+                        shuffle_queue.store(false, Ordering::SeqCst);
+                        return Ok(());
+                    }
+                    KeyCode::Char('r') => {
+                        if let Some((first, second)) = state.pick_double_feature() {
+                            state.input_mode = InputMode::DoubleFeature(first, second);
+                        }
+                    }
+                    _ => {}
+                },
+                InputMode::Queue(row) => {
+                    let len = state.user_queue.len();
+                    // Moving, reordering, removing and playing use the list's keys; the rest are
+                    // the popup's own letters
+                    match (action, key.code) {
+                        (Some(Action::MoveUp), _) if row > 0 => {
+                            state.user_queue.swap(row, row - 1);
+                            state.input_mode = InputMode::Queue(row - 1);
+                        }
+                        (Some(Action::MoveDown), _) if row + 1 < len => {
+                            state.user_queue.swap(row, row + 1);
+                            state.input_mode = InputMode::Queue(row + 1);
+                        }
+                        (Some(Action::NavigateUp), _) if len > 0 => {
+                            state.input_mode = InputMode::Queue((row + len - 1) % len);
+                        }
+                        (Some(Action::NavigateDown), _) if len > 0 => {
+                            state.input_mode = InputMode::Queue((row + 1) % len);
+                        }
+                        (Some(Action::DeleteMovie), _) | (_, KeyCode::Char('d') | KeyCode::Backspace) if row < len => {
+                            state.user_queue.remove(row);
+                            state.input_mode = InputMode::Queue(row.min(len.saturating_sub(2)));
+                        }
+                        (Some(Action::Play), _) if len > 0 => {
+                            if let Some(queue) = state.take_user_queue() {
+                                state.back();
+                                session.user_queue.clear();
+                                *selected_index.borrow_mut() = queue.next_index();
+                                session.play_queue = Some(queue);
+                                shuffle_queue.store(false, Ordering::SeqCst);
+                                return Ok(());
+                            }
+                        }
+                        (_, KeyCode::Char('c')) => {
+                            state.user_queue.clear();
+                            state.input_mode = InputMode::Queue(0);
+                        }
+                        (_, KeyCode::Char('s')) if len > 0 => {
+                            state.queue_notice = None;
+                            state.clear_input();
+                            state.open(InputMode::PlaylistName);
+                        }
+                        (_, KeyCode::Char('l')) => {
+                            state.playlist_names = playlists::names();
+                            state.open(InputMode::Playlists(0));
+                        }
+                        (_, KeyCode::Char('i')) => {
+                            state.queue_notice = None;
+                            state.clear_input();
+                            state.open(InputMode::M3uImport);
+                        }
+                        (_, KeyCode::Char('e')) if len > 0 => {
+                            state.queue_notice = None;
+                            state.clear_input();
+                            state.open(InputMode::M3uExport);
+                        }
+                        _ => {}
+                    }
+                    session.user_queue = state.user_queue.clone();
+                }
+                mode @ (InputMode::PlaylistName | InputMode::M3uImport | InputMode::M3uExport) => match key.code {
+                    KeyCode::Enter => {
+                        let done = match mode {
+                            InputMode::PlaylistName => state.save_playlist(),
+                            InputMode::M3uImport => state.import_m3u(),
+                            _ => state.export_m3u(),
+                        };
+                        if done {
+                            session.user_queue = state.user_queue.clone();
+                            state.clear_input();
+                            state.back();
+                        }
+                    }
+                    code => state.edit_input(code),
+                },
+                InputMode::Playlists(row) => {
+                    let count = state.playlist_names.len();
+                    match key.code {
+                        KeyCode::Up if count > 0 => {
+                            state.input_mode = InputMode::Playlists((row + count - 1) % count);
+                        }
+                        KeyCode::Down if count > 0 => {
+                            state.input_mode = InputMode::Playlists((row + 1) % count);
+                        }
+                        KeyCode::Enter if row < count => {
+                            let name = state.playlist_names[row].clone();
+                            state.load_playlist(&name);
+                            session.user_queue = state.user_queue.clone();
+                            state.back();
+                        }
+                        _ => {}
+                    }
+                }
+                InputMode::Stats => {
+                    if key.code == KeyCode::Enter || action == Some(Action::ShowStats) {
+                        state.back();
+                    }
+                }
+                InputMode::Help => {
+                    if key.code == KeyCode::Char('?') || action == Some(Action::ShowHelp) {
+                        state.back();
+                    }
+                }
+                InputMode::YearFilter => match key.code {
+                    KeyCode::Enter => {
+                        if state.apply_year_input() {
+                            session.year_range = state.year_range;
+                            state.back();
+                            state.clear_input();
+                        }
+                    }
+                    code => state.edit_input(code),
+                },
+                InputMode::RenameFile => match key.code {
+                    KeyCode::Enter => {
+                        let old = state.movies.get(state.selected).map(|m| m.path.clone());
+                        if state.apply_rename_input() {
+                            session.favorites = state.favorites.clone();
+                            session.watched_marks = state.watched_marks.clone();
+                            session.ratings = state.ratings.clone();
+                            session.resume_times = state.resume_times.clone();
+                            session.custom_order = state.custom_order.clone();
+                            session.user_queue = state.user_queue.clone();
+                            session.library_update = Some(Library {
+                                movies: state.movies.clone(),
+                                info_map: state.movie_info_cache.clone(),
+                            });
+                            // The API keeps the movie's metadata under the file's name, so it follows the rename
+                            if let Some(old) = old
+                                && let Some(new) = state.movies.get(state.selected).map(|m| m.path.clone())
+                                && old != new
+                                && api::is_configured() {
+                                let roots = session.movie_roots.clone();
+                                api_in_background(session, move || {
+                                    api::rename_file(&api::client(), &api_keys(&old, &roots), &api_keys(&new, &roots))
+                                        .map_err(|e| format!("Renamed, but the API still has the old name: {}", e))
+                                });
+                            }
+                            state.back();
+                            state.clear_input();
+                        }
+                    }
+                    code => {
+                        state.rename_error = None;
+                        state.edit_input(code);
+                    }
+                },
+                InputMode::ExportList => match key.code {
+                    KeyCode::Enter => {
+                        let file = state.user_input.clone();
+                        match state.export_list(&file, state.export_overwrite) {
+                            Ok(()) => {
+                                state.export_error = None;
+                                state.export_overwrite = false;
+                                state.back();
+                                state.clear_input();
+                            }
+                            // Asked once; a second Enter on the same name replaces the file
+                            Err(export::WriteError::Exists) => {
+                                state.export_error = Some("the file exists | Enter=Replace it".to_string());
+                                state.export_overwrite = true;
+                            }
+                            Err(export::WriteError::Failed(e)) => state.export_error = Some(e),
+                        }
+                    }
+                    code => {
+                        state.export_error = None;
+                        state.export_overwrite = false;
+                        state.edit_input(code);
+                    }
+                },
+                InputMode::Rating => match key.code {
+                    KeyCode::Enter => {
+                        if state.apply_rating_input() {
+                            session.ratings = state.ratings.clone();
+                            sync_ratings(&state, session);
+                            state.refresh_order();
+                            state.back();
+                            state.clear_input();
+                        }
+                    }
+                    code => state.edit_input(code),
+                },
+                InputMode::GenrePicker(row) => {
+                    let genres = state.available_genres();
+                    match key.code {
+                        KeyCode::Enter => {
+                            state.back();
+                        }
+                        KeyCode::Up if !genres.is_empty() => {
+                            state.input_mode = InputMode::GenrePicker((row + genres.len() - 1) % genres.len());
+                        }
+                        KeyCode::Down if !genres.is_empty() => {
+                            state.input_mode = InputMode::GenrePicker((row + 1) % genres.len());
+                        }
+                        KeyCode::Char(' ') => {
+                            if let Some(genre) = genres.get(row) {
+                                state.toggle_genre(genre);
+                                session.genre_filter = state.genre_filter.clone();
+                            }
+                        }
+                        KeyCode::Char('c') => {
+                            state.genre_filter.clear();
+                            session.genre_filter.clear();
+                        }
+                        _ => {}
+                    }
+                }
+                InputMode::VersionMenu(row) => {
+                    // Pick which file of a collapsed entry to play
+                    let movie = &state.movies[state.selected];
+                    let choices = movie.versions.len() + 1;
+                    match key.code {
+                        KeyCode::Up => {
+                            state.input_mode = InputMode::VersionMenu((row + choices - 1) % choices);
+                        }
+                        KeyCode::Down => {
+                            state.input_mode = InputMode::VersionMenu((row + 1) % choices);
+                        }
+                        KeyCode::Enter => {
+                            if row > 0 {
+                                session.version_choice = Some(movie.versions[row - 1].clone());
+                            }
+                            *selected_index.borrow_mut() = Some(state.selected);
+                            session.visible = state.visible_order();
+                            return Ok(());
+                        }
+                        _ => {}
+                    }
+                }
+                InputMode::Normal => match action {
+                    Some(Action::Quit) if state.search_hits.is_some() => {
+                        // Leave the search results before anything else
+                        state.clear_input();
+                        state.clear_search();
+                    }
+                    Some(Action::Quit) if state.filters_active() => {
+                        // Then the filters, and only then the app itself
+                        state.reset_filters();
+                        session.genre_filter.clear();
+                        session.min_rating = None;
+                        session.year_range = None;
+                        session.favorites_only = false;
+                        state.set_status(format!("Filters cleared ({} again to quit)", state.keymap.label(Action::Quit)));
+                    }
+                    Some(Action::Quit) if state.confirm_exit => {
+                        state.open(InputMode::ConfirmExit);
+                    }
+                    Some(Action::Quit) => {
+                        // Exit the app when popup is not open, optionally requiring a second press
+                        if state.exit_pending() || state.exit_esc_window.is_none() {
+                            *should_exit.borrow_mut() = true;
+                            return Ok(());
+                        }
+                        state.last_esc = Some(Instant::now());
+                    }
+                    Some(Action::MoveUp) if state.sort_mode == SortMode::Custom => {
+                        state.move_in_custom_order(true);
+                        session.custom_order = state.custom_order.clone();
+                    }
+                    Some(Action::MoveDown) if state.sort_mode == SortMode::Custom => {
+                        state.move_in_custom_order(false);
+                        session.custom_order = state.custom_order.clone();
+                    }
+                    Some(Action::NavigateUp | Action::MoveUp) => {
+                        state.select_steps(count, false);
+                    }
+                    Some(Action::NavigateDown | Action::MoveDown) => {
+                        state.select_steps(count, true);
+                    }
+                    Some(Action::PageUp) => {
+                        for _ in 0..count {
+                            state.select_page(false);
+                        }
+                    }
+                    Some(Action::PageDown) => {
+                        for _ in 0..count {
+                            state.select_page(true);
+                        }
+                    }
+                    Some(Action::ScrollInfoUp) => {
+                        state.info_scroll_offset = state.info_scroll_offset.saturating_sub(count as u16);
+                    }
+                    Some(Action::ScrollInfoDown) => {
+                        // Clamped to the text length when drawn
+                        state.info_scroll_offset = state.info_scroll_offset.saturating_add(count as u16);
+                    }
+                    Some(Action::First) => {
+                        state.select_first();
+                    }
+                    Some(Action::Last) => {
+                        // The "Random Movie" entry closes the list
+                        state.selected = state.movies.len();
+                    }
+                    Some(Action::Play | Action::ToggleGroup) if state.selected < state.movies.len() && state.in_collapsed_group(state.selected) => {
+                        // Enter on a collapsed header opens it up
+                        state.toggle_group();
+                        session.collapsed = state.collapsed.clone();
+                    }
+                    Some(Action::ToggleGroup) => {
+                        state.toggle_group();
+                        session.collapsed = state.collapsed.clone();
+                    }
+                    Some(Action::PinGroup) => {
+                        if let Some((group, pinned)) = state.toggle_pin() {
+                            session.pinned_groups = state.pinned_groups.clone();
+                            state.set_status(if pinned { format!("Pinned {} to the top", group) } else { format!("Unpinned {}", group) });
+                        }
+                    }
+                    Some(Action::Play) if !state.user_queue.is_empty() => {
+                        // A hand-built queue plays instead of the library
+                        if let Some(queue) = state.take_user_queue() {
+                            session.user_queue.clear();
+                            *selected_index.borrow_mut() = queue.next_index();
+                            session.play_queue = Some(queue);
+                            shuffle_queue.store(false, Ordering::SeqCst);
+                            return Ok(());
+                        }
+                    }
+                    Some(Action::Enqueue) if state.selected < state.movies.len() => {
+                        state.user_queue.push(state.movies[state.selected].clone());
+                        session.user_queue = state.user_queue.clone();
+                        state.set_status(format!("Added to the queue ({})", state.user_queue.len()));
+                    }
+                    Some(Action::ShowQueue) => {
+                        state.queue_notice = None;
+                        state.open(InputMode::Queue(0));
+                    }
+                    Some(action @ (Action::Play | Action::PlayFromStart)) if state.selected < state.movies.len() && !state.movies[state.selected].versions.is_empty() => {
+                        // Collapsed duplicates: ask which version to play first
+                        session.playback.from_start = action == Action::PlayFromStart;
+                        state.open(InputMode::VersionMenu(0));
+                    }
+                    Some(action @ (Action::Play | Action::PlayFromStart)) => {
+                        session.playback.from_start = action == Action::PlayFromStart;
+                        // Store the selected index and exit to restore terminal
+                        let (start_index, should_shuffle) = if state.selected == state.movies.len() {
+                            // Random movie selected - shuffle the queue
+                            match state.random_visible() {
+                                Some(random_index) => (random_index, true),
+                                None => continue,
+                            }
+                        } else if SHUFFLE_QUEUE.load(Ordering::SeqCst) {
+                            // Selected movie - shuffle order
+                            (state.selected, true)
+                        } else {
+                            // Selected movie - keep original order
+                            (state.selected, false)
+                        };
+                        
+                        *selected_index.borrow_mut() = Some(start_index);
+                        session.visible = state.visible_order();
+                        shuffle_queue.store(should_shuffle, Ordering::SeqCst);
+                        return Ok(());
+                    }
+                    Some(Action::ToggleAutoNext) => {
+                        toggle_auto_play_next();
+                        state.set_status(format!("Autoplay next: {}", on_off(check_auto_play_next())));
+                    }
+                    Some(Action::ToggleIdleAutoplay) => {
+                        toggle_idle_autoplay();
+                        state.set_status(format!("Idle auto-play: {}", on_off(check_idle_autoplay())));
+                    }
+                    Some(action @ (Action::VolumeDown | Action::VolumeUp)) => {
+                        let current = state.volume.unwrap_or(100);
+                        let next = if action == Action::VolumeUp { current.saturating_add(VOLUME_STEP) } else { current.saturating_sub(VOLUME_STEP) };
+                        state.volume = Some(next.min(MAX_VOLUME));
+                        session.playback.volume = state.volume;
+                        state.set_status(format!("Volume: {}%", next.min(MAX_VOLUME)));
+                    }
+                    Some(Action::CycleSpeed) => {
+                        let current = state.speed.unwrap_or(1.0);
+                        let next = SPEEDS.iter().copied().find(|&s| s > current).unwrap_or(1.0);
+                        state.speed = (next != 1.0).then_some(next);
+                        session.playback.speed = state.speed;
+                        state.set_status(format!("Speed: {}x", next));
+                    }
+                    Some(Action::CycleRepeat) => {
+                        state.repeat = state.repeat.next();
+                        session.playback.repeat = state.repeat;
+                        state.set_status(format!("Repeat: {}", state.repeat.label()));
+                    }
+                    Some(Action::ToggleShuffle) => {
+                        toggle_shuffle_queue();
+                        state.set_status(format!("Shuffle: {}", on_off(check_shuffle_queue())));
+                    }
+                    Some(Action::ToggleSubtitles) => {
+                        toggle_subtitles();
+                        state.set_status(format!("Subtitles: {}", on_off(check_subtitles())));
+                    }
+                    Some(Action::ToggleMiniMode) => {
+                        toggle_mini_mode();
+                    }
+                    Some(Action::ToggleHighContrast) => {
+                        toggle_high_contrast();
+                        state.set_status(format!("High contrast: {}", on_off(check_high_contrast())));
+                    }
+                    Some(Action::DoubleFeature) => {
+                        if let Some((first, second)) = state.pick_double_feature() {
+                            state.open(InputMode::DoubleFeature(first, second));
+                        }
+                    }
+                    Some(Action::DeleteMovie) if state.selected < state.movies.len() => {
+                        state.open(InputMode::ConfirmDelete);
+                    }
+                    Some(Action::ToggleWatched) => {
+                        state.toggle_watched();
+                        session.watched_marks = state.watched_marks.clone();
+                        sync_watched_marks(&state, session);
+                    }
+                    Some(action @ (Action::CycleSort | Action::CycleGrouping)) => {
+                        state.sort_mode = if action == Action::CycleGrouping { state.sort_mode.next_grouping() } else { state.sort_mode.next() };
+                        session.sort_mode = state.sort_mode;
+                        state.refresh_order();
+                        state.set_status(format!("Sort: {}", state.sort_mode.label()));
+                    }
+                    Some(action @ (Action::NarrowInfoPanel | Action::WidenInfoPanel)) => {
+                        state.info_percent = if action == Action::WidenInfoPanel {
+                            (state.info_percent + INFO_PERCENT_STEP).min(MAX_INFO_PERCENT)
+                        } else {
+                            state.info_percent.saturating_sub(INFO_PERCENT_STEP).max(MIN_INFO_PERCENT)
+                        };
+                        session.info_percent = state.info_percent;
+                        state.set_status(format!("Info panel: {}%", state.info_percent));
+                    }
+                    Some(Action::TogglePaths) => {
+                        state.show_full_paths = !state.show_full_paths;
+                        session.show_full_paths = state.show_full_paths;
+                    }
+                    Some(Action::ToggleContentFilter) if state.content_filter.max_rating.is_some() => {
+                        if state.content_filter.kids_mode && state.content_filter.enabled {
+                            // Kids mode: turning the filter off needs confirmation
+                            state.open(InputMode::ConfirmFilterOff);
+                        } else {
+                            state.content_filter.enabled = !state.content_filter.enabled;
+                            session.content_filter.enabled = state.content_filter.enabled;
+                            state.ensure_selection_visible();
+                        }
+                    }
+                    Some(Action::OpenSearch) => {
+                        state.open(InputMode::Search);
+                    }
+                    Some(Action::JumpToTitle) => {
+                        state.type_ahead.clear();
+                        state.type_ahead_at = Some(Instant::now());
+                    }
+                    Some(Action::OpenCommand) => {
+                        state.clear_input();
+                        state.open(InputMode::Command);
+                    }
+                    Some(Action::ToggleFavorite) if state.selected < state.movies.len() => {
+                        let path = state.movies[state.selected].path.clone();
+                        state.favorites.toggle(&path);
+                        session.favorites = state.favorites.clone();
+                        state.set_status(if state.favorites.contains(&path) { "Marked as favorite" } else { "Removed from favorites" });
+                        state.ensure_selection_visible();
+                    }
+                    Some(Action::ToggleFlatList) => {
+                        state.flat_list = !state.flat_list;
+                        session.flat_list = state.flat_list;
+                        state.refresh_order();
+                        state.ensure_selection_visible();
+                        state.set_status(format!("Flat list: {}", on_off(state.flat_list)));
+                    }
+                    Some(Action::FavoritesOnly) => {
+                        state.favorites_only = !state.favorites_only;
+                        session.favorites_only = state.favorites_only;
+                        state.ensure_selection_visible();
+                    }
+                    Some(Action::ShowStats) => {
+                        state.library_stats = LibraryStats::collect(&state.movies, &state.movie_info_cache);
+                        state.open(InputMode::Stats);
+                    }
+                    Some(Action::ShowHelp) => {
+                        state.open(InputMode::Help);
+                    }
+                    Some(Action::FilterGenres) => {
+                        state.open(InputMode::GenrePicker(0));
+                    }
+                    Some(Action::FilterYears) => {
+                        // Start from the current range so it can be edited
+                        state.user_input = state.year_range.map(|r| r.to_string()).unwrap_or_default();
+                        state.character_index = state.user_input.chars().count();
+                        state.open(InputMode::YearFilter);
+                    }
+                    Some(Action::RateMovie) if state.selected < state.movies.len() => {
+                        // Start from the current rating so it can be edited
+                        let rating = state.movie_info_cache.get(&state.movies[state.selected].path).and_then(|i| i.rating);
+                        state.user_input = rating.map(|r| r.to_string()).unwrap_or_default();
+                        state.character_index = state.user_input.chars().count();
+                        state.open(InputMode::Rating);
+                    }
+                    Some(Action::RenameMovie) if state.selected < state.movies.len() => {
+                        // Start from the current name so it can be edited
+                        let name = state.movies[state.selected].path.file_name().map(|n| n.to_string_lossy().to_string());
+                        state.user_input = name.unwrap_or_default();
+                        state.character_index = state.user_input.chars().count();
+                        state.open(InputMode::RenameFile);
+                    }
+                    Some(Action::ExportList) => {
+                        state.user_input = DEFAULT_EXPORT_FILE.to_string();
+                        state.character_index = state.user_input.chars().count();
+                        state.open(InputMode::ExportList);
+                    }
+                    Some(Action::OpenImdb) if state.selected < state.movies.len() => {
+                        let imdb_id = state.movie_info_cache.get(&state.movies[state.selected].path).and_then(|i| i.imdb_id.clone());
+                        match imdb_id {
+                            Some(id) => match open_in_browser(&format!("https://www.imdb.com/title/{}/", id)) {
+                                Ok(()) => state.set_status(format!("Opening {} on IMDb", id)),
+                                Err(e) => state.set_status(format!("Cannot open the browser: {}", e)),
+                            },
+                            None => state.set_status("No IMDb id known for this movie"),
+                        }
+                    }
+                    Some(Action::PlayTrailer) if state.selected < state.movies.len() => {
+                        let movie = &state.movies[state.selected];
+                        let info = state.movie_info_cache.get(&movie.path);
+                        let url = info.and_then(|i| i.trailer_url.clone());
+                        let title = display_title(movie, info);
+                        let query = trailer::search_query(&title, info.and_then(|i| i.year));
+                        if session.playback.player == Player::Mpv && backend::ytdlp_installed() {
+                            session.trailer = Some((trailer::stream_target(url.as_deref(), &query), title));
+                            return Ok(());
+                        }
+                        // Without mpv and yt-dlp the browser plays it
+                        match open_in_browser(&trailer::browser_url(url.as_deref(), &query)) {
+                            Ok(()) => state.set_status("Opening the trailer in the browser"),
+                            Err(e) => state.set_status(format!("Cannot open the browser: {}", e)),
+                        }
+                    }
+                    Some(Action::Refresh) if session.metadata_rx.is_none() => {
+                        session.metadata_rx = Some(refresh_library(session.movie_roots.clone(), session.use_cache));
+                        state.metadata_loading = true;
+                    }
+                    Some(Action::ResetFilters) => {
+                        state.reset_filters();
+                        session.genre_filter.clear();
+                        session.min_rating = None;
+                        session.year_range = None;
+                        session.favorites_only = false;
+                    }
+                    Some(action @ (Action::RaiseMinRating | Action::LowerMinRating)) => {
+                        state.adjust_min_rating(action == Action::RaiseMinRating);
+                        session.min_rating = state.min_rating;
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+}
+
+fn render(frame: &mut Frame, state: &mut AppState, elapsed: Duration, timeout_seconds: Option<u64>) {
+    let area = frame.area();
+    let theme = current_theme(state.theme);
+
+    // Calculate remaining time until auto-play (None when the idle auto-play is off)
+    let remaining_secs = timeout_seconds.map(|secs| Duration::from_secs(secs).saturating_sub(elapsed).as_secs());
+
+    // Mini mode drops the taskbar and info panel so the list stays usable in tiny panes
+    let mini = check_mini_mode() || area.width < MINI_MODE_MAX_WIDTH || area.height < MINI_MODE_MAX_HEIGHT;
+
+    let (list_area, info_area) = if mini {
+        (area, None)
+    } else {
+        // Split the frame: top taskbar, then main content area
+        let main_chunks = Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+
+        // Split the content area into two: left for list, right for info
+        let chunks = Layout::default()
+            .direction(ratatui::layout::Direction::Horizontal)
+            .constraints([Constraint::Percentage(100 - state.info_percent), Constraint::Percentage(state.info_percent)])
+            .split(main_chunks[1]);
+
+        render_taskbar(frame, main_chunks[0], state, remaining_secs);
+        (chunks[0], Some(chunks[1]))
+    };
+
+    // Build display list with group headers
+    let mut items: Vec<ListItem> = Vec::new();
+    // The movie each item stands for, for mouse clicks
+    let mut targets: Vec<Option<usize>> = Vec::new();
+    let mut current_group: Option<Cow<str>> = None;
+    let mut selected_display_index = 0; // Track where selected item appears in display list
+    let up_next = state.play_queue.as_ref().and_then(|q| q.next_index());
+    // Room left for a name inside the borders after the 2-char selection prefix
+    let name_width = (list_area.width as usize).saturating_sub(4);
+    
+    for &movie_idx in state.list_order() {
+        if !state.is_visible(movie_idx) {
+            continue;
+        }
+        let movie = &state.movies[movie_idx];
+
+        // Add group header if this is a new group
+        let group = state.group_label(movie_idx);
+        let collapsed = state.in_collapsed_group(movie_idx);
+        if state.shows_groups() && current_group.as_ref() != Some(&group) {
+            current_group = Some(group.clone());
+            let heading = if state.pinned_groups.iter().any(|g| *g == group) { format!("{} (pinned)", group) } else { group.to_string() };
+            if collapsed {
+                // A collapsed group is just its header, selectable through its first movie
+                let count = state.list_order().iter().filter(|&&i| state.is_visible(i) && state.group_label(i) == group).count();
+                let selected = movie_idx == state.selected;
+                items.push(ListItem::new(format!("{}┌─ {} ({}) ─┐", if selected { "> " } else { "" }, heading, count))
+                    .style(if selected {
+                        theme.selected_style()
+                    } else {
+                        Style::default().fg(theme.header).add_modifier(Modifier::BOLD)
+                    }));
+                targets.push(Some(movie_idx));
+                if selected {
+                    selected_display_index = items.len() - 1;
+                }
+            } else {
+                let header_text = format!("┌─ {} ─┐", heading);
+                items.push(ListItem::new(header_text)
+                    .style(Style::default()
+                        .fg(theme.header)
+                        .add_modifier(Modifier::BOLD)));
+                targets.push(None);
+            }
+        }
+        if collapsed {
+            continue;
+        }
+        
+        // Add movie item
+        let watched = state.is_watched(movie_idx);
+        let favorite = state.favorites.contains(&movie.path);
+        // Leave room for the ★ and ✓ markers after the name
+        let name_width = name_width.saturating_sub(if watched { 2 } else { 0 } + if favorite { 2 } else { 0 });
+        // Bare file name, or the path relative to the library root when toggled
+        let name = if state.show_full_paths {
+            let rel = relative_to_root(&movie.path, &state.movie_roots).to_string_lossy();
+            truncate_with_ellipsis(&rel, name_width, true)
+        } else {
+            let file_name = movie.path.file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("Unknown");
+            truncate_with_ellipsis(file_name, name_width, false)
+        };
+        let prefix = if movie_idx == state.selected {
+            "> "
+        } else if Some(movie_idx) == up_next {
+            "» "
+        } else {
+            "  "
+        };
+        
+        // Style the selected item, the queued next title and watched titles by their theme roles
+        let style = if movie_idx == state.selected {
+            theme.selected_style()
+        } else if Some(movie_idx) == up_next {
+            Style::default()
+                .fg(theme.positive)
+        } else if watched && state.watched_style == WatchedStyle::Dim {
+            Style::default()
+                .fg(theme.muted)
+        } else {
+            Style::default()
+                .fg(theme.item)
+        };
+        
+        let mut spans = vec![Span::raw(prefix)];
+        // While searching, the characters the query matched stand out
+        let matched = match (&state.search_hits, &state.search_pattern) {
+            (Some(_), Some(pattern)) => search::regex_positions(pattern, &name),
+            (Some(_), None) => search::match_positions(&state.search_query, &name),
+            (None, _) => Vec::new(),
+        };
+        if matched.is_empty() {
+            spans.push(Span::raw(name));
+        } else {
+            let highlight = style.fg(theme.accent).add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+            let mut run = String::new();
+            let mut run_matched = false;
+            for (i, c) in name.chars().enumerate() {
+                let is_match = matched.contains(&i);
+                if is_match != run_matched && !run.is_empty() {
+                    let text = std::mem::take(&mut run);
+                    spans.push(if run_matched { Span::styled(text, highlight) } else { Span::raw(text) });
+                }
+                run_matched = is_match;
+                run.push(c);
+            }
+            spans.push(if run_matched { Span::styled(run, highlight) } else { Span::raw(run) });
+        }
+        if favorite {
+            spans.push(Span::styled(" ★", Style::default().fg(theme.accent)));
+        }
+        if watched {
+            spans.push(Span::styled(" ✓", Style::default().fg(theme.muted)));
+        }
+        items.push(ListItem::new(Line::from(spans)).style(style));
+        targets.push(Some(movie_idx));
+        
+        // Track display index for selected movie (after adding to list)
+        if movie_idx == state.selected {
+            selected_display_index = items.len() - 1;
+        }
+    }
+    
+    // Add separator and "Random Movie" option with its own group
+    items.push(ListItem::new("┌─ Special ─┐")
+        .style(Style::default()
+            .fg(theme.header)
+            .add_modifier(Modifier::BOLD)));
+    targets.push(None);
+    
+    let random_movie_idx = state.movies.len();
+    if state.selected == random_movie_idx {
+        selected_display_index = items.len();
+    }
+    
+    let random_prefix = if state.selected == random_movie_idx { "> " } else { "  " };
+    let random_style = if state.selected == random_movie_idx {
+        theme.selected_style()
+    } else {
+        Style::default()
+            .fg(theme.item)
+    };
+    items.push(ListItem::new(format!("{}Random Movie", random_prefix)).style(random_style));
+    targets.push(Some(random_movie_idx));
+
+    // Calculate visible area (accounting for borders - 2 lines for top/bottom borders)
+    let visible_height = list_area.height.saturating_sub(2);
+    state.page_size = visible_height as usize;
+    
+    // Update scroll offset to keep selected item visible
+    if selected_display_index < state.scroll_offset {
+        // Selected item is above visible area, scroll up
+        state.scroll_offset = selected_display_index;
+    } else if selected_display_index >= state.scroll_offset + visible_height as usize {
+        // Selected item is below visible area, scroll down
+        state.scroll_offset = selected_display_index.saturating_sub((visible_height as usize).saturating_sub(1));
+    }
+    
+    // Ensure scroll offset doesn't go beyond bounds
+    if state.scroll_offset + visible_height as usize > items.len() {
+        state.scroll_offset = items.len().saturating_sub(visible_height as usize);
+    }
+    if state.scroll_offset > items.len() {
+        state.scroll_offset = 0;
+    }
+    
+    // Get visible slice of items
+    let end_index = (state.scroll_offset + visible_height as usize).min(items.len());
+    let visible_items: Vec<ListItem> = items[state.scroll_offset..end_index].to_vec();
+    state.list_rows = targets[state.scroll_offset..end_index].to_vec();
+    state.list_area = list_area;
+    state.info_area = info_area;
+
+    // In mini mode the list title doubles as a compact status line
+    let list_title = if mini {
+        let key = |action| state.keymap.label(action);
+        let timer = remaining_secs.map(|secs| format!(" | Auto-play {:02}s", secs)).unwrap_or_default();
+        format!("{}/{}{} | {}={} {}={} | {}=Full",
+            state.selected + 1, state.movies.len() + 1, timer, key(Action::ToggleAutoNext), check_auto_play_next(),
+            key(Action::ToggleShuffle), check_shuffle_queue(), key(Action::ToggleMiniMode))
+    } else if let Some(hits) = &state.search_hits {
+        format!("Search Results ({})", hits.len())
+    } else {
+        "Select a Movie".to_string()
+    };
+
+    let mut list_block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .title_style(Style::default().fg(theme.title).add_modifier(Modifier::BOLD))
+        .title(list_title);
+
+    // Show where the interrupted queue would pick up
+    if let Some(queue) = &state.play_queue
+        && let Some(next_idx) = queue.next_index() {
+        let movie = &state.movies[next_idx];
+        let title = display_title(movie, state.movie_info_cache.get(&movie.path));
+        list_block = list_block.title_bottom(Line::from(Span::styled(
+            format!(" Up next: {} ({} of {}) ", title, queue.position + 1, queue.order.len()),
+            Style::default().fg(theme.positive),
+        )));
+    }
+
+    if state.exit_pending() {
+        list_block = list_block.title_bottom(Line::from(Span::styled(
+            " Press Esc again to exit ",
+            Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
+        )).right_aligned());
+    }
+
+    let list = List::new(visible_items).block(list_block);
+
+    frame.render_widget(list, list_area);
+    
+    if let Some(info_area) = info_area {
+        render_info_panel(frame, state, info_area);
+    }
+
+    // Render the version submenu for collapsed duplicates
+    if let InputMode::VersionMenu(row) = state.input_mode {
+        let movie = &state.movies[state.selected];
+        let version_items: Vec<ListItem> = std::iter::once(&movie.path)
+            .chain(movie.versions.iter())
+            .enumerate()
+            .map(|(i, path)| {
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("Unknown");
+                if i == row {
+                    ListItem::new(format!("> {}", name)).style(theme.selected_style())
+                } else {
+                    ListItem::new(format!("  {}", name)).style(Style::default().fg(theme.item))
+                }
+            })
+            .collect();
+
+        let area = popup_area(frame.area(), 50, 30);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            List::new(version_items).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(theme.popup_border))
+                    .title("Choose Version | Enter=Play | Esc=Back")
+            ),
+            area,
+        );
+    }
+
+    // The hand-built play queue, in order
+    if let InputMode::Queue(row) = state.input_mode {
+        let queue_items: Vec<ListItem> = state.user_queue.iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let title = display_title(entry, state.movie_info_cache.get(&entry.path));
+                ListItem::new(format!("{:>2}. {}", i + 1, title)).style(Style::default().fg(theme.item))
+            })
+            .collect();
+
+        let area = popup_area(frame.area(), 50, 50);
+        frame.render_widget(Clear, area);
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.popup_border))
+            .title(format!(
+                "Queue ({}) | {}=Play | {}/{}=Move | d=Remove | c=Clear | s=Save | l=Load | i/e=Import/Export m3u | Esc=Close",
+                state.user_queue.len(),
+                state.keymap.label(Action::Play),
+                state.keymap.label(Action::MoveUp),
+                state.keymap.label(Action::MoveDown),
+            ));
+        if let Some(notice) = &state.queue_notice {
+            block = block.title_bottom(Line::from(Span::styled(format!(" {} ", notice), Style::default().fg(theme.accent))));
+        }
+        if queue_items.is_empty() {
+            let hint = format!("Empty - add the selected movie with {}", state.keymap.label(Action::Enqueue));
+            frame.render_widget(Paragraph::new(hint).style(Style::default().fg(theme.muted)).block(block), area);
+        } else {
+            let mut list_state = ListState::default().with_selected(Some(row));
+            frame.render_stateful_widget(
+                List::new(queue_items)
+                    .block(block)
+                    .highlight_style(theme.selected_style()),
+                area,
+                &mut list_state,
+            );
+        }
+    }
+
+    // Saved playlists to load into the queue
+    if let InputMode::Playlists(row) = state.input_mode {
+        let area = popup_area(frame.area(), 40, 40);
+        frame.render_widget(Clear, area);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.popup_border))
+            .title("Playlists | Enter=Load | Esc=Back");
+        if state.playlist_names.is_empty() {
+            frame.render_widget(Paragraph::new("No saved playlists - save a queue with s").style(Style::default().fg(theme.muted)).block(block), area);
+        } else {
+            let items: Vec<ListItem> = state.playlist_names.iter()
+                .map(|name| ListItem::new(name.as_str()).style(Style::default().fg(theme.item)))
+                .collect();
+            let mut list_state = ListState::default().with_selected(Some(row));
+            frame.render_stateful_widget(
+                List::new(items)
+                    .block(block)
+                    .highlight_style(theme.selected_style()),
+                area,
+                &mut list_state,
+            );
+        }
+    }
+
+    // Name or file prompts opened from the queue popup
+    let prompt = match state.input_mode {
+        InputMode::PlaylistName => Some(("Save playlist", "Save playlist as | Enter=Save | Esc=Cancel")),
+        InputMode::M3uImport => Some(("Import m3u", "Import m3u file | Enter=Load | Esc=Cancel")),
+        InputMode::M3uExport => Some(("Export m3u", "Write queue to m3u file | Enter=Save | Esc=Cancel")),
+        _ => None,
+    };
+    if let Some((name, hint)) = prompt {
+        let area = popup_area(frame.area(), 40, 10);
+        frame.render_widget(Clear, area);
+        let title = match &state.queue_notice {
+            Some(e) => format!("{}: {}", name, e),
+            None => hint.to_string(),
+        };
+        let border = if state.queue_notice.is_some() { theme.error } else { theme.popup_border };
+        frame.render_widget(
+            Paragraph::new(format!("{}_", state.user_input))
+                .style(Style::default().fg(theme.value))
+                .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(border)).title(title)),
+            area,
+        );
+        frame.set_cursor_position(Position {
+            x: area.x + state.character_index as u16 + 1,
+            y: area.y + 1,
+        });
+    }
+
+    // Genre filter: every genre in the library, picked ones marked
+    if let InputMode::GenrePicker(row) = state.input_mode {
+        let genre_items: Vec<ListItem> = state.available_genres()
+            .into_iter()
+            .map(|genre| {
+                let picked = state.genre_filter.iter().any(|g| g.eq_ignore_ascii_case(&genre));
+                let style = if picked { Style::default().fg(theme.positive) } else { Style::default().fg(theme.item) };
+                ListItem::new(format!("[{}] {}", if picked { "x" } else { " " }, genre)).style(style)
+            })
+            .collect();
+        let empty = genre_items.is_empty();
+
+        let area = popup_area(frame.area(), 40, 50);
+        frame.render_widget(Clear, area);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.popup_border))
+            .title("Genres | Space=Toggle | c=Clear | Enter/Esc=Done");
+        if empty {
+            frame.render_widget(Paragraph::new("No genres known yet").style(Style::default().fg(theme.muted)).block(block), area);
+        } else {
+            let mut list_state = ListState::default().with_selected(Some(row));
+            frame.render_stateful_widget(
+                List::new(genre_items)
+                    .block(block)
+                    .highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED)),
+                area,
+                &mut list_state,
+            );
+        }
+    }
+
+    // Present the double feature picks for confirmation
+    if let InputMode::DoubleFeature(first, second) = state.input_mode {
+        let info = |idx: usize| state.movie_info_cache.get(&state.movies[idx].path);
+        let pairing = match (info(first), info(second)) {
+            (Some(a), Some(b)) => a.related_by(b),
+            _ => None,
+        };
+        let lines = vec![
+            Line::from(Span::styled(format!("1. {}", display_title(&state.movies[first], info(first))), theme.selected_style())),
+            Line::from(Span::styled("   ~ intermission ~", Style::default().fg(theme.muted))),
+            Line::from(Span::styled(format!("2. {}", display_title(&state.movies[second], info(second))), theme.selected_style())),
+            Line::from(""),
+            Line::from(Span::styled(pairing.unwrap_or_else(|| "Random pairing".to_string()), Style::default().fg(theme.accent))),
+        ];
+        let area = popup_area(frame.area(), 50, 30);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(theme.panel_border))
+                        .title("Double Feature | Enter=Play | r=Re-roll | Esc=Back")
+                ),
+            area,
+        );
+    }
+
+    // Confirm before lifting the kids-mode filter
+    if state.input_mode == InputMode::ConfirmFilterOff {
+        let area = popup_area(frame.area(), 40, 15);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new("Turn off the kids filter? (y/n)")
+                .style(Style::default().fg(theme.value))
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(theme.error))
+                        .title("Kids Mode")
+                ),
+            area,
+        );
+    }
+
+    if state.input_mode == InputMode::ConfirmDelete && let Some(movie) = state.movies.get(state.selected) {
+        let area = popup_area(frame.area(), 40, 20);
+        frame.render_widget(Clear, area);
+        let name = movie.path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let extra = if state.delete_sidecars { " with its subtitle files" } else { "" };
+        frame.render_widget(
+            Paragraph::new(format!("Delete \"{}\"{} from disk? This cannot be undone. (y/n)", name, extra))
+                .style(Style::default().fg(theme.value))
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(theme.error))
+                        .title("Delete")
+                ),
+            area,
+        );
+    }
+
+    if state.input_mode == InputMode::ConfirmExit {
+        let area = popup_area(frame.area(), 30, 15);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new("Quit the movie player? (y/n)")
+                .style(Style::default().fg(theme.value))
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(theme.error))
+                        .title("Quit")
+                ),
+            area,
+        );
+    }
+
+    // Render Year Filter Popup
+    if state.input_mode == InputMode::YearFilter {
+        let area = popup_area(frame.area(), 40, 10);
+        frame.render_widget(Clear, area);
+        let title = match &state.year_input_error {
+            Some(e) => format!("Years: {}", e),
+            None => "Years (1980s, 1994, 1975-1985, 1990-) | Enter=Apply | Esc=Cancel".to_string(),
+        };
+        let border = if state.year_input_error.is_some() { theme.error } else { theme.popup_border };
+        frame.render_widget(
+            Paragraph::new(format!("{}_", state.user_input))
+                .style(Style::default().fg(theme.value))
+                .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(border)).title(title)),
+            area,
+        );
+        frame.set_cursor_position(Position {
+            x: area.x + state.character_index as u16 + 1,
+            y: area.y + 1,
+        });
+    }
+
+    if state.input_mode == InputMode::Command {
+        let area = popup_area(frame.area(), 60, 10);
+        frame.render_widget(Clear, area);
+        let title = match &state.command_error {
+            Some(e) => format!("Command: {}", e),
+            None => "Command (sort year, filter genre=comedy, queue add, ...) | Enter=Run | Esc=Cancel".to_string(),
+        };
+        let border = if state.command_error.is_some() { theme.error } else { theme.popup_border };
+        frame.render_widget(
+            Paragraph::new(format!(":{}_", state.user_input))
+                .style(Style::default().fg(theme.value))
+                .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(border)).title(title)),
+            area,
+        );
+        frame.set_cursor_position(Position {
+            x: area.x + state.character_index as u16 + 2,
+            y: area.y + 1,
+        });
+    }
+
+    if state.input_mode == InputMode::RenameFile {
+        let area = popup_area(frame.area(), 60, 10);
+        frame.render_widget(Clear, area);
+        let title = match &state.rename_error {
+            Some(e) => format!("Rename: {}", e),
+            None => "Rename file | Enter=Rename | Esc=Cancel".to_string(),
+        };
+        let border = if state.rename_error.is_some() { theme.error } else { theme.popup_border };
+        frame.render_widget(
+            Paragraph::new(format!("{}_", state.user_input))
+                .style(Style::default().fg(theme.value))
+                .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(border)).title(title)),
+            area,
+        );
+        frame.set_cursor_position(Position {
+            x: area.x + state.character_index as u16 + 1,
+            y: area.y + 1,
+        });
+    }
+
+    if state.input_mode == InputMode::ExportList {
+        let area = popup_area(frame.area(), 60, 10);
+        frame.render_widget(Clear, area);
+        let title = match &state.export_error {
+            Some(e) => format!("Export: {}", e),
+            None => format!("Export {} movies to (.csv or .json) | Enter=Save | Esc=Cancel", state.visible_order().len()),
+        };
+        let border = if state.export_error.is_some() { theme.error } else { theme.popup_border };
+        frame.render_widget(
+            Paragraph::new(format!("{}_", state.user_input))
+                .style(Style::default().fg(theme.value))
+                .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(border)).title(title)),
+            area,
+        );
+        frame.set_cursor_position(Position {
+            x: area.x + state.character_index as u16 + 1,
+            y: area.y + 1,
+        });
+    }
+
+    if state.input_mode == InputMode::Rating {
+        let area = popup_area(frame.area(), 40, 10);
+        frame.render_widget(Clear, area);
+        let title = match &state.rating_input_error {
+            Some(e) => format!("Rating: {}", e),
+            None => "Your rating (0-10) | Enter=Save | Esc=Cancel".to_string(),
+        };
+        let border = if state.rating_input_error.is_some() { theme.error } else { theme.popup_border };
+        frame.render_widget(
+            Paragraph::new(format!("{}_", state.user_input))
+                .style(Style::default().fg(theme.value))
+                .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(border)).title(title)),
+            area,
+        );
+        frame.set_cursor_position(Position {
+            x: area.x + state.character_index as u16 + 1,
+            y: area.y + 1,
+        });
+    }
+
+    // Render Search Bar Popup
+    if state.input_mode == InputMode::Search {
+        let area = popup_area(frame.area(), 40, 10);
+        frame.render_widget(Clear, area); // Clear the background
         
-        // Check if 30 seconds have passed since last input
-        if elapsed >= Duration::from_secs(TIMEOUT_SECONDS) {
-            // Auto-select random movie and shuffle queue
-            let random_index = rand::thread_rng().gen_range(0..state.movies.len());
-            *selected_index.borrow_mut() = Some(random_index);
-            shuffle_queue.store(true, Ordering::SeqCst);
-            return Ok(());
-        }
+        // Create the input display with cursor
+        let input_display = format!("{}_", state.user_input);
+        let cursor_position = state.character_index;
         
-        // Poll for events with a short timeout (100ms) to allow checking elapsed time
-        let remaining_time = Duration::from_secs(TIMEOUT_SECONDS) - elapsed;
-        let poll_timeout = remaining_time.min(Duration::from_millis(100));
+        let mode = if state.search_regex { ", regex" } else { "" };
+        let title = match &state.search_error {
+            Some(e) => format!("Search [{}{}]: {}", state.search_scope.label(), mode, e),
+            None => format!("Search [{}{}] | Tab=Scope | Ctrl+R=Regex | Esc=Close", state.search_scope.label(), mode),
+        };
+        let border = if state.search_error.is_some() { theme.error } else { theme.popup_border };
+        let input_paragraph = Paragraph::new(input_display)
+            .style(Style::default().fg(theme.value))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(border))
+                    .title(title)
+            );
         
-        if poll(poll_timeout)? {
-        if let Event::Key(key) = crossterm::event::read()? {
-            if key.kind != KeyEventKind::Press {
-                continue;
-            }
-
-                // Reset the timer on any user input
-                last_input_time = Instant::now();
-
-                // Handle text input when popup is open
-                if state.show_popup {
-                    match key.code {
-                        KeyCode::Esc => {
-                            // Close the popup without exiting the app
-                            state.show_popup = false;
-                            state.clear_input();
-                        }
-                        KeyCode::Char(c) => {
-                            state.enter_char(c);
-                        }
-                        KeyCode::Backspace => {
-                            state.delete_char();
-                        }
-                        KeyCode::Left => {
-                            state.move_cursor_left();
-                        }
-                        KeyCode::Right => {
-                            state.move_cursor_right();
-                        }
-                        KeyCode::Home => {
-                            state.reset_cursor();
-                        }
-                        KeyCode::End => {
-                            state.character_index = state.user_input.chars().count();
-                        }
-                        _ => {}
-                    }
-                } else {
-                    // Handle normal navigation when popup is closed
-                    match key.code {
-                        KeyCode::Esc => {
-                            // Exit the app when popup is not open
-                            *should_exit.borrow_mut() = true;
-                            return Ok(());
-                        }
-                        KeyCode::Up => {
-                            if state.selected > 0 {
-                                state.selected -= 1;
-                            } else {
-                                state.selected = state.movies.len(); // Wrap to "Random Movie"
-                            }
-                        }
-                        KeyCode::Down => {
-                            if state.selected < state.movies.len() {
-                                state.selected += 1;
-                            } else {
-                                state.selected = 0; // Wrap to first movie
-                            }
-                        }
-                        KeyCode::Enter => {
-                            // Store the selected index and exit to restore terminal
-                            let (start_index, should_shuffle) = if state.selected == state.movies.len() {
-                                // Random movie selected - shuffle the queue
-                                (rand::thread_rng().gen_range(0..state.movies.len()), true)
-                            } else if SHUFFLE_QUEUE.load(Ordering::SeqCst) {
-                                // Selected movie - shuffle order
-                                (state.selected, true)
-                            } else {
-                                // Selected movie - keep original order
-                                (state.selected, false)
-                            };
-                            
-                            *selected_index.borrow_mut() = Some(start_index);
-                            shuffle_queue.store(should_shuffle, Ordering::SeqCst);
-                            return Ok(());
-                        }
-                        KeyCode::Char('n') => {
-                            toggle_auto_play_next();
-                        }
-                        KeyCode::Char('s') => {
-                            toggle_shuffle_queue();
-                        }
-                        KeyCode::Char(' ') => {
-                            state.show_popup = !state.show_popup;
-                        }
-                        _ => {}
-                    }
-                }
-            }
-        }
+        frame.render_widget(input_paragraph, area);
+        
+        // Set the cursor position for the terminal
+        frame.set_cursor_position(Position {
+            x: area.x + cursor_position as u16 + 1,
+            y: area.y + 1,
+        });
     }
 }
 
-fn render(frame: &mut Frame, state: &mut AppState, elapsed: Duration, timeout_seconds: u64) {
-    // Split the frame: top taskbar, then main content area
-    let main_chunks = Layout::default()
-        .direction(ratatui::layout::Direction::Vertical)
-        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
-        .split(frame.area());
-    
-    let taskbar_area = main_chunks[0];
-    let content_area = main_chunks[1];
-    
-    // Split the content area into two: left for list, right for info
-    let chunks = Layout::default()
-        .direction(ratatui::layout::Direction::Horizontal)
-        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
-        .split(content_area);
-    
-    let list_area = chunks[0];
-    let info_area = chunks[1];
-    
-    // Render the taskbar
+fn render_taskbar(frame: &mut Frame, taskbar_area: Rect, state: &AppState, remaining_secs: Option<u64>) {
+    let theme = current_theme(state.theme);
     // Get current time and date using chrono
     let now = chrono::Local::now();
     let time_str = now.format("%H:%M:%S").to_string();
     let date_str = now.format("%Y-%m-%d").to_string();
-    
-    // Calculate remaining time until auto-play
-    let remaining = Duration::from_secs(timeout_seconds).saturating_sub(elapsed);
-    let remaining_secs = remaining.as_secs();
-    let timer_str = format!("Auto-play in: {:02}s", remaining_secs);
+    let timer_str = match remaining_secs {
+        Some(secs) => format!(" | Auto-play ({}) in: {:02}s", state.keymap.label(Action::ToggleIdleAutoplay), secs),
+        None if !check_idle_autoplay() => format!(" | Auto-play ({})=off", state.keymap.label(Action::ToggleIdleAutoplay)),
+        None => String::new(),
+    };
     
     // Create taskbar content
-    let taskbar_text = format!("{} | {} | {} | Enter=Play | Esc=Exit | ↑↓=Navigate | Autoplay Next (n)={} | Shuffle (s)={}", 
-        time_str, date_str, timer_str, check_auto_play_next().to_string(), check_shuffle_queue().to_string());
+    let key = |action| state.keymap.label(action);
+    let mut taskbar_text = format!("{} | {}{} | {}=Play | {}=Exit | {}=Help | {}{}=Navigate | Autoplay Next ({})={} | Shuffle ({})={} | Repeat ({})={} | Subtitles ({})={} | Sort ({})={} | Paths ({}) | Watched ({}) | Double Feature ({}) | Mini ({})",
+        time_str, date_str, timer_str, key(Action::Play), key(Action::Quit), key(Action::ShowHelp), key(Action::NavigateUp), key(Action::NavigateDown),
+        key(Action::ToggleAutoNext), check_auto_play_next(), key(Action::ToggleShuffle), check_shuffle_queue(),
+        key(Action::CycleRepeat), state.repeat.label(), key(Action::ToggleSubtitles), if check_subtitles() { "on" } else { "off" }, key(Action::CycleSort), state.sort_mode.label(), key(Action::TogglePaths), key(Action::ToggleWatched), key(Action::DoubleFeature), key(Action::ToggleMiniMode));
+    if state.flat_list {
+        taskbar_text.push_str(&format!(" | Flat ({})", key(Action::ToggleFlatList)));
+    }
+    if state.sort_mode == SortMode::Custom {
+        taskbar_text.push_str(&format!(" | {}{}=Reorder", key(Action::MoveUp), key(Action::MoveDown)));
+    }
+    if let Some(volume) = state.volume {
+        taskbar_text.push_str(&format!(" | Volume ({}{})={}%", key(Action::VolumeDown), key(Action::VolumeUp), volume));
+    }
+    if let Some(speed) = state.speed {
+        taskbar_text.push_str(&format!(" | Speed ({})={}x", key(Action::CycleSpeed), speed));
+    }
+    if state.api_timed_out && state.metadata_cached_at.is_none() {
+        taskbar_text.push_str(" | API timed out: showing files only");
+    }
+    if let Some(fetched_at) = state.metadata_cached_at {
+        let hours = (chrono::Local::now() - fetched_at).num_hours();
+        taskbar_text.push_str(&format!(" | Offline: metadata cached {}h ago", hours));
+    }
+    if state.show_popup() {
+        taskbar_text = format!("-- {} -- | {}", state.input_mode.label(), taskbar_text);
+    }
+    if !state.genre_filter.is_empty() {
+        taskbar_text.push_str(&format!(" | Genres ({})={}", key(Action::FilterGenres), state.genre_filter.join(", ")));
+    }
+    if state.type_ahead_active() {
+        taskbar_text.push_str(&format!(" | Jump: {}", state.type_ahead));
+    }
+    if state.count_active() {
+        taskbar_text.push_str(&format!(" | Count: {}", state.count));
+    }
+    if let Some(range) = state.year_range {
+        taskbar_text.push_str(&format!(" | Years ({})={}", key(Action::FilterYears), range));
+    }
+    if !state.user_queue.is_empty() {
+        taskbar_text.push_str(&format!(" | Queue ({})={}", key(Action::ShowQueue), state.user_queue.len()));
+    }
+    if state.favorites_only {
+        taskbar_text.push_str(&format!(" | Favorites only ({})", key(Action::FavoritesOnly)));
+    }
+    if state.filters_active() {
+        taskbar_text.push_str(&format!(" | {}=Reset Filters", key(Action::ResetFilters)));
+    }
+    if let Some(min) = state.min_rating {
+        taskbar_text.push_str(&format!(" | Rating ≥{:.1} ({}/{})", min, key(Action::RaiseMinRating), key(Action::LowerMinRating)));
+    }
+    if let Some(max_rating) = &state.content_filter.max_rating {
+        let label = if state.content_filter.kids_mode { "Kids" } else { "Rated" };
+        let status = if state.content_filter.enabled { format!("≤{}", max_rating) } else { "off".to_string() };
+        taskbar_text.push_str(&format!(" | {} ({})={}", label, key(Action::ToggleContentFilter), status));
+    }
     
+    // The latest status message sits on the bottom border, clear of the long key list
+    let mut taskbar_block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.taskbar_border));
+    if let Some((message, _)) = &state.status_message {
+        taskbar_block = taskbar_block.title_bottom(Span::styled(
+            format!(" {} ", message),
+            Style::default().fg(theme.status).add_modifier(Modifier::BOLD),
+        ));
+    }
     let taskbar = Paragraph::new(taskbar_text)
-        .style(Style::default().fg(Color::White))
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Cyan))
-        );
+        .style(Style::default().fg(theme.value))
+        .block(taskbar_block);
     
     frame.render_widget(taskbar, taskbar_area);
-    
-    // Build display list with group headers
-    let mut items: Vec<ListItem> = Vec::new();
-    let mut current_group: Option<&str> = None;
-    let mut selected_display_index = 0; // Track where selected item appears in display list
-    
-    for (movie_idx, movie) in state.movies.iter().enumerate() {
-        // Add group header if this is a new group
-        if current_group != Some(movie.group_name.as_str()) {
-            current_group = Some(movie.group_name.as_str());
-            let header_text = format!("┌─ {} ─┐", movie.group_name);
-            items.push(ListItem::new(header_text)
-                .style(Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD)));
-        }
-        
-        // Add movie item
-        let name = movie.path.file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("Unknown");
-        let prefix = if movie_idx == state.selected { "> " } else { "  " };
-        let item_text = format!("{}{}", prefix, name);
-        
-        // Style selected items with bright cyan, unselected with gray
-        let style = if movie_idx == state.selected {
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD)
-        } else {
-            Style::default()
-                .fg(Color::Gray)
-        };
-        
-        items.push(ListItem::new(item_text).style(style));
-        
-        // Track display index for selected movie (after adding to list)
-        if movie_idx == state.selected {
-            selected_display_index = items.len() - 1;
-        }
+
+    if state.input_mode == InputMode::Stats {
+        render_stats(frame, &state.library_stats, theme);
     }
-    
-    // Add separator and "Random Movie" option with its own group
-    items.push(ListItem::new("┌─ Special ─┐")
-        .style(Style::default()
-            .fg(Color::Yellow)
-            .add_modifier(Modifier::BOLD)));
-    
-    let random_movie_idx = state.movies.len();
-    if state.selected == random_movie_idx {
-        selected_display_index = items.len();
+    if state.input_mode == InputMode::Help {
+        render_help(frame, &state.keymap, theme);
     }
-    
-    let random_prefix = if state.selected == random_movie_idx { "> " } else { "  " };
-    let random_style = if state.selected == random_movie_idx {
-        Style::default()
-            .fg(Color::Cyan)
-            .add_modifier(Modifier::BOLD)
+}
+
+/// Full-screen list of every action and its keys, in two columns of categories
+fn render_help(frame: &mut Frame, keymap: &Keymap, theme: Theme) {
+    let category_lines = |categories: &[(&'static str, &'static [(Action, &'static str)])]| {
+        let mut lines = Vec::new();
+        for (name, entries) in categories {
+            lines.push(Line::from(Span::styled(*name, Style::default().fg(theme.label).add_modifier(Modifier::BOLD))));
+            for &(action, description) in *entries {
+                let labels = keymap.labels(action);
+                let keys = if labels.is_empty() { "-".to_string() } else { labels.join(", ") };
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {:<14}", keys), Style::default().fg(theme.accent)),
+                    Span::styled(description, Style::default().fg(theme.value)),
+                ]));
+            }
+            lines.push(Line::from(""));
+        }
+        lines
+    };
+
+    let area = frame.area();
+    frame.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.panel_border))
+        .title_style(Style::default().fg(theme.title).add_modifier(Modifier::BOLD))
+        .title("Keys | Esc/?=Back");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let [left, right] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(inner);
+    let (first, second) = keys::HELP.split_at(keys::HELP.len().div_ceil(2));
+    frame.render_widget(Paragraph::new(category_lines(first)), left);
+    frame.render_widget(Paragraph::new(category_lines(second)), right);
+}
+
+/// Full-screen library totals, drawn over the list
+fn render_stats(frame: &mut Frame, stats: &LibraryStats, theme: Theme) {
+    let label = |name: &str| Span::styled(format!("{:<16}", name), Style::default().fg(theme.label).add_modifier(Modifier::BOLD));
+    let value = |text: String| Span::styled(text, Style::default().fg(theme.value));
+    let of_total = |count: usize| if count < stats.movies { format!(" ({} of {} known)", count, stats.movies) } else { String::new() };
+
+    let hours = stats.runtime_secs / 3600;
+    let minutes = (stats.runtime_secs % 3600) / 60;
+    let runtime = if hours >= 24 {
+        format!("{}h {}m, {:.1} days{}", hours, minutes, stats.runtime_secs as f64 / 86400.0, of_total(stats.timed))
     } else {
-        Style::default()
-            .fg(Color::Gray)
+        format!("{}h {}m{}", hours, minutes, of_total(stats.timed))
+    };
+    let rating = match stats.average_rating {
+        Some(avg) => format!("{:.1}/10{}", avg, of_total(stats.rated)),
+        None => "no ratings yet".to_string(),
     };
-    items.push(ListItem::new(format!("{}Random Movie", random_prefix)).style(random_style));
 
-    // Calculate visible area (accounting for borders - 2 lines for top/bottom borders)
-    let visible_height = list_area.height.saturating_sub(2);
-    
-    // Update scroll offset to keep selected item visible
-    if selected_display_index < state.scroll_offset {
-        // Selected item is above visible area, scroll up
-        state.scroll_offset = selected_display_index;
-    } else if selected_display_index >= state.scroll_offset + visible_height as usize {
-        // Selected item is below visible area, scroll down
-        state.scroll_offset = selected_display_index.saturating_sub(visible_height as usize - 1);
-    }
-    
-    // Ensure scroll offset doesn't go beyond bounds
-    if state.scroll_offset + visible_height as usize > items.len() {
-        state.scroll_offset = items.len().saturating_sub(visible_height as usize);
+    let mut lines = vec![
+        Line::from(vec![label("Movies"), value(stats.movies.to_string())]),
+        Line::from(vec![label("Total runtime"), value(runtime)]),
+        Line::from(vec![label("Average rating"), value(rating)]),
+        Line::from(vec![label("Times watched"), value(stats.watch_count.to_string())]),
+        Line::from(""),
+        Line::from(label("Genres")),
+    ];
+    if stats.genres.is_empty() {
+        lines.push(Line::from(Span::styled("  No genres known yet", Style::default().fg(theme.muted))));
     }
-    if state.scroll_offset > items.len() {
-        state.scroll_offset = 0;
+    let widest = stats.genres.iter().map(|(genre, _)| genre.chars().count()).max().unwrap_or(0);
+    let most = stats.genres.first().map_or(1, |(_, count)| *count).max(1);
+    for (genre, count) in &stats.genres {
+        let bar = "█".repeat((count * 30).div_ceil(most));
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {:<width$} {:>4} ", genre, count, width = widest), Style::default().fg(theme.item)),
+            Span::styled(bar, Style::default().fg(theme.positive)),
+        ]));
     }
-    
-    // Get visible slice of items
-    let end_index = (state.scroll_offset + visible_height as usize).min(items.len());
-    let visible_items: Vec<ListItem> = items[state.scroll_offset..end_index].to_vec();
 
-    let list = List::new(visible_items)
-        .block(
+    let area = frame.area();
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Blue))
-                .title_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
-                .title("Select a Movie")
-        );
+                .border_style(Style::default().fg(theme.panel_border))
+                .title_style(Style::default().fg(theme.title).add_modifier(Modifier::BOLD))
+                .title("Library Stats | Esc=Back")
+        ),
+        area,
+    );
+}
 
-    frame.render_widget(list, list_area);
-    
-    // Render the info panel
-    let info_lines: Vec<Line> = if state.selected < state.movies.len() {
+fn render_info_panel(frame: &mut Frame, state: &mut AppState, info_area: Rect) {
+    let theme = current_theme(state.theme);
+    let mut poster_url = None;
+    let mut selected_path = None;
+    let mut info_lines: Vec<Line> = if state.selected < state.movies.len() {
         let movie = &state.movies[state.selected];
         
         // Get or cache movie info (DB-backed). If not present, fallback to file probe
-        let movie_info = state.movie_info_cache.entry(movie.path.clone()).or_insert_with(|| get_movie_info(&movie.path));
+        let movie_info = state.movie_info_cache.entry(movie.path.clone()).or_insert_with(|| with_user_rating(&state.ratings, &movie.path, get_movie_info(&movie.path)));
+        poster_url = movie_info.poster_url.clone();
+        selected_path = Some(movie.path.clone());
 
         // Prefer DB title if present; otherwise show filename
-        let title = movie_info.title.clone().or_else(|| movie.path.file_stem().and_then(|s| s.to_str().map(|s| s.to_string()))).unwrap_or_else(|| "Unknown".to_string());
+        let title = display_title(movie, Some(movie_info));
 
         let mut lines: Vec<Line> = Vec::new();
         lines.push(Line::from(vec![
-            Span::styled("Title: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
-            Span::styled(title, Style::default().fg(Color::White)),
+            Span::styled("Title: ", Style::default().fg(theme.label).add_modifier(Modifier::BOLD)),
+            Span::styled(title, Style::default().fg(theme.value)),
         ]));
+        if let Some(episode) = EpisodeInfo::from_path(&movie.path) {
+            lines.push(Line::from(vec![
+                Span::styled("Episode: ", Style::default().fg(theme.label).add_modifier(Modifier::BOLD)),
+                Span::styled(format!("Season {}, Episode {}", episode.season, episode.episode), Style::default().fg(theme.value)),
+            ]));
+        }
+        if state.resume_enabled && state.resume_times.contains_key(&movie.path) {
+            lines.push(Line::from(vec![
+                Span::styled("▶ resume available", Style::default().fg(theme.positive).add_modifier(Modifier::BOLD)),
+                Span::styled(format!(" ({}=start over)", state.keymap.label(Action::PlayFromStart)), Style::default().fg(theme.muted)),
+            ]));
+        }
 
         // Year
         if let Some(y) = movie_info.year {
             lines.push(Line::from(vec![
-                Span::styled("Year: ", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
-                Span::styled(y.to_string(), Style::default().fg(Color::White)),
+                Span::styled("Year: ", Style::default().fg(theme.field_label("Year")).add_modifier(Modifier::BOLD)),
+                Span::styled(y.to_string(), Style::default().fg(theme.value)),
             ]));
         }
 
         // Genre
         if let Some(ref g) = movie_info.genre {
             lines.push(Line::from(vec![
-                Span::styled("Genre: ", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-                Span::styled(g.clone(), Style::default().fg(Color::White)),
+                Span::styled("Genre: ", Style::default().fg(theme.field_label("Genre")).add_modifier(Modifier::BOLD)),
+                Span::styled(g.clone(), Style::default().fg(theme.value)),
             ]));
         }
 
         // Director
         if let Some(ref d) = movie_info.director {
             lines.push(Line::from(vec![
-                Span::styled("Director: ", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
-                Span::styled(d.clone(), Style::default().fg(Color::White)),
+                Span::styled("Director: ", Style::default().fg(theme.field_label("Director")).add_modifier(Modifier::BOLD)),
+                Span::styled(d.clone(), Style::default().fg(theme.value)),
             ]));
         }
 
         // Runtime (DB runtime preferred, else file probe runtime)
         if let Some(ref rtime) = movie_info.runtime {
             lines.push(Line::from(vec![
-                Span::styled("Runtime: ", Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
-                Span::styled(rtime.clone(), Style::default().fg(Color::White)),
+                Span::styled("Runtime: ", Style::default().fg(theme.field_label("Runtime")).add_modifier(Modifier::BOLD)),
+                Span::styled(rtime.clone(), Style::default().fg(theme.value)),
             ]));
         }
 
-        // Rating
-        if let Some(r) = movie_info.rating {
+        // Rating, as five stars and the number
+        let mut rating_spans = vec![Span::styled("Rating: ", Style::default().fg(theme.field_label("Rating")).add_modifier(Modifier::BOLD))];
+        rating_spans.extend(rating_stars(movie_info.rating, theme));
+        rating_spans.push(match movie_info.rating {
+            Some(r) => Span::styled(format!(" {:.1}", r), Style::default().fg(theme.value)),
+            None => Span::styled(" unrated", Style::default().fg(theme.muted)),
+        });
+        lines.push(Line::from(rating_spans));
+
+        // Watch count
+        if let Some(wc) = movie_info.watch_count {
             lines.push(Line::from(vec![
-                Span::styled("Rating: ", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
-                Span::styled(format!("{:.1}", r), Style::default().fg(Color::White)),
+                Span::styled("Watch Count: ", Style::default().fg(theme.field_label("Watch Count")).add_modifier(Modifier::BOLD)),
+                Span::styled(wc.to_string(), Style::default().fg(theme.value)),
             ]));
         }
 
-        // Watch count
-        if let Some(wc) = movie_info.watch_count {
+        // Content rating
+        if let Some(ref cr) = movie_info.content_rating {
+            lines.push(Line::from(vec![
+                Span::styled("Rated: ", Style::default().fg(theme.label).add_modifier(Modifier::BOLD)),
+                Span::styled(cr.clone(), Style::default().fg(theme.value)),
+            ]));
+        }
+
+        // Other local files mapped to the same API entry
+        if !movie_info.duplicates.is_empty() {
+            let (label, note) = if !movie.versions.is_empty() {
+                ("Versions: ", format!("{} more (Enter to choose)", movie.versions.len()))
+            } else if movie_info.duplicates.iter().all(|d| state.movies.iter().any(|m| m.path == *d)) {
+                ("Shared With: ", format!("{} other file(s)", movie_info.duplicates.len()))
+            } else {
+                ("Hidden Copies: ", format!("{} lower quality", movie_info.duplicates.len()))
+            };
             lines.push(Line::from(vec![
-                Span::styled("Watch Count: ", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-                Span::styled(wc.to_string(), Style::default().fg(Color::White)),
+                Span::styled(label, Style::default().fg(theme.label).add_modifier(Modifier::BOLD)),
+                Span::styled(note, Style::default().fg(theme.value)),
             ]));
+            for dup in &movie_info.duplicates {
+                let name = dup.file_name().and_then(|n| n.to_str()).unwrap_or("Unknown");
+                lines.push(Line::from(Span::styled(format!("  {}", name), Style::default().fg(theme.muted))));
+            }
         }
 
         // Plot (wrap as single paragraph line)
         if let Some(ref ptxt) = movie_info.plot {
             lines.push(Line::from(""));
             lines.push(Line::from(vec![
-                Span::styled("Plot: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
-                Span::styled(ptxt.clone(), Style::default().fg(Color::White)),
+                Span::styled("Plot: ", Style::default().fg(theme.field_label("Plot")).add_modifier(Modifier::BOLD)),
+                Span::styled(ptxt.clone(), Style::default().fg(theme.value)),
             ]));
         }
 
         // File-level metadata fallbacks: file size, codec, resolution
         if let Some(ref fsz) = movie_info.file_size {
             lines.push(Line::from(vec![
-                Span::styled("File Size: ", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-                Span::styled(fsz.clone(), Style::default().fg(Color::White)),
+                Span::styled("File Size: ", Style::default().fg(theme.field_label("File Size")).add_modifier(Modifier::BOLD)),
+                Span::styled(fsz.clone(), Style::default().fg(theme.value)),
             ]));
         }
         if let Some(ref c) = movie_info.codec {
             lines.push(Line::from(vec![
-                Span::styled("Codec: ", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
-                Span::styled(c.clone(), Style::default().fg(Color::White)),
+                Span::styled("Codec: ", Style::default().fg(theme.field_label("Codec")).add_modifier(Modifier::BOLD)),
+                Span::styled(c.clone(), Style::default().fg(theme.value)),
             ]));
         }
         if let Some(ref res) = movie_info.resolution {
             lines.push(Line::from(vec![
-                Span::styled("Resolution: ", Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
-                Span::styled(res.clone(), Style::default().fg(Color::White)),
+                Span::styled("Resolution: ", Style::default().fg(theme.field_label("Resolution")).add_modifier(Modifier::BOLD)),
+                Span::styled(res.clone(), Style::default().fg(theme.value)),
+            ]));
+        }
+        if !movie_info.chapters.is_empty() {
+            lines.push(Line::from(vec![
+                Span::styled("Chapters: ", Style::default().fg(theme.label).add_modifier(Modifier::BOLD)),
+                Span::styled(movie_info.chapters.len().to_string(), Style::default().fg(theme.value)),
+            ]));
+        }
+        if !movie_info.subtitles.is_empty() {
+            let found: Vec<String> = movie_info.subtitles.iter().map(|s| subtitles::describe(s, &movie.path)).collect();
+            lines.push(Line::from(vec![
+                Span::styled("Subtitles: ", Style::default().fg(theme.label).add_modifier(Modifier::BOLD)),
+                Span::styled(found.join(", "), Style::default().fg(theme.value)),
             ]));
         }
 
         lines
     } else {
         vec![Line::from(vec![
-            Span::styled("Select a movie to see details", Style::default().fg(Color::DarkGray)),
+            Span::styled("Select a movie to see details", Style::default().fg(theme.muted)),
         ])]
     };
     
+    if state.metadata_loading {
+        const SPINNER: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+        let frame_idx = (chrono::Local::now().timestamp_subsec_millis() / 100) as usize % SPINNER.len();
+        info_lines.insert(0, Line::from(Span::styled(
+            format!("{} Loading metadata…", SPINNER[frame_idx]),
+            Style::default().fg(theme.muted),
+        )));
+    }
+
+    // A new selection starts at the top; otherwise stop once the last line is in view
+    if state.info_scroll_for != state.selected {
+        state.info_scroll_for = state.selected;
+        state.info_scroll_offset = 0;
+    }
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.panel_border))
+        .title_style(Style::default().fg(theme.title).add_modifier(Modifier::BOLD));
+    let mut text_area = block.inner(info_area);
+
+    // The poster (or a frame from the film when there is none) takes the top of the panel once
+    // it is cached, if the panel is tall enough to keep room for the text below it
+    if text_area.height >= POSTER_HEIGHT * 2
+        && let Some(posters) = &mut state.posters
+        && let Some(path) = match (poster_url, selected_path) {
+            (Some(url), _) => posters.poster(&url),
+            (None, Some(video)) => posters.frame(&video),
+            (None, None) => None,
+        } {
+        let [poster_area, rest] = Layout::vertical([Constraint::Length(POSTER_HEIGHT), Constraint::Fill(1)]).areas(text_area);
+        if posters.draw(frame, poster_area, &path) {
+            text_area = rest;
+        }
+    }
+
+    let inner_width = text_area.width.max(1) as usize;
+    let inner_height = text_area.height as usize;
+    let wrapped_lines: usize = info_lines.iter().map(|line| line.width().max(1).div_ceil(inner_width)).sum();
+    let max_scroll = wrapped_lines.saturating_sub(inner_height).min(u16::MAX as usize) as u16;
+    state.info_scroll_offset = state.info_scroll_offset.min(max_scroll);
+    let title = match (state.info_scroll_offset, max_scroll) {
+        (_, 0) => "Movie Info".to_string(),
+        (offset, max) if offset < max => format!("Movie Info ▼ {}", state.keymap.label(Action::ScrollInfoDown)),
+        _ => format!("Movie Info ▲ {}", state.keymap.label(Action::ScrollInfoUp)),
+    };
+
     let info_paragraph = Paragraph::new(info_lines)
         .wrap(Wrap { trim: true })
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Magenta))
-                .title_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
-                .title("Movie Info")
-        );
-    
-    frame.render_widget(info_paragraph, info_area);
+        .scroll((state.info_scroll_offset, 0));
 
-    // Render Search Bar Popup
-    if state.show_popup {
-        let area = popup_area(frame.area(), 20, 10);
-        frame.render_widget(Clear, area); // Clear the background
-        
-        // Create the input display with cursor
-        let input_display = format!("{}_", state.user_input);
-        let cursor_position = state.character_index;
-        
-        let input_paragraph = Paragraph::new(input_display)
-            .style(Style::default().fg(Color::White))
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Green))
-                    .title("Search | Press ESC to exit")
-            );
-        
-        frame.render_widget(input_paragraph, area);
-        
-        // Set the cursor position for the terminal
-        frame.set_cursor_position(Position {
-            x: area.x + cursor_position as u16 + 1,
-            y: area.y + 1,
-        });
+    frame.render_widget(block.title(title), info_area);
+    frame.render_widget(info_paragraph, text_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library() -> Vec<MovieEntry> {
+        [("a.mkv", "Root"), ("b.mkv", "Action"), ("c.mkv", "Action"), ("d.mkv", "Root"), ("e.mkv", "Action")]
+            .into_iter()
+            .map(|(name, group)| MovieEntry { path: PathBuf::from(name), group_name: group.to_string(), versions: Vec::new() })
+            .collect()
+    }
+
+    #[test]
+    fn play_order_wraps_around_from_the_start() {
+        let movies = library();
+        let pool = [0, 1, 2, 3, 4];
+        assert_eq!(build_play_order(&movies, &pool, 0, false, AutoplayScope::Library), vec![0, 1, 2, 3, 4]);
+        assert_eq!(build_play_order(&movies, &pool, 3, false, AutoplayScope::Library), vec![3, 4, 0, 1, 2]);
+        // The display order, not the library order, is followed
+        assert_eq!(build_play_order(&movies, &[4, 2, 0], 2, false, AutoplayScope::Library), vec![2, 0, 4]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn play_order_scopes() {
+        let movies = library();
+        let pool = [0, 1, 2, 3, 4];
+        assert_eq!(build_play_order(&movies, &pool, 2, false, AutoplayScope::Group), vec![2, 4, 1]);
+        // The filter scope gets the visible movies as its pool; an unlisted start begins at the top
+        assert_eq!(build_play_order(&movies, &[1, 3], 3, false, AutoplayScope::Filter), vec![3, 1]);
+        assert_eq!(build_play_order(&movies, &[1, 3], 0, false, AutoplayScope::Filter), vec![1, 3]);
+        assert!(build_play_order(&movies, &[], 0, false, AutoplayScope::Library).is_empty());
+        assert!(build_play_order(&movies, &[0, 3], 1, false, AutoplayScope::Group).is_empty());
+    }
+
+    #[test]
+    fn shuffled_play_order_starts_with_the_selection() {
+        let movies = library();
+        let pool = [0, 1, 2, 3, 4];
+        let order = build_play_order(&movies, &pool, 3, true, AutoplayScope::Library);
+        assert_eq!(order[0], 3);
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, pool);
+
+        let mut group = build_play_order(&movies, &pool, 1, true, AutoplayScope::Group);
+        assert_eq!(group[0], 1);
+        group.sort();
+        assert_eq!(group, vec![1, 2, 4]);
+    }
+
+    fn duplicates() -> (Vec<PathBuf>, Vec<MovieEntry>, HashMap<PathBuf, MovieInfo>) {
+        let files: Vec<PathBuf> = ["Heat.720p.mkv", "Heat.2160p.mkv", "Heat.1080p.mkv"].into_iter().map(PathBuf::from).collect();
+        let movies = files.iter().chain([&PathBuf::from("Ronin.mkv")])
+            .map(|path| MovieEntry { path: path.clone(), group_name: "Root".to_string(), versions: Vec::new() })
+            .collect();
+        let info_map = files.iter().map(|path| (path.clone(), MovieInfo::default())).collect();
+        (files, movies, info_map)
+    }
+
+    fn listed(movies: &[MovieEntry]) -> Vec<&str> {
+        movies.iter().map(|m| m.path.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn quality_ranks_the_resolution_in_the_name() {
+        // None of these exist, so the size is 0 throughout
+        assert_eq!(quality_score(Path::new("Heat.4K.mkv")), (2160, 0));
+        assert_eq!(quality_score(Path::new("Heat.UHD.mkv")), (2160, 0));
+        assert_eq!(quality_score(Path::new("Heat.1080p.mkv")), (1080, 0));
+        assert_eq!(quality_score(Path::new("Heat.720p.mkv")), (720, 0));
+        assert_eq!(quality_score(Path::new("Heat.480p.mkv")), (480, 0));
+        assert_eq!(quality_score(Path::new("Heat.mkv")), (0, 0));
+    }
+
+    #[test]
+    fn best_policy_lists_only_the_best_version() {
+        let (files, mut movies, mut info_map) = duplicates();
+        apply_duplicate_policy(DuplicatePolicy::Best, &files, &mut movies, &mut info_map);
+        assert_eq!(listed(&movies), vec!["Heat.2160p.mkv", "Ronin.mkv"]);
+        assert!(movies[0].versions.is_empty());
+    }
+
+    #[test]
+    fn collapse_policy_offers_the_others_as_versions_best_first() {
+        let (files, mut movies, mut info_map) = duplicates();
+        apply_duplicate_policy(DuplicatePolicy::Collapse, &files, &mut movies, &mut info_map);
+        assert_eq!(listed(&movies), vec!["Heat.2160p.mkv", "Ronin.mkv"]);
+        assert_eq!(movies[0].versions, vec![PathBuf::from("Heat.1080p.mkv"), PathBuf::from("Heat.720p.mkv")]);
+    }
+
+    #[test]
+    fn share_policy_keeps_every_file() {
+        let (files, mut movies, mut info_map) = duplicates();
+        apply_duplicate_policy(DuplicatePolicy::Share, &files, &mut movies, &mut info_map);
+        assert_eq!(listed(&movies), vec!["Heat.720p.mkv", "Heat.2160p.mkv", "Heat.1080p.mkv", "Ronin.mkv"]);
+        // Each file still knows about the others
+        assert_eq!(info_map[Path::new("Heat.720p.mkv")].duplicates, vec![PathBuf::from("Heat.2160p.mkv"), PathBuf::from("Heat.1080p.mkv")]);
+    }
+
+    #[test]
+    fn equal_quality_keeps_the_first_file_listed() {
+        let files = vec![PathBuf::from("Heat.b.1080p.mkv"), PathBuf::from("Heat.a.1080p.mkv")];
+        let mut movies: Vec<MovieEntry> = files.iter()
+            .map(|path| MovieEntry { path: path.clone(), group_name: "Root".to_string(), versions: Vec::new() })
+            .collect();
+        apply_duplicate_policy(DuplicatePolicy::Collapse, &files, &mut movies, &mut HashMap::new());
+        assert_eq!(listed(&movies), vec!["Heat.b.1080p.mkv"]);
+        assert_eq!(movies[0].versions, vec![PathBuf::from("Heat.a.1080p.mkv")]);
+    }
+
+    #[test]
+    fn watch_counts_from_the_time_open_without_a_duration() {
+        let threshold = WatchThreshold { secs: 60, percent: 90 };
+        assert!(threshold.reached(Duration::from_secs(60), None));
+        assert!(!threshold.reached(Duration::from_millis(59_999), None));
+    }
+
+    #[test]
+    fn watch_counts_from_the_share_reached() {
+        let threshold = WatchThreshold { secs: 600, percent: 90 };
+        assert!(threshold.reached(Duration::from_secs(10), Some(0.9)));
+        assert!(!threshold.reached(Duration::from_secs(10), Some(0.89)));
+        // Either one is enough
+        assert!(threshold.reached(Duration::from_secs(600), Some(0.1)));
+    }
+
+    #[test]
+    fn truncation_marks_the_cut() {
+        assert_eq!(truncate_with_ellipsis("Heat", 4, false), "Heat");
+        assert_eq!(truncate_with_ellipsis("Heat (1995)", 6, false), "Heat …");
+        assert_eq!(truncate_with_ellipsis("Heat", 1, false), "…");
+        assert_eq!(truncate_with_ellipsis("Heat", 0, false), "");
+        assert_eq!(truncate_with_ellipsis("", 0, false), "");
+    }
+
+    #[test]
+    fn truncation_counts_characters_not_bytes() {
+        assert_eq!(truncate_with_ellipsis("Amélie", 6, false), "Amélie");
+        assert_eq!(truncate_with_ellipsis("千と千尋の神隠し", 4, false), "千と千…");
+        assert_eq!(truncate_with_ellipsis("千と千尋の神隠し", 4, true), "…神隠し");
+    }
+
+    #[test]
+    fn paths_keep_their_end() {
+        assert_eq!(truncate_with_ellipsis("/mnt/movies/Action/Heat.mkv", 9, true), "…Heat.mkv");
+        assert_eq!(truncate_with_ellipsis("/mnt/movies/Action/Heat.mkv", 1, true), "…");
+    }
+
+    #[test]
+    fn roots_with_the_same_name_get_their_parents() {
+        let roots = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+        assert_eq!(root_labels(&roots(&["/mnt/nas/Movies", "/home/me/Films"])), ["Movies", "Films"]);
+        assert_eq!(root_labels(&roots(&["/mnt/a/Movies", "/mnt/b/Movies", "/srv/Films"])), ["a/Movies", "b/Movies", "Films"]);
+        // Parents are added until the labels differ
+        assert_eq!(root_labels(&roots(&["/mnt/x/media/Movies", "/mnt/y/media/Movies"])), ["x/media/Movies", "y/media/Movies"]);
+        assert_eq!(root_labels(&roots(&["/"])), ["/"]);
+    }
+}
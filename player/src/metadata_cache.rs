@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::MovieInfo;
+use crate::persist;
+
+/// API metadata as last fetched, used when the API cannot be reached
+#[derive(Serialize, Deserialize)]
+struct MetadataCache {
+    // Unix timestamp of the fetch
+    fetched_at: i64,
+    movies: HashMap<PathBuf, MovieInfo>,
+}
+
+fn cache_path() -> Option<PathBuf> {
+    persist::cache_dir().map(|dir| dir.join("metadata.json"))
+}
+
+/// Store the metadata from a successful API fetch
+pub fn save(info_map: &HashMap<PathBuf, MovieInfo>) {
+    if let Some(path) = cache_path() {
+        save_to(&path, info_map, Local::now());
+    }
+}
+
+fn save_to(path: &Path, info_map: &HashMap<PathBuf, MovieInfo>, fetched_at: DateTime<Local>) {
+    let cache = MetadataCache {
+        fetched_at: fetched_at.timestamp(),
+        movies: info_map.clone(),
+    };
+    if let Err(e) = persist::save_json(path, &cache) {
+        log::warn!("Failed to save metadata cache to {}: {}", path.display(), e);
+    }
+}
+
+/// The cached metadata and when it was fetched
+pub fn load() -> Option<(HashMap<PathBuf, MovieInfo>, DateTime<Local>)> {
+    load_from(&cache_path()?)
+}
+
+fn load_from(path: &Path) -> Option<(HashMap<PathBuf, MovieInfo>, DateTime<Local>)> {
+    let cache: MetadataCache = persist::load_json(path)?;
+    let fetched_at = DateTime::from_timestamp(cache.fetched_at, 0)?.with_timezone(&Local);
+    Some((cache.movies, fetched_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::test_dir;
+
+    #[test]
+    fn round_trips_the_metadata_and_fetch_time() {
+        let dir = test_dir("metadata");
+        let file = dir.join("metadata.json");
+        let mut info_map = HashMap::new();
+        info_map.insert(PathBuf::from("/movies/Heat.mkv"), MovieInfo { title: Some("Heat".to_string()), year: Some(1995), ..Default::default() });
+        let fetched_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap().with_timezone(&Local);
+        save_to(&file, &info_map, fetched_at);
+
+        let (movies, at) = load_from(&file).unwrap();
+        assert_eq!(at, fetched_at);
+        let heat = &movies[Path::new("/movies/Heat.mkv")];
+        assert_eq!((heat.title.as_deref(), heat.year), (Some("Heat"), Some(1995)));
+    }
+
+    #[test]
+    fn missing_or_unreadable_caches_fall_back_to_nothing() {
+        let dir = test_dir("metadata-bad");
+        let file = dir.join("metadata.json");
+        assert!(load_from(&file).is_none());
+        std::fs::write(&file, "{\"fetched_at\": \"yesterday\"}").unwrap();
+        assert!(load_from(&file).is_none());
+    }
+}
@@ -0,0 +1,194 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Where playback should begin, in one of the forms mpv's `--start` understands
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StartSpec {
+    Seconds(f64),
+    Percent(f64),
+    // 1-based chapter index
+    Chapter(u32),
+}
+
+impl StartSpec {
+    /// Parse a user-provided start position:
+    /// `630`, `630s`, `10:30` or `1:02:03` (time), `10%` (percentage),
+    /// `#2`, `ch2` or `chapter 2` (chapter).
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        if spec.is_empty() {
+            return Err("start position is empty".to_string());
+        }
+
+        if let Some(pct) = spec.strip_suffix('%') {
+            let value: f64 = pct.trim().parse().map_err(|_| format!("invalid percentage: {}", spec))?;
+            if !(0.0..=100.0).contains(&value) {
+                return Err(format!("percentage must be between 0 and 100: {}", spec));
+            }
+            return Ok(StartSpec::Percent(value));
+        }
+
+        let lower = spec.to_lowercase();
+        let chapter = lower.strip_prefix('#')
+            .or_else(|| lower.strip_prefix("chapter"))
+            .or_else(|| lower.strip_prefix("ch"));
+        if let Some(chapter) = chapter {
+            let index: u32 = chapter.trim().parse().map_err(|_| format!("invalid chapter: {}", spec))?;
+            if index == 0 {
+                return Err(format!("chapters are numbered from 1: {}", spec));
+            }
+            return Ok(StartSpec::Chapter(index));
+        }
+
+        let time = lower.strip_suffix('s').unwrap_or(&lower);
+        let mut seconds = 0.0;
+        let parts: Vec<&str> = time.split(':').collect();
+        if parts.len() > 3 {
+            return Err(format!("invalid time: {}", spec));
+        }
+        for (i, part) in parts.iter().enumerate() {
+            let value: f64 = part.trim().parse().map_err(|_| format!("invalid time: {}", spec))?;
+            if !value.is_finite() || value < 0.0 {
+                return Err(format!("start time must be a positive number: {}", spec));
+            }
+            // Minutes and seconds fields after the first must stay below 60
+            if i > 0 && value >= 60.0 {
+                return Err(format!("invalid time: {}", spec));
+            }
+            seconds = seconds * 60.0 + value;
+        }
+        Ok(StartSpec::Seconds(seconds))
+    }
+
+    /// The `--start=` argument for mpv
+    pub fn to_mpv_arg(self) -> String {
+        format!("--start={}", self)
+    }
+}
+
+impl fmt::Display for StartSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartSpec::Seconds(s) if s.fract() == 0.0 => write!(f, "{}", *s as u64),
+            StartSpec::Seconds(s) => write!(f, "{}", s),
+            StartSpec::Percent(p) => write!(f, "{}%", p),
+            StartSpec::Chapter(c) => write!(f, "#{}", c),
+        }
+    }
+}
+
+/// Directories where mpv keeps watch-later (resume) files: the XDG state directory used
+/// by current mpv and the config directory used by older releases
+fn watch_later_dirs() -> Vec<PathBuf> {
+    if let Some(mpv_home) = env::var_os("MPV_HOME") {
+        return vec![PathBuf::from(mpv_home).join("watch_later")];
+    }
+    let Some(home) = env::var_os("HOME").map(PathBuf::from) else {
+        return Vec::new();
+    };
+    let state = env::var_os("XDG_STATE_HOME").map(PathBuf::from).unwrap_or_else(|| home.join(".local/state"));
+    let config = env::var_os("XDG_CONFIG_HOME").map(PathBuf::from).unwrap_or_else(|| home.join(".config"));
+    vec![state.join("mpv/watch_later"), config.join("mpv/watch_later")]
+}
+
+/// Name of the watch-later file mpv writes for `path`: the MD5 of the path, in uppercase hex
+fn watch_later_name(path: &str) -> String {
+    format!("{:X}", md5::compute(path.as_bytes()))
+}
+
+/// The path to hand to mpv so that its watch-later file name is predictable
+pub fn resume_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// When mpv last saved a position for `path` (as passed through `resume_path`), if it has one
+pub fn resume_saved_at(path: &Path) -> Option<SystemTime> {
+    let name = watch_later_name(&resume_path(path).to_string_lossy());
+    watch_later_dirs().iter()
+        .filter_map(|dir| fs::metadata(dir.join(&name)).ok())
+        .filter(|meta| meta.is_file())
+        .filter_map(|meta| meta.modified().ok())
+        .max()
+}
+
+pub fn has_resume_position(path: &Path) -> bool {
+    resume_saved_at(path).is_some()
+}
+
+/// Move the saved position of a file renamed from `old` to `new` (both as passed through
+/// `resume_path`; `old` has to be resolved before the rename)
+pub fn move_resume_position(old: &Path, new: &Path) {
+    move_watch_later(&watch_later_dirs(), old, new);
+}
+
+fn move_watch_later(dirs: &[PathBuf], old: &Path, new: &Path) {
+    let from = watch_later_name(&old.to_string_lossy());
+    let to = watch_later_name(&new.to_string_lossy());
+    for dir in dirs {
+        let file = dir.join(&from);
+        if file.is_file()
+            && let Err(e) = fs::rename(&file, dir.join(&to)) {
+            log::warn!("Failed to move the resume position of {}: {}", old.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::test_dir;
+
+    #[test]
+    fn watch_later_names_are_uppercase_md5() {
+        assert_eq!(watch_later_name(""), "D41D8CD98F00B204E9800998ECF8427E");
+        assert_eq!(watch_later_name("/movies/Heat.mkv").len(), 32);
+    }
+
+    #[test]
+    fn resume_positions_follow_a_rename() {
+        let dir = test_dir("watch-later");
+        let (old, new) = (Path::new("/movies/heat.mkv"), Path::new("/movies/Heat (1995).mkv"));
+        fs::write(dir.join(watch_later_name("/movies/heat.mkv")), b"start=630\n").unwrap();
+        move_watch_later(&[dir.to_path_buf()], old, new);
+        assert!(!dir.join(watch_later_name("/movies/heat.mkv")).exists());
+        assert_eq!(fs::read(dir.join(watch_later_name("/movies/Heat (1995).mkv"))).unwrap(), b"start=630\n");
+    }
+
+    #[test]
+    fn seconds_forms() {
+        assert_eq!(StartSpec::parse("630").unwrap().to_mpv_arg(), "--start=630");
+        assert_eq!(StartSpec::parse("630s").unwrap().to_mpv_arg(), "--start=630");
+        assert_eq!(StartSpec::parse("10:30").unwrap().to_mpv_arg(), "--start=630");
+        assert_eq!(StartSpec::parse("1:02:03").unwrap().to_mpv_arg(), "--start=3723");
+        assert_eq!(StartSpec::parse("12.5").unwrap().to_mpv_arg(), "--start=12.5");
+    }
+
+    #[test]
+    fn percent_forms() {
+        assert_eq!(StartSpec::parse("10%").unwrap().to_mpv_arg(), "--start=10%");
+        assert_eq!(StartSpec::parse(" 2.5 %").unwrap(), StartSpec::Percent(2.5));
+        assert!(StartSpec::parse("150%").is_err());
+        assert!(StartSpec::parse("-5%").is_err());
+    }
+
+    #[test]
+    fn chapter_forms() {
+        assert_eq!(StartSpec::parse("#2").unwrap().to_mpv_arg(), "--start=#2");
+        assert_eq!(StartSpec::parse("ch3").unwrap(), StartSpec::Chapter(3));
+        assert_eq!(StartSpec::parse("Chapter 4").unwrap(), StartSpec::Chapter(4));
+        assert!(StartSpec::parse("#0").is_err());
+        assert!(StartSpec::parse("#two").is_err());
+    }
+
+    #[test]
+    fn rejects_nonsense() {
+        assert!(StartSpec::parse("").is_err());
+        assert!(StartSpec::parse("soon").is_err());
+        assert!(StartSpec::parse("-30").is_err());
+        assert!(StartSpec::parse("1:75").is_err());
+        assert!(StartSpec::parse("1:2:3:4").is_err());
+    }
+}
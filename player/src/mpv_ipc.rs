@@ -0,0 +1,126 @@
+use std::env;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{Value as JsonValue, json};
+
+// How long mpv gets to create its socket, and to answer one request
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REPLY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Socket passed to mpv as `--input-ipc-server`, one per player process
+pub fn socket_path() -> PathBuf {
+    env::temp_dir().join(format!("movieplayer-mpv-{}.sock", std::process::id()))
+}
+
+/// A connection to mpv's JSON IPC socket
+pub struct MpvIpc {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    next_id: u64,
+}
+
+/// One command as mpv reads it: a JSON object on a single line
+fn request_line(id: u64, command: &[JsonValue]) -> String {
+    format!("{}\n", json!({ "command": command, "request_id": id }))
+}
+
+/// The data of the reply to request `id`; None for events, other replies and errors
+fn parse_reply(line: &str, id: u64) -> Option<Result<JsonValue, String>> {
+    let reply: JsonValue = serde_json::from_str(line).ok()?;
+    if reply.get("request_id").and_then(|v| v.as_u64()) != Some(id) {
+        return None;
+    }
+    Some(match reply.get("error").and_then(|e| e.as_str()) {
+        Some("success") => Ok(reply.get("data").cloned().unwrap_or(JsonValue::Null)),
+        Some(error) => Err(error.to_string()),
+        None => Err("reply without status".to_string()),
+    })
+}
+
+impl MpvIpc {
+    /// Connect once mpv has created the socket, giving up when `child` (the mpv process) exits first
+    pub fn connect(path: &Path, child: &mut Child) -> io::Result<Self> {
+        let started = Instant::now();
+        let stream = loop {
+            match UnixStream::connect(path) {
+                Ok(stream) => break stream,
+                Err(e) if started.elapsed() >= CONNECT_TIMEOUT => return Err(e),
+                Err(_) if child.try_wait()?.is_some() => {
+                    return Err(io::Error::new(io::ErrorKind::NotConnected, "mpv exited before opening its socket"));
+                }
+                Err(_) => thread::sleep(Duration::from_millis(50)),
+            }
+        };
+        stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+        Ok(MpvIpc { reader: BufReader::new(stream.try_clone()?), writer: stream, next_id: 1 })
+    }
+
+    /// Run a command and wait for its reply, skipping the events mpv sends in between
+    pub fn command(&mut self, command: &[JsonValue]) -> io::Result<JsonValue> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.writer.write_all(request_line(id, command).as_bytes())?;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "mpv closed the socket"));
+            }
+            if let Some(reply) = parse_reply(&line, id) {
+                return reply.map_err(io::Error::other);
+            }
+        }
+    }
+
+    /// A numeric property such as `time-pos` or `duration`; None while it is unavailable
+    pub fn get_f64(&mut self, property: &str) -> Option<f64> {
+        self.command(&[json!("get_property"), json!(property)]).ok()?.as_f64()
+    }
+
+    pub fn is_paused(&mut self) -> bool {
+        self.command(&[json!("get_property"), json!("pause")]).ok().and_then(|v| v.as_bool()).unwrap_or(false)
+    }
+
+    pub fn toggle_pause(&mut self) -> io::Result<()> {
+        self.command(&[json!("cycle"), json!("pause")]).map(|_| ())
+    }
+
+    /// Jump `secs` forwards (or backwards when negative)
+    pub fn seek(&mut self, secs: f64) -> io::Result<()> {
+        self.command(&[json!("seek"), json!(secs), json!("relative")]).map(|_| ())
+    }
+
+    /// Stop playback, exiting with `code` (mpv's own exit code 4 reads as interrupted)
+    pub fn quit(&mut self, code: i32) -> io::Result<()> {
+        self.command(&[json!("quit"), json!(code)]).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_single_lines() {
+        assert_eq!(
+            request_line(3, &[json!("seek"), json!(-10.0), json!("relative")]),
+            "{\"command\":[\"seek\",-10.0,\"relative\"],\"request_id\":3}\n"
+        );
+    }
+
+    #[test]
+    fn replies_match_their_request() {
+        assert_eq!(parse_reply(r#"{"data":12.5,"error":"success","request_id":7}"#, 7), Some(Ok(json!(12.5))));
+        assert_eq!(parse_reply(r#"{"data":12.5,"error":"success","request_id":6}"#, 7), None);
+        assert_eq!(parse_reply(r#"{"event":"pause"}"#, 7), None);
+        assert_eq!(
+            parse_reply(r#"{"error":"property unavailable","request_id":7}"#, 7),
+            Some(Err("property unavailable".to_string()))
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use std::io;
+use std::path::Path;
+#[cfg(unix)]
+use std::process::{Child, ExitStatus};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyEventKind};
+#[cfg(unix)]
+use crossterm::event::KeyCode;
+use ratatui::{Frame,
+    layout::{Alignment, Constraint, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph}};
+#[cfg(unix)]
+use ratatui::{DefaultTerminal, widgets::Gauge};
+
+#[cfg(unix)]
+use crate::format_duration;
+#[cfg(unix)]
+use crate::mpv_ipc::MpvIpc;
+use crate::posters::Posters;
+use crate::theme::Theme;
+
+// How often the position is read from mpv
+#[cfg(unix)]
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+// Seek steps for Left/Right and Down/Up
+#[cfg(unix)]
+const SEEK_SHORT: f64 = 10.0;
+#[cfg(unix)]
+const SEEK_LONG: f64 = 60.0;
+// Exit code asked of mpv when stopped from here, the one it uses for Ctrl+C
+#[cfg(unix)]
+const STOPPED_EXIT_CODE: i32 = 4;
+// How long the title card stays up before the player starts, unless a key is pressed
+const CARD_TIME: Duration = Duration::from_millis(1500);
+// Length of the break between the films of a double feature, unless INTERMISSION_SECS says otherwise
+const DEFAULT_INTERMISSION_SECS: u64 = 10;
+
+/// What the title card shows about the film about to play
+pub struct Card<'a> {
+    pub title: &'a str,
+    // Year, runtime and the like, on one line under the title
+    pub details: String,
+    // How it is played: resuming, skipping the intro, ...
+    pub notes: &'a [String],
+    pub player: &'a str,
+}
+
+/// What mpv last reported
+#[cfg(unix)]
+#[derive(Default)]
+struct Progress {
+    position: Option<f64>,
+    duration: Option<f64>,
+    paused: bool,
+    // Furthest share of the film reached, 0.0-1.0
+    furthest: Option<f64>,
+}
+
+/// Show the playing title with a progress bar until mpv exits; Space pauses,
+/// the arrows seek and q/Esc stops. Returns the exit status and the furthest
+/// share of the film that was reached, when mpv reported one.
+#[cfg(unix)]
+pub fn run(child: &mut Child, ipc: &mut MpvIpc, title: &str, theme: Theme) -> io::Result<(ExitStatus, Option<f64>)> {
+    ratatui::run(|terminal| watch(terminal, child, ipc, title, theme))
+}
+
+/// Show a full-screen card with the film about to play, and its poster when one is cached,
+/// for a moment before the player takes over the terminal; any key starts it straight away
+pub fn announce(card: &Card, mut poster: Option<(&mut Posters, &Path)>, theme: Theme) -> io::Result<()> {
+    ratatui::run(|terminal| {
+        let shown = Instant::now();
+        loop {
+            terminal.draw(|frame| render_card(frame, card, poster.as_mut().map(|(posters, path)| (&mut **posters, *path)), theme))?;
+            let left = CARD_TIME.saturating_sub(shown.elapsed());
+            if left.is_zero() {
+                return Ok(());
+            }
+            if event::poll(left)?
+                && let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press {
+                return Ok(());
+            }
+        }
+    })
+}
+
+/// Length of the intermission, from INTERMISSION_SECS ("0" skips it)
+pub fn intermission_length(value: Option<&str>) -> Duration {
+    let secs = value.and_then(|v| v.trim().parse::<u64>().ok()).unwrap_or(DEFAULT_INTERMISSION_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Whole seconds left on the countdown, rounded up so that it shows 1 until it is over
+fn seconds_left(length: Duration, elapsed: Duration) -> u64 {
+    length.saturating_sub(elapsed).as_millis().div_ceil(1000) as u64
+}
+
+/// Count down to the second film of a double feature on a full-screen card; any key starts it
+/// straight away
+pub fn intermission(next_title: &str, length: Duration, theme: Theme) -> io::Result<()> {
+    if length.is_zero() {
+        return Ok(());
+    }
+    ratatui::run(|terminal| {
+        let started = Instant::now();
+        loop {
+            let left = seconds_left(length, started.elapsed());
+            if left == 0 {
+                return Ok(());
+            }
+            terminal.draw(|frame| render_intermission(frame, next_title, left, theme))?;
+            // Wake up for the next second on the clock
+            let tick = length.saturating_sub(started.elapsed()).saturating_sub(Duration::from_secs(left - 1));
+            if event::poll(tick)?
+                && let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press {
+                return Ok(());
+            }
+        }
+    })
+}
+
+fn render_intermission(frame: &mut Frame, next_title: &str, left: u64, theme: Theme) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.panel_border))
+        .title_style(Style::default().fg(theme.title).add_modifier(Modifier::BOLD))
+        .title("Intermission");
+    let inner = block.inner(frame.area());
+    frame.render_widget(block, frame.area());
+
+    let lines = vec![
+        Line::from(Span::styled("Up next", Style::default().fg(theme.muted))),
+        Line::from(Span::styled(next_title, Style::default().fg(theme.selected).add_modifier(Modifier::BOLD))),
+        Line::default(),
+        Line::from(Span::styled(format!("Starting in {}s | any key starts it now", left), Style::default().fg(theme.muted))),
+    ];
+    let [_, centered, _] = Layout::vertical([Constraint::Fill(1), Constraint::Length(lines.len() as u16), Constraint::Fill(1)]).areas(inner);
+    frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), centered);
+}
+
+fn render_card(frame: &mut Frame, card: &Card, poster: Option<(&mut Posters, &Path)>, theme: Theme) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.panel_border))
+        .title_style(Style::default().fg(theme.title).add_modifier(Modifier::BOLD))
+        .title("Now Playing");
+    let inner = block.inner(frame.area());
+    frame.render_widget(block, frame.area());
+
+    let mut lines = vec![Line::from(Span::styled(card.title, Style::default().fg(theme.selected).add_modifier(Modifier::BOLD)))];
+    if !card.details.is_empty() {
+        lines.push(Line::from(Span::styled(card.details.as_str(), Style::default().fg(theme.value))));
+    }
+    lines.extend(card.notes.iter().map(|note| Line::from(Span::styled(note.as_str(), Style::default().fg(theme.muted)))));
+    lines.push(Line::default());
+    lines.push(Line::from(Span::styled(format!("Starting {}…", card.player), Style::default().fg(theme.muted))));
+    let text_height = lines.len() as u16;
+
+    // The poster above the text when there is room for it, the text alone centered otherwise
+    let [_, centered, _] = Layout::vertical([Constraint::Fill(1), Constraint::Length(text_height), Constraint::Fill(1)]).areas(inner);
+    let text_area = match poster {
+        Some((posters, path)) if inner.height > text_height + 4 => {
+            let [poster_area, below] = Layout::vertical([Constraint::Fill(1), Constraint::Length(text_height + 1)]).areas(inner);
+            // Cells are about twice as tall as wide: a square of the height, centered, fits a poster
+            let [_, poster_area, _] = Layout::horizontal([Constraint::Fill(1), Constraint::Length(poster_area.height * 2), Constraint::Fill(1)]).areas(poster_area);
+            if posters.draw(frame, poster_area, path) { below } else { centered }
+        }
+        _ => centered,
+    };
+    frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), text_area);
+}
+
+#[cfg(unix)]
+fn watch(terminal: &mut DefaultTerminal, child: &mut Child, ipc: &mut MpvIpc, title: &str, theme: Theme) -> io::Result<(ExitStatus, Option<f64>)> {
+    let mut progress = Progress::default();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((status, progress.furthest));
+        }
+        progress.position = ipc.get_f64("time-pos");
+        progress.duration = ipc.get_f64("duration");
+        progress.paused = ipc.is_paused();
+        if let (Some(position), Some(duration)) = (progress.position, progress.duration.filter(|&d| d > 0.0)) {
+            let share = (position / duration).clamp(0.0, 1.0);
+            progress.furthest = Some(progress.furthest.map_or(share, |f| f.max(share)));
+        }
+        terminal.draw(|frame| render(frame, title, &progress, theme))?;
+
+        if event::poll(POLL_INTERVAL)?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press {
+            // A failed command only means mpv is on its way out; try_wait picks that up
+            let _ = match key.code {
+                KeyCode::Char(' ') | KeyCode::Char('p') => ipc.toggle_pause(),
+                KeyCode::Left => ipc.seek(-SEEK_SHORT),
+                KeyCode::Right => ipc.seek(SEEK_SHORT),
+                KeyCode::Down => ipc.seek(-SEEK_LONG),
+                KeyCode::Up => ipc.seek(SEEK_LONG),
+                KeyCode::Char('q') | KeyCode::Esc => ipc.quit(STOPPED_EXIT_CODE),
+                _ => Ok(()),
+            };
+        }
+    }
+}
+
+#[cfg(unix)]
+fn render(frame: &mut Frame, title: &str, progress: &Progress, theme: Theme) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.panel_border))
+        .title_style(Style::default().fg(theme.title).add_modifier(Modifier::BOLD))
+        .title(if progress.paused { "Paused" } else { "Now Playing" });
+    let inner = block.inner(frame.area());
+    frame.render_widget(block, frame.area());
+
+    let [title_area, gauge_area, _, hint_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ]).areas(inner);
+
+    frame.render_widget(
+        Paragraph::new(Span::styled(title, Style::default().fg(theme.selected).add_modifier(Modifier::BOLD))),
+        title_area,
+    );
+
+    let position = progress.position.unwrap_or(0.0);
+    let (ratio, label) = match progress.duration.filter(|&d| d > 0.0) {
+        Some(duration) => (
+            (position / duration).clamp(0.0, 1.0),
+            format!("{} / {} (-{})", format_duration(position), format_duration(duration), format_duration((duration - position).max(0.0))),
+        ),
+        None => (0.0, format_duration(position)),
+    };
+    frame.render_widget(
+        Gauge::default()
+            .gauge_style(Style::default().fg(if progress.paused { theme.muted } else { theme.positive }))
+            .ratio(ratio)
+            .label(label),
+        gauge_area,
+    );
+
+    frame.render_widget(
+        Paragraph::new(Line::from(Span::styled(
+            "Space=Pause | ←/→=Seek 10s | ↓/↑=Seek 1min | q/Esc=Stop",
+            Style::default().fg(theme.muted),
+        ))),
+        hint_area,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intermission_length_from_the_environment() {
+        assert_eq!(intermission_length(None), Duration::from_secs(10));
+        assert_eq!(intermission_length(Some(" 30 ")), Duration::from_secs(30));
+        assert_eq!(intermission_length(Some("0")), Duration::ZERO);
+        assert_eq!(intermission_length(Some("soon")), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn countdown_rounds_up_until_it_is_over() {
+        let length = Duration::from_secs(10);
+        assert_eq!(seconds_left(length, Duration::ZERO), 10);
+        assert_eq!(seconds_left(length, Duration::from_millis(100)), 10);
+        assert_eq!(seconds_left(length, Duration::from_millis(1000)), 9);
+        assert_eq!(seconds_left(length, Duration::from_millis(9999)), 1);
+        assert_eq!(seconds_left(length, Duration::from_secs(10)), 0);
+        assert_eq!(seconds_left(length, Duration::from_secs(12)), 0);
+    }
+}
@@ -0,0 +1,41 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Directory for user data that should survive restarts (~/.local/share/movieplayer)
+pub fn data_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share/movieplayer"))
+}
+
+/// Directory for user configuration (~/.config/movieplayer)
+pub fn config_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".config/movieplayer"))
+}
+
+/// Directory for data that can be rebuilt at any time (~/.cache/movieplayer)
+pub fn cache_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache/movieplayer"))
+}
+
+/// Read a JSON file, returning None if it is missing or malformed
+pub fn load_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let raw = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&raw) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            log::warn!("Ignoring unreadable {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Write `value` as pretty JSON, creating parent directories as needed
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(value)?)
+}
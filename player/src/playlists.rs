@@ -0,0 +1,68 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::persist;
+
+/// Saved queues, one JSON list of paths per name (~/.local/share/movieplayer/playlists/<name>.json)
+fn playlists_dir() -> Option<PathBuf> {
+    persist::data_dir().map(|dir| dir.join("playlists"))
+}
+
+/// Names become file names, so keep them to one plain path component
+fn check_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("playlist name is empty".to_string());
+    }
+    if name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(format!("invalid playlist name: {}", name));
+    }
+    Ok(name)
+}
+
+/// Names of the saved playlists, sorted
+pub fn names() -> Vec<String> {
+    let Some(entries) = playlists_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            Some(path.file_stem()?.to_string_lossy().to_string())
+        })
+        .collect();
+    names.sort_by_key(|name| name.to_lowercase());
+    names
+}
+
+/// Save `paths` under `name`, replacing any playlist of that name; returns the trimmed name
+pub fn save(name: &str, paths: &[PathBuf]) -> Result<String, String> {
+    let name = check_name(name)?;
+    let dir = playlists_dir().ok_or("HOME is not set")?;
+    let file = dir.join(format!("{}.json", name));
+    persist::save_json(&file, &paths).map_err(|e| format!("failed to save {}: {}", file.display(), e))?;
+    Ok(name.to_string())
+}
+
+pub fn load(name: &str) -> Result<Vec<PathBuf>, String> {
+    let name = check_name(name)?;
+    let dir = playlists_dir().ok_or("HOME is not set")?;
+    persist::load_json(&dir.join(format!("{}.json", name))).ok_or_else(|| format!("cannot read playlist {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_stay_inside_the_playlists_dir() {
+        assert_eq!(check_name(" friday "), Ok("friday"));
+        assert_eq!(check_name("80s marathon"), Ok("80s marathon"));
+        assert!(check_name("").is_err());
+        assert!(check_name("../config").is_err());
+        assert!(check_name(".hidden").is_err());
+    }
+}
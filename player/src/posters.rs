@@ -0,0 +1,242 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::UNIX_EPOCH;
+
+use image::{DynamicImage, ImageReader};
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui_image::StatefulImage;
+use ratatui_image::picker::{Picker, ProtocolType};
+use ratatui_image::protocol::StatefulProtocol;
+
+use crate::api;
+use crate::backend;
+use crate::persist;
+
+// Where in the film the preview frame is taken, as a share of its length
+const FRAME_AT: f64 = 0.1;
+// Seek used when the length is unknown (no ffprobe)
+const FRAME_FALLBACK_SECS: f64 = 60.0;
+// Width of the saved frame; the panel never shows it larger
+const FRAME_WIDTH: u32 = 480;
+
+/// Ask the terminal which graphics protocol it speaks. Call before the TUI starts reading
+/// input; None for terminals that can only draw text (posters are left out there).
+pub fn detect() -> Option<Picker> {
+    match Picker::from_query_stdio() {
+        Ok(picker) if picker.protocol_type() != ProtocolType::Halfblocks => Some(picker),
+        Ok(_) => {
+            log::debug!("Terminal has no graphics protocol, showing no posters");
+            None
+        }
+        Err(e) => {
+            log::debug!("Terminal graphics query failed, showing no posters: {}", e);
+            None
+        }
+    }
+}
+
+/// Where the poster at `url` is kept (~/.cache/movieplayer/posters/<md5 of the url>)
+fn cache_path(url: &str) -> Option<PathBuf> {
+    persist::cache_dir().map(|dir| dir.join("posters").join(format!("{:x}", md5::compute(url))))
+}
+
+fn frames_dir() -> Option<PathBuf> {
+    persist::cache_dir().map(|dir| dir.join("frames"))
+}
+
+/// Where the preview frame of `video` is kept (~/.cache/movieplayer/frames/<md5 of path and
+/// mtime>), so a replaced file gets a new frame
+fn frame_path(video: &Path) -> Option<PathBuf> {
+    let mtime = fs::metadata(video).ok()?.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let key = format!("{}:{}", std::path::absolute(video).ok()?.display(), mtime);
+    frames_dir().map(|dir| dir.join(format!("{:x}", md5::compute(key))))
+}
+
+/// Length of `video` in seconds, from ffprobe
+fn duration_secs(video: &Path) -> Option<f64> {
+    if !backend::ffprobe_installed() {
+        return None;
+    }
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "csv=p=0"])
+        .arg(video)
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse::<f64>().ok().filter(|secs| *secs > 0.0)
+}
+
+/// Save one frame from FRAME_AT into `video` as a JPEG at `path`
+fn extract_frame(video: &Path, path: &Path) -> Result<(), String> {
+    let at = duration_secs(video).map_or(FRAME_FALLBACK_SECS, |secs| secs * FRAME_AT);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let partial = path.with_extension("part");
+    let output = Command::new("ffmpeg")
+        .args(["-v", "error", "-nostdin", "-y", "-ss", &format!("{:.1}", at), "-i"])
+        .arg(video)
+        .args(["-frames:v", "1", "-vf", &format!("scale={}:-2", FRAME_WIDTH), "-f", "mjpeg"])
+        .arg(&partial)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() || !partial.is_file() {
+        let _ = fs::remove_file(&partial);
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    fs::rename(&partial, path).map_err(|e| e.to_string())
+}
+
+/// Fetch `url` into `path`, through a temporary file so a broken download never looks cached
+fn download(url: &str, path: &Path) -> Result<(), String> {
+    let resp = api::client().get(url).send().map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(api::error_summary(resp));
+    }
+    let bytes = resp.bytes().map_err(|e| e.to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let partial = path.with_extension("part");
+    fs::write(&partial, &bytes).and_then(|()| fs::rename(&partial, path)).map_err(|e| e.to_string())
+}
+
+/// Read an image whatever its format; cached posters are stored without an extension
+fn decode(path: &Path) -> Result<DynamicImage, String> {
+    let reader = ImageReader::open(path).and_then(|r| r.with_guessed_format()).map_err(|e| e.to_string())?;
+    reader.decode().map_err(|e| e.to_string())
+}
+
+/// Poster images for the info panel, downloaded (or taken from the film with ffmpeg) once
+/// and drawn with the terminal's graphics
+pub struct Posters {
+    picker: Picker,
+    // The file drawn last and its image, resized to the panel as needed (None if it cannot be decoded)
+    shown: Option<(PathBuf, Option<StatefulProtocol>)>,
+    // Cache files still being written in the background, and the ones that failed while the
+    // list was open
+    pending: HashSet<PathBuf>,
+    failed: HashSet<PathBuf>,
+    done_tx: Sender<(PathBuf, bool)>,
+    done_rx: Receiver<(PathBuf, bool)>,
+}
+
+impl Posters {
+    pub fn new(picker: Picker) -> Self {
+        let (done_tx, done_rx) = mpsc::channel();
+        Posters { picker, shown: None, pending: HashSet::new(), failed: HashSet::new(), done_tx, done_rx }
+    }
+
+    /// The cached poster for `url`, or None while it is downloaded in the background
+    pub fn poster(&mut self, url: &str) -> Option<PathBuf> {
+        let path = cache_path(url)?;
+        let url = url.to_string();
+        self.cached_or_start(path, move |path| {
+            download(&url, path).map_err(|e| format!("Failed to fetch poster {}: {}", url, e))
+        })
+    }
+
+    /// The cached preview frame of `video`, or None while ffmpeg takes it in the background.
+    /// Only one frame is taken at a time, so moving through the list does not pile up ffmpeg runs.
+    pub fn frame(&mut self, video: &Path) -> Option<PathBuf> {
+        if !backend::ffmpeg_installed() {
+            return None;
+        }
+        let path = frame_path(video)?;
+        let frames = frames_dir();
+        if !path.is_file() && self.pending.iter().any(|p| p.parent() == frames.as_deref()) {
+            self.collect();
+            return None;
+        }
+        let video = video.to_path_buf();
+        self.cached_or_start(path, move |path| {
+            extract_frame(&video, path).map_err(|e| format!("Failed to take a frame from {}: {}", video.display(), e))
+        })
+    }
+
+    /// The poster for `url`, or else the frame of `video`, if one is already cached; nothing
+    /// is fetched
+    pub fn cached(&self, url: Option<&str>, video: &Path) -> Option<PathBuf> {
+        let path = match url {
+            Some(url) => cache_path(url),
+            None => frame_path(video),
+        };
+        path.filter(|p| p.is_file())
+    }
+
+    /// Note the background jobs that have finished
+    fn collect(&mut self) {
+        for (path, ok) in self.done_rx.try_iter() {
+            self.pending.remove(&path);
+            if !ok {
+                self.failed.insert(path);
+            }
+        }
+    }
+
+    /// `path` if it is cached; otherwise have `job` write it on a worker thread, unless that
+    /// is already under way or failed before
+    fn cached_or_start(&mut self, path: PathBuf, job: impl FnOnce(&Path) -> Result<(), String> + Send + 'static) -> Option<PathBuf> {
+        self.collect();
+        if path.is_file() {
+            return Some(path);
+        }
+        if !self.pending.contains(&path) && !self.failed.contains(&path) {
+            self.pending.insert(path.clone());
+            let tx = self.done_tx.clone();
+            thread::spawn(move || {
+                let result = job(&path);
+                if let Err(e) = &result {
+                    log::warn!("{}", e);
+                }
+                let _ = tx.send((path, result.is_ok()));
+            });
+        }
+        None
+    }
+
+    /// Draw the image in `path` into `area`; false if it cannot be decoded
+    pub fn draw(&mut self, frame: &mut Frame, area: Rect, path: &Path) -> bool {
+        if self.shown.as_ref().is_none_or(|(shown, _)| shown != path) {
+            let protocol = match decode(path) {
+                Ok(image) => Some(self.picker.new_resize_protocol(image)),
+                Err(e) => {
+                    log::warn!("Cannot decode poster {}: {}", path.display(), e);
+                    None
+                }
+            };
+            self.shown = Some((path.to_path_buf(), protocol));
+        }
+        match &mut self.shown {
+            Some((_, Some(protocol))) => {
+                frame.render_stateful_widget(StatefulImage::default(), area, protocol);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+    use crate::test_dir::test_dir;
+
+    #[test]
+    fn changed_files_get_a_new_frame() {
+        let dir = test_dir("frame");
+        let file = dir.join("Heat.mkv");
+        fs::write(&file, b"x").unwrap();
+        let before = frame_path(&file).unwrap();
+        assert_eq!(frame_path(&file), Some(before.clone()));
+
+        let handle = fs::File::options().write(true).open(&file).unwrap();
+        handle.set_modified(SystemTime::now() - Duration::from_secs(3600)).unwrap();
+        assert_ne!(frame_path(&file).unwrap(), before);
+    }
+}
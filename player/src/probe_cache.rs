@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::MovieInfo;
+use crate::persist;
+
+/// One ffprobe result and the modification time of the file it was read from
+#[derive(Clone, Serialize, Deserialize)]
+struct Entry {
+    mtime: u64,
+    info: MovieInfo,
+}
+
+/// ffprobe results by absolute path (~/.cache/movieplayer/probe.json)
+#[derive(Default)]
+struct ProbeCache {
+    entries: HashMap<PathBuf, Entry>,
+    // Changed since the last save
+    dirty: bool,
+}
+
+fn cache_path() -> Option<PathBuf> {
+    persist::cache_dir().map(|dir| dir.join("probe.json"))
+}
+
+/// Loaded on first use; shared by the info panel and the prewarm threads
+fn cache() -> MutexGuard<'static, ProbeCache> {
+    static CACHE: OnceLock<Mutex<ProbeCache>> = OnceLock::new();
+    CACHE
+        .get_or_init(|| {
+            let entries = cache_path().and_then(|path| persist::load_json(&path)).unwrap_or_default();
+            Mutex::new(ProbeCache { entries, dirty: false })
+        })
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The cache key and current modification time of `path`
+fn key(path: &Path) -> Option<(PathBuf, u64)> {
+    let mtime = fs::metadata(path).ok()?.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some((std::path::absolute(path).ok()?, mtime))
+}
+
+impl ProbeCache {
+    fn get(&self, path: &Path) -> Option<MovieInfo> {
+        let (key, mtime) = key(path)?;
+        self.entries.get(&key).filter(|e| e.mtime == mtime).map(|e| e.info.clone())
+    }
+
+    fn insert(&mut self, path: &Path, info: &MovieInfo) {
+        if let Some((key, mtime)) = key(path) {
+            self.entries.insert(key, Entry { mtime, info: info.clone() });
+            self.dirty = true;
+        }
+    }
+}
+
+/// The stored probe of `path`, unless the file changed since
+pub fn get(path: &Path) -> Option<MovieInfo> {
+    cache().get(path)
+}
+
+/// Remember a successful probe; written out by `save`
+pub fn insert(path: &Path, info: &MovieInfo) {
+    cache().insert(path, info);
+}
+
+/// Write the cache if anything was probed since the last save
+pub fn save() {
+    let mut cache = cache();
+    if !cache.dirty {
+        return;
+    }
+    if let Some(path) = cache_path() {
+        match persist::save_json(&path, &cache.entries) {
+            Ok(()) => cache.dirty = false,
+            Err(e) => log::warn!("Failed to save probe cache to {}: {}", path.display(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::test_dir;
+
+    #[test]
+    fn changed_files_miss() {
+        let dir = test_dir("probe");
+        let file = dir.join("Heat.mkv");
+        fs::write(&file, b"x").unwrap();
+        let info = MovieInfo { runtime: Some("1:30:00".to_string()), ..Default::default() };
+
+        let mut cache = ProbeCache::default();
+        cache.insert(&file, &info);
+        assert_eq!(cache.get(&file).and_then(|i| i.runtime).as_deref(), Some("1:30:00"));
+
+        let (key, mtime) = key(&file).unwrap();
+        cache.entries.get_mut(&key).unwrap().mtime = mtime - 1;
+        assert!(cache.get(&file).is_none());
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+use crate::persist;
+
+/// A rating given by hand with the rate key
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct Rating {
+    rating: f64,
+    // Whether the API accepted it; unsynced ratings are kept in ratings.json
+    #[serde(skip)]
+    synced: bool,
+}
+
+/// Ratings set from the list, shown over the API's until it has them
+#[derive(Clone, Debug, Default)]
+pub struct UserRatings {
+    ratings: HashMap<PathBuf, Rating>,
+}
+
+fn ratings_path() -> Option<PathBuf> {
+    persist::data_dir().map(|dir| dir.join("ratings.json"))
+}
+
+/// Read a rating typed into the popup: a number from 0 to 10
+pub fn parse(input: &str) -> Result<f64, String> {
+    let rating: f64 = input.trim().parse().map_err(|_| format!("\"{}\" is not a number", input.trim()))?;
+    if !(0.0..=10.0).contains(&rating) {
+        return Err("the rating must be between 0 and 10".to_string());
+    }
+    Ok(rating)
+}
+
+impl UserRatings {
+    /// Ratings saved while the API was unreachable
+    pub fn load() -> Self {
+        UserRatings {
+            ratings: ratings_path().and_then(|path| persist::load_json(&path)).unwrap_or_default(),
+        }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<f64> {
+        self.ratings.get(path).map(|r| r.rating)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&PathBuf, f64)> {
+        self.ratings.iter().map(|(path, r)| (path, r.rating))
+    }
+
+    /// Record a rating; ratings the API did not take are written to ratings.json
+    pub fn set(&mut self, path: PathBuf, rating: f64, synced: bool) {
+        self.ratings.insert(path, Rating { rating, synced });
+        self.save();
+    }
+
+    /// Forget the rating of a deleted file
+    pub fn remove(&mut self, path: &Path) {
+        if self.ratings.remove(path).is_some() {
+            self.save();
+        }
+    }
+
+    /// Keep the rating of a file renamed from `old` to `new`
+    pub fn rename(&mut self, old: &Path, new: PathBuf) {
+        if let Some(rating) = self.ratings.remove(old) {
+            self.ratings.insert(new, rating);
+            self.save();
+        }
+    }
+
+    /// Send the ratings still waiting for the API through `send` on a worker thread. The
+    /// receiver gets the ratings it took, for `mark_synced`; None when nothing is waiting.
+    pub fn sync_in_background(&self, send: impl Fn(&Path, f64) -> bool + Send + 'static) -> Option<Receiver<Vec<(PathBuf, f64)>>> {
+        let pending: Vec<(PathBuf, f64)> = self.pending().into_iter().map(|(path, r)| (path.clone(), r.rating)).collect();
+        if pending.is_empty() {
+            return None;
+        }
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let sent = pending.into_iter().filter(|(path, rating)| send(path, *rating)).collect();
+            let _ = tx.send(sent);
+        });
+        Some(rx)
+    }
+
+    /// Note the ratings the API took, unless they were changed again meanwhile
+    pub fn mark_synced(&mut self, sent: &[(PathBuf, f64)]) {
+        for (path, rating) in sent {
+            if let Some(r) = self.ratings.get_mut(path)
+                && r.rating == *rating {
+                r.synced = true;
+            }
+        }
+        self.save();
+    }
+
+    pub fn has_pending(&self) -> bool {
+        self.ratings.values().any(|r| !r.synced)
+    }
+
+    /// Ratings still waiting to reach the API
+    fn pending(&self) -> HashMap<&PathBuf, &Rating> {
+        self.ratings.iter().filter(|(_, r)| !r.synced).collect()
+    }
+
+    fn save(&self) {
+        if let Some(path) = ratings_path()
+            && let Err(e) = persist::save_json(&path, &self.pending()) {
+            log::warn!("Failed to save ratings to {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ratings_must_be_numbers_in_range() {
+        assert_eq!(parse(" 7.5 "), Ok(7.5));
+        assert_eq!(parse("0"), Ok(0.0));
+        assert_eq!(parse("10"), Ok(10.0));
+        assert!(parse("10.1").is_err());
+        assert!(parse("-1").is_err());
+        assert!(parse("great").is_err());
+        assert!(parse("NaN").is_err());
+    }
+
+    #[test]
+    fn background_sync_keeps_ratings_changed_meanwhile() {
+        let mut ratings = UserRatings::default();
+        ratings.ratings.insert(PathBuf::from("a.mkv"), Rating { rating: 7.0, synced: false });
+        ratings.ratings.insert(PathBuf::from("b.mkv"), Rating { rating: 4.0, synced: false });
+        let rx = ratings.sync_in_background(|path, _| path != Path::new("b.mkv")).unwrap();
+        let sent = rx.recv().unwrap();
+        assert_eq!(sent, vec![(PathBuf::from("a.mkv"), 7.0)]);
+        // Rated again while the request was out: still waiting
+        ratings.ratings.insert(PathBuf::from("a.mkv"), Rating { rating: 8.5, synced: false });
+        ratings.ratings.get_mut(Path::new("b.mkv")).unwrap().synced = true;
+        ratings.mark_synced(&sent);
+        assert!(ratings.has_pending());
+        ratings.mark_synced(&[(PathBuf::from("a.mkv"), 8.5)]);
+        assert!(!ratings.has_pending());
+        assert!(UserRatings::default().sync_in_background(|_, _| true).is_none());
+    }
+}
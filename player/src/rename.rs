@@ -0,0 +1,78 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::subtitles;
+
+// Kept out of new names so the files stay usable on every system the library is shared with
+const ILLEGAL_CHARS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// Where `old` goes when renamed to `new_name` in the same folder, or why it cannot
+pub fn target(old: &Path, new_name: &str) -> Result<PathBuf, String> {
+    let name = new_name.trim();
+    if name.is_empty() || name == "." || name == ".." {
+        return Err("the name is empty".to_string());
+    }
+    if let Some(c) = name.chars().find(|c| ILLEGAL_CHARS.contains(c) || c.is_control()) {
+        return Err(format!("\"{}\" is not allowed in a file name", c.escape_default()));
+    }
+    let new = old.with_file_name(name);
+    // A change of case only is the same file on case-insensitive filesystems
+    let same_file = old.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.eq_ignore_ascii_case(name));
+    if !same_file && new.exists() {
+        return Err(format!("\"{}\" already exists", name));
+    }
+    Ok(new)
+}
+
+/// Rename `old` to `new_name`, taking along the subtitle files named after it
+/// ("Heat.en.srt" follows "Heat.mkv"). Returns the new path.
+pub fn rename(old: &Path, new_name: &str) -> Result<PathBuf, String> {
+    let new = target(old, new_name)?;
+    let subs = subtitles::find(old);
+    fs::rename(old, &new).map_err(|e| format!("Cannot rename {}: {}", old.display(), e))?;
+    let old_stem = old.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let new_stem = new.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    for sub in subs {
+        let Some(rest) = sub.file_name().and_then(|n| n.to_str()).and_then(|n| subtitles::suffix(n, old_stem)) else {
+            log::warn!("Not renaming {}: its name does not follow {}", sub.display(), old.display());
+            continue;
+        };
+        let moved = sub.with_file_name(format!("{}{}", new_stem, rest));
+        if !moved.exists()
+            && let Err(e) = fs::rename(&sub, &moved) {
+            log::warn!("Failed to rename {}: {}", sub.display(), e);
+        }
+    }
+    Ok(new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::test_dir;
+
+    #[test]
+    fn bad_names_are_refused() {
+        let old = Path::new("/nonexistent/Heat.mkv");
+        assert_eq!(target(old, " Heat (1995).mkv "), Ok(PathBuf::from("/nonexistent/Heat (1995).mkv")));
+        assert!(target(old, "").is_err());
+        assert!(target(old, "..").is_err());
+        assert_eq!(target(old, "Heat: Director's Cut.mkv"), Err("\":\" is not allowed in a file name".to_string()));
+        assert!(target(old, "../Heat.mkv").is_err());
+    }
+
+    #[test]
+    fn renames_the_video_and_its_subtitles() {
+        let dir = test_dir("rename");
+        for name in ["heat.mkv", "heat.en.srt", "heat.fr.forced.ASS", "heat 2.srt", "Heat 2.mkv"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        let old = dir.join("heat.mkv");
+        assert_eq!(rename(&old, "Heat 2.mkv"), Err("\"Heat 2.mkv\" already exists".to_string()));
+        assert_eq!(rename(&old, "Heat (1995).mkv"), Ok(dir.join("Heat (1995).mkv")));
+        assert!(dir.join("Heat (1995).mkv").exists() && !old.exists());
+        assert!(dir.join("Heat (1995).en.srt").exists());
+        assert!(dir.join("Heat (1995).fr.forced.ASS").exists());
+        assert!(dir.join("heat 2.srt").exists());
+    }
+}
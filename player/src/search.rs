@@ -0,0 +1,146 @@
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use regex::{Regex, RegexBuilder};
+
+// Matches scoring below this are too scattered to be what the user meant
+const MIN_SCORE: i64 = 10;
+
+/// Which fields the search box matches against (Tab cycles through them)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SearchScope {
+    #[default]
+    Title,
+    Director,
+    Genre,
+    // Title, director, genre and plot
+    All,
+}
+
+impl SearchScope {
+    pub fn next(self) -> Self {
+        match self {
+            SearchScope::Title => SearchScope::Director,
+            SearchScope::Director => SearchScope::Genre,
+            SearchScope::Genre => SearchScope::All,
+            SearchScope::All => SearchScope::Title,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SearchScope::Title => "Title",
+            SearchScope::Director => "Director",
+            SearchScope::Genre => "Genre",
+            SearchScope::All => "All",
+        }
+    }
+}
+
+/// A movie (index into the movie list) that matched the query
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchHit {
+    pub index: usize,
+    pub score: i64,
+}
+
+/// Fuzzy-match `query` against each candidate's texts (e.g. title and file name), keeping
+/// its best score. Hits come back best first; equal scores keep the candidates' order.
+pub fn rank(query: &str, candidates: &[(usize, Vec<String>)]) -> Vec<SearchHit> {
+    let matcher = SkimMatcherV2::default().ignore_case();
+    let mut hits: Vec<SearchHit> = candidates.iter()
+        .filter_map(|(index, texts)| {
+            let score = texts.iter().filter_map(|t| matcher.fuzzy_match(t, query)).max()?;
+            (score >= MIN_SCORE).then_some(SearchHit { index: *index, score })
+        })
+        .collect();
+    // Stable sort, so ties stay in list order
+    hits.sort_by_key(|h| std::cmp::Reverse(h.score));
+    hits
+}
+
+/// The query as a regular expression for the regex mode, ignoring case like the fuzzy search
+/// (an inline "(?-i)" turns that off)
+pub fn compile(query: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(query).case_insensitive(true).build()
+}
+
+/// Candidates with a text `pattern` matches, in their own order
+pub fn matching(pattern: &Regex, candidates: &[(usize, Vec<String>)]) -> Vec<usize> {
+    candidates.iter()
+        .filter(|(_, texts)| texts.iter().any(|t| pattern.is_match(t)))
+        .map(|(index, _)| *index)
+        .collect()
+}
+
+/// Char positions in `text` inside a match of `pattern`, for highlighting
+pub fn regex_positions(pattern: &Regex, text: &str) -> Vec<usize> {
+    let ranges: Vec<_> = pattern.find_iter(text).map(|m| m.range()).collect();
+    text.char_indices()
+        .enumerate()
+        .filter(|(_, (byte, _))| ranges.iter().any(|r| r.contains(byte)))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Char positions in `text` that `query` matched, for highlighting; empty without a match
+pub fn match_positions(query: &str, text: &str) -> Vec<usize> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    SkimMatcherV2::default().ignore_case()
+        .fuzzy_indices(text, query)
+        .map(|(_, indices)| indices)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates(names: &[&str]) -> Vec<(usize, Vec<String>)> {
+        names.iter().enumerate().map(|(i, n)| (i, vec![n.to_string()])).collect()
+    }
+
+    #[test]
+    fn subsequence_finds_long_names() {
+        let hits = rank("drknght", &candidates(&["Heat.mkv", "The Dark Knight.mkv", "Drive.mp4"]));
+        assert_eq!(hits.first().map(|h| h.index), Some(1));
+    }
+
+    #[test]
+    fn unrelated_entries_are_dropped() {
+        let hits = rank("nolan", &candidates(&["Amelie.mkv", "Up.mp4"]));
+        assert!(hits.is_empty());
+    }
+
+    #[test]
+    fn ties_keep_list_order() {
+        let hits = rank("alien", &candidates(&["Alien.mkv", "Aliens.mkv", "Alien.mp4"]));
+        let order: Vec<usize> = hits.iter().map(|h| h.index).collect();
+        assert_eq!(order[0], 0);
+        assert!(order.iter().position(|&i| i == 0) < order.iter().position(|&i| i == 2));
+    }
+
+    #[test]
+    fn best_text_counts() {
+        let candidates = vec![(0, vec!["tt0468569.mkv".to_string(), "The Dark Knight".to_string()])];
+        assert_eq!(rank("dark knight", &candidates).len(), 1);
+    }
+
+    #[test]
+    fn positions_of_the_matched_chars() {
+        assert_eq!(match_positions("heat", "Heat.mkv"), vec![0, 1, 2, 3]);
+        assert_eq!(match_positions("drk", "The Dark Knight.mkv"), vec![4, 6, 7]);
+        assert!(match_positions("zzz", "Heat.mkv").is_empty());
+        assert!(match_positions("", "Heat.mkv").is_empty());
+    }
+
+    #[test]
+    fn regex_mode_keeps_list_order() {
+        let pattern = compile(r"^the .* \(19[89]\d\)").unwrap();
+        let names = ["The Thing (1982).mkv", "Heat (1995).mkv", "The Matrix (1999).mkv", "The Batman (2022).mkv"];
+        assert_eq!(matching(&pattern, &candidates(&names)), vec![0, 2]);
+        assert_eq!(regex_positions(&compile("é.i").unwrap(), "Amélie"), vec![2, 3, 4]);
+        assert!(compile("(unclosed").is_err());
+    }
+}
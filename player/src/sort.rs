@@ -0,0 +1,654 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::episodes::EpisodeInfo;
+use crate::persist;
+use crate::{MovieEntry, MovieInfo};
+
+/// How the list is ordered on screen
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+    // Library order: folder groups, sorted by SORT_KEYS within each group
+    #[default]
+    Name,
+    // Grouped by each movie's first genre instead of its folder, "Unknown" last
+    Genre,
+    // Grouped by decade, oldest first and by year within each, "Unknown year" last
+    Decade,
+    // Flat lists across all groups, newest / best rated / most watched / largest first
+    Year,
+    Rating,
+    WatchCount,
+    FileSize,
+    // The user's hand-arranged order, shown as a flat list
+    Custom,
+}
+
+impl SortMode {
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Name => SortMode::Genre,
+            SortMode::Genre => SortMode::Decade,
+            SortMode::Decade => SortMode::Year,
+            SortMode::Year => SortMode::Rating,
+            SortMode::Rating => SortMode::WatchCount,
+            SortMode::WatchCount => SortMode::FileSize,
+            SortMode::FileSize => SortMode::Custom,
+            SortMode::Custom => SortMode::Name,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Name => "Name",
+            SortMode::Genre => "Genre",
+            SortMode::Decade => "Decade",
+            SortMode::Year => "Year",
+            SortMode::Rating => "Rating",
+            SortMode::WatchCount => "Watch Count",
+            SortMode::FileSize => "File Size",
+            SortMode::Custom => "Custom",
+        }
+    }
+
+    /// Whether the list shows group headers in this mode
+    pub fn is_grouped(self) -> bool {
+        matches!(self, SortMode::Name | SortMode::Genre | SortMode::Decade)
+    }
+
+    /// The next of the grouped modes: folders, genres, decades
+    pub fn next_grouping(self) -> Self {
+        match self {
+            SortMode::Name => SortMode::Genre,
+            SortMode::Genre => SortMode::Decade,
+            _ => SortMode::Name,
+        }
+    }
+}
+
+// Group of the movies without genre metadata in the genre view
+pub const UNKNOWN_GENRE: &str = "Unknown";
+
+/// The group a movie is listed under in the genre view: its first genre
+pub fn genre_group(info: Option<&MovieInfo>) -> &str {
+    info.and_then(|i| i.genres().next()).unwrap_or(UNKNOWN_GENRE)
+}
+
+// Group of the movies without a year in the decade view
+pub const UNKNOWN_YEAR: &str = "Unknown year";
+
+/// The group a movie is listed under in the decade view: "1990s"
+pub fn decade_group(info: Option<&MovieInfo>) -> String {
+    match info.and_then(|i| i.year) {
+        Some(year) => format!("{}s", year - year.rem_euclid(10)),
+        None => UNKNOWN_YEAR.to_string(),
+    }
+}
+
+/// Indices into `movies` in the order they should be displayed.
+/// Movies missing the sorted field go last, in library order.
+pub fn display_order(movies: &[MovieEntry], mode: SortMode, custom_order: &[PathBuf], info: &HashMap<PathBuf, MovieInfo>) -> Vec<usize> {
+    let by_field = |field: SortField| {
+        let key = SortKey::desc(field);
+        let mut order: Vec<usize> = (0..movies.len()).collect();
+        // Stable sort, so ties keep library order
+        order.sort_by(|&a, &b| compare_field(&key, &movies[a], info.get(&movies[a].path), &movies[b], info.get(&movies[b].path)));
+        order
+    };
+    match mode {
+        SortMode::Name => (0..movies.len()).collect(),
+        SortMode::Genre => {
+            let mut order: Vec<usize> = (0..movies.len()).collect();
+            // Stable sort, so each genre keeps library order
+            order.sort_by_cached_key(|&i| {
+                let genre = genre_group(info.get(&movies[i].path));
+                (genre == UNKNOWN_GENRE, genre.to_lowercase())
+            });
+            order
+        }
+        SortMode::Decade => {
+            let mut order: Vec<usize> = (0..movies.len()).collect();
+            order.sort_by_key(|&i| {
+                let year = info.get(&movies[i].path).and_then(|i| i.year);
+                (year.is_none(), year)
+            });
+            order
+        }
+        SortMode::Year => by_field(SortField::Year),
+        SortMode::Rating => by_field(SortField::Rating),
+        SortMode::WatchCount => by_field(SortField::WatchCount),
+        SortMode::FileSize => {
+            // Sizes come with the metadata; only movies without one are read from disk, once per sort
+            let sizes: Vec<Option<u64>> = movies.iter()
+                .map(|m| info.get(&m.path).and_then(|i| i.file_bytes).or_else(|| fs::metadata(&m.path).ok().map(|meta| meta.len())))
+                .collect();
+            let mut order: Vec<usize> = (0..movies.len()).collect();
+            order.sort_by(|&a, &b| compare_present(sizes[a], sizes[b], SortDirection::Desc, |x, y| x.cmp(y)));
+            order
+        }
+        SortMode::Custom => {
+            let position: HashMap<&PathBuf, usize> = custom_order.iter().enumerate().map(|(i, p)| (p, i)).collect();
+            let mut order: Vec<usize> = (0..movies.len()).collect();
+            // Movies missing from the saved order go last, in library order
+            order.sort_by_key(|&i| position.get(&movies[i].path).copied().unwrap_or(usize::MAX));
+            order
+        }
+    }
+}
+
+/// Every movie by title, across the groups (the name sort of the flat list)
+pub fn alphabetical(movies: &[MovieEntry], info: &HashMap<PathBuf, MovieInfo>) -> Vec<usize> {
+    let key = SortKey::asc(SortField::Title);
+    let mut order: Vec<usize> = (0..movies.len()).collect();
+    order.sort_by(|&a, &b| compare_field(&key, &movies[a], info.get(&movies[a].path), &movies[b], info.get(&movies[b].path)));
+    order
+}
+
+fn custom_order_path() -> Option<PathBuf> {
+    persist::data_dir().map(|dir| dir.join("custom_order.json"))
+}
+
+/// Load the saved manual order, dropping files that no longer exist and
+/// appending new ones at the end in library order.
+pub fn load_custom_order(movies: &[MovieEntry]) -> Vec<PathBuf> {
+    let saved: Vec<PathBuf> = custom_order_path()
+        .and_then(|path| persist::load_json(&path))
+        .unwrap_or_default();
+    reconcile(saved, movies)
+}
+
+fn reconcile(saved: Vec<PathBuf>, movies: &[MovieEntry]) -> Vec<PathBuf> {
+    let mut order: Vec<PathBuf> = saved.into_iter()
+        .filter(|p| movies.iter().any(|m| m.path == *p))
+        .collect();
+    for movie in movies {
+        if !order.contains(&movie.path) {
+            order.push(movie.path.clone());
+        }
+    }
+    order
+}
+
+/// Put `new` in the place of `old` in a manual order, for a renamed file. Returns whether
+/// `old` was in it.
+pub fn rename_in_order(order: &mut [PathBuf], old: &Path, new: &Path) -> bool {
+    match order.iter_mut().find(|p| *p == old) {
+        Some(place) => {
+            *place = new.to_path_buf();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Trade the places of `a` and `b` in a manual order; the movies between them stay put.
+/// Returns whether both were in it.
+pub fn swap_in_order(order: &mut [PathBuf], a: &Path, b: &Path) -> bool {
+    let find = |path: &Path| order.iter().position(|p| p == path);
+    match (find(a), find(b)) {
+        (Some(a), Some(b)) => {
+            order.swap(a, b);
+            true
+        }
+        _ => false,
+    }
+}
+
+pub fn save_custom_order(order: &[PathBuf]) {
+    if let Some(path) = custom_order_path()
+        && let Err(e) = persist::save_json(&path, &order) {
+        log::warn!("Failed to save custom order to {}: {}", path.display(), e);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortField {
+    Title,
+    Year,
+    Rating,
+    WatchCount,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SortKey {
+    pub field: SortField,
+    pub direction: SortDirection,
+}
+
+impl SortKey {
+    pub fn asc(field: SortField) -> Self {
+        SortKey { field, direction: SortDirection::Asc }
+    }
+
+    pub fn desc(field: SortField) -> Self {
+        SortKey { field, direction: SortDirection::Desc }
+    }
+
+    /// Parse a single key such as `rating:desc` or `title` (ascending by default)
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut parts = spec.trim().splitn(2, ':');
+        let field = match parts.next()?.trim().to_lowercase().as_str() {
+            "title" | "name" => SortField::Title,
+            "year" => SortField::Year,
+            "rating" => SortField::Rating,
+            "watch_count" | "watches" => SortField::WatchCount,
+            _ => return None,
+        };
+        match parts.next().map(|d| d.trim().to_lowercase()).as_deref() {
+            None | Some("asc") => Some(SortKey::asc(field)),
+            Some("desc") => Some(SortKey::desc(field)),
+            Some(_) => None,
+        }
+    }
+}
+
+/// Composable multi-key comparator over movie metadata.
+/// Keys are applied in order; the file name is always the final tie-breaker so
+/// the resulting order is deterministic.
+#[derive(Clone, Debug, Default)]
+pub struct MovieComparator {
+    keys: Vec<SortKey>,
+}
+
+impl MovieComparator {
+    pub fn new() -> Self {
+        MovieComparator { keys: Vec::new() }
+    }
+
+    pub fn then(mut self, key: SortKey) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Build a comparator from a comma separated list, e.g. `rating:desc,year:desc,title`.
+    /// Unknown keys are reported and skipped.
+    pub fn parse(spec: &str) -> Self {
+        let mut comparator = MovieComparator::new();
+        for part in spec.split(',').filter(|p| !p.trim().is_empty()) {
+            match SortKey::parse(part) {
+                Some(key) => comparator = comparator.then(key),
+                None => log::warn!("Ignoring unknown sort key: {}", part.trim()),
+            }
+        }
+        comparator
+    }
+
+    pub fn compare(&self, a: &MovieEntry, b: &MovieEntry, info: &HashMap<PathBuf, MovieInfo>) -> Ordering {
+        let a_info = info.get(&a.path);
+        let b_info = info.get(&b.path);
+        for key in &self.keys {
+            let ord = compare_field(key, a, a_info, b, b_info);
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        }
+        // Ties between episodes go by show, season and episode rather than by name
+        match (EpisodeInfo::from_path(&a.path), EpisodeInfo::from_path(&b.path)) {
+            (Some(x), Some(y)) => x.cmp(&y).then_with(|| natural_cmp(file_name(a), file_name(b))),
+            _ => natural_cmp(file_name(a), file_name(b)),
+        }
+    }
+
+    pub fn sort(&self, movies: &mut [MovieEntry], info: &HashMap<PathBuf, MovieInfo>) {
+        movies.sort_by(|a, b| self.compare(a, b, info));
+    }
+}
+
+fn file_name(movie: &MovieEntry) -> &str {
+    movie.path.file_name().and_then(|n| n.to_str()).unwrap_or("")
+}
+
+/// Pin `group` above the other groups, or unpin it; returns whether it is pinned now
+pub fn toggle_pin(pinned: &mut Vec<String>, group: &str) -> bool {
+    match pinned.iter().position(|g| g == group) {
+        Some(pos) => {
+            pinned.remove(pos);
+            false
+        }
+        None => {
+            pinned.push(group.to_string());
+            true
+        }
+    }
+}
+
+/// Move the movies of pinned groups above the rest, in pin order; movies keep their order
+/// within each group
+pub fn pin_groups<S: AsRef<str>>(order: &mut [usize], pinned: &[String], group_of: impl Fn(usize) -> S) {
+    if pinned.is_empty() {
+        return;
+    }
+    let ranks: HashMap<usize, usize> = order.iter()
+        .filter_map(|&i| Some((i, pinned.iter().position(|g| g == group_of(i).as_ref())?)))
+        .collect();
+    order.sort_by_key(|i| ranks.get(i).copied().unwrap_or(usize::MAX));
+}
+
+/// Put folder groups in display order: the ones named in `priority` first, in that order
+/// (case-insensitively), then the rest by name with "Root" leading unless it is named
+pub fn order_groups(names: &mut [String], priority: &[String]) {
+    let rank = |name: &str| priority.iter().position(|p| p.trim().eq_ignore_ascii_case(name));
+    names.sort_by(|a, b| match (rank(a), rank(b)) {
+        (Some(x), Some(y)) => x.cmp(&y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => (b == "Root").cmp(&(a == "Root")).then_with(|| natural_cmp(a, b)),
+    });
+}
+
+/// Compare so that runs of digits count as numbers: "Rocky 2" before "Rocky 10"
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let take_number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                        digits.push(c);
+                    }
+                    digits
+                };
+                let (x, y) = (take_number(&mut a), take_number(&mut b));
+                let (x_trimmed, y_trimmed) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                // Longer numbers are bigger; same length compares digit by digit; then fewer leading zeros first
+                let ord = x_trimmed.len().cmp(&y_trimmed.len())
+                    .then_with(|| x_trimmed.cmp(y_trimmed))
+                    .then_with(|| x.len().cmp(&y.len()));
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+fn title_of(movie: &MovieEntry, info: Option<&MovieInfo>) -> Option<String> {
+    info.and_then(|i| i.title.clone())
+        .or_else(|| movie.path.file_stem().and_then(|s| s.to_str()).map(|s| s.to_string()))
+        .map(|t| t.to_lowercase())
+}
+
+fn compare_field(key: &SortKey, a: &MovieEntry, a_info: Option<&MovieInfo>, b: &MovieEntry, b_info: Option<&MovieInfo>) -> Ordering {
+    match key.field {
+        SortField::Title => compare_present(title_of(a, a_info), title_of(b, b_info), key.direction, |x, y| natural_cmp(x, y)),
+        SortField::Year => compare_present(a_info.and_then(|i| i.year), b_info.and_then(|i| i.year), key.direction, |x, y| x.cmp(y)),
+        SortField::Rating => compare_present(a_info.and_then(|i| i.rating), b_info.and_then(|i| i.rating), key.direction, |x, y| x.total_cmp(y)),
+        SortField::WatchCount => compare_present(a_info.and_then(|i| i.watch_count), b_info.and_then(|i| i.watch_count), key.direction, |x, y| x.cmp(y)),
+    }
+}
+
+/// Compare two optional values; missing values always sort after present ones,
+/// regardless of the requested direction.
+fn compare_present<T>(a: Option<T>, b: Option<T>, direction: SortDirection, cmp: impl Fn(&T, &T) -> Ordering) -> Ordering {
+    match (a, b) {
+        (Some(x), Some(y)) => match direction {
+            SortDirection::Asc => cmp(&x, &y),
+            SortDirection::Desc => cmp(&y, &x),
+        },
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str) -> MovieEntry {
+        MovieEntry { path: PathBuf::from(name), group_name: "Root".to_string(), versions: Vec::new() }
+    }
+
+    fn info(title: &str, year: Option<i32>, rating: Option<f64>) -> MovieInfo {
+        MovieInfo { title: Some(title.to_string()), year, rating, ..Default::default() }
+    }
+
+    fn sorted_names(comparator: &MovieComparator, movies: &[MovieEntry], map: &HashMap<PathBuf, MovieInfo>) -> Vec<String> {
+        let mut movies = movies.to_vec();
+        comparator.sort(&mut movies, map);
+        movies.iter().map(|m| m.path.to_string_lossy().to_string()).collect()
+    }
+
+    #[test]
+    fn rating_ties_break_on_year_then_title() {
+        let movies = vec![entry("a.mkv"), entry("b.mkv"), entry("c.mkv"), entry("d.mkv")];
+        let mut map = HashMap::new();
+        map.insert(PathBuf::from("a.mkv"), info("Zulu", Some(1990), Some(8.0)));
+        map.insert(PathBuf::from("b.mkv"), info("Alpha", Some(1990), Some(8.0)));
+        map.insert(PathBuf::from("c.mkv"), info("Mike", Some(2001), Some(8.0)));
+        map.insert(PathBuf::from("d.mkv"), info("Echo", Some(1985), Some(9.1)));
+
+        let comparator = MovieComparator::new()
+            .then(SortKey::desc(SortField::Rating))
+            .then(SortKey::desc(SortField::Year))
+            .then(SortKey::asc(SortField::Title));
+
+        assert_eq!(sorted_names(&comparator, &movies, &map), vec!["d.mkv", "c.mkv", "b.mkv", "a.mkv"]);
+    }
+
+    #[test]
+    fn missing_fields_sort_last_in_both_directions() {
+        let movies = vec![entry("a.mkv"), entry("b.mkv"), entry("c.mkv")];
+        let mut map = HashMap::new();
+        map.insert(PathBuf::from("a.mkv"), info("A", None, None));
+        map.insert(PathBuf::from("b.mkv"), info("B", Some(2000), None));
+        map.insert(PathBuf::from("c.mkv"), info("C", Some(1970), None));
+
+        let asc = MovieComparator::new().then(SortKey::asc(SortField::Year));
+        assert_eq!(sorted_names(&asc, &movies, &map), vec!["c.mkv", "b.mkv", "a.mkv"]);
+
+        let desc = MovieComparator::new().then(SortKey::desc(SortField::Year));
+        assert_eq!(sorted_names(&desc, &movies, &map), vec!["b.mkv", "c.mkv", "a.mkv"]);
+    }
+
+    #[test]
+    fn episodes_sort_by_season_and_episode() {
+        let movies = vec![entry("Show.S01E10.mkv"), entry("Show.S02E01.mkv"), entry("Extras.mkv"), entry("Show.S01E02.mkv")];
+        let comparator = MovieComparator::new().then(SortKey::asc(SortField::Title));
+        assert_eq!(
+            sorted_names(&comparator, &movies, &HashMap::new()),
+            vec!["Extras.mkv", "Show.S01E02.mkv", "Show.S01E10.mkv", "Show.S02E01.mkv"]
+        );
+
+        // Configured keys still come first; the episode order only breaks ties
+        let mut map = HashMap::new();
+        map.insert(PathBuf::from("Show.S02E01.mkv"), info("Show", None, Some(9.0)));
+        map.insert(PathBuf::from("Show.S01E10.mkv"), info("Show", None, Some(7.0)));
+        map.insert(PathBuf::from("Show.S01E02.mkv"), info("Show", None, Some(7.0)));
+        let by_rating = MovieComparator::new().then(SortKey::desc(SortField::Rating));
+        assert_eq!(
+            sorted_names(&by_rating, &movies, &map),
+            vec!["Show.S02E01.mkv", "Show.S01E02.mkv", "Show.S01E10.mkv", "Extras.mkv"]
+        );
+    }
+
+    #[test]
+    fn saved_order_follows_the_library() {
+        let movies = vec![entry("a.mkv"), entry("b.mkv"), entry("c.mkv"), entry("d.mkv")];
+        let saved = vec![PathBuf::from("c.mkv"), PathBuf::from("gone.mkv"), PathBuf::from("a.mkv")];
+        // Deleted files drop out; new ones are appended in library order
+        assert_eq!(
+            reconcile(saved, &movies),
+            vec![PathBuf::from("c.mkv"), PathBuf::from("a.mkv"), PathBuf::from("b.mkv"), PathBuf::from("d.mkv")]
+        );
+        assert_eq!(reconcile(Vec::new(), &movies).len(), 4);
+    }
+
+    #[test]
+    fn custom_mode_lists_the_saved_order() {
+        let movies = vec![entry("a.mkv"), entry("b.mkv"), entry("c.mkv"), entry("d.mkv")];
+        let custom = vec![PathBuf::from("d.mkv"), PathBuf::from("b.mkv")];
+        // Movies missing from the order follow, in library order
+        assert_eq!(display_order(&movies, SortMode::Custom, &custom, &HashMap::new()), vec![3, 1, 0, 2]);
+        let full = reconcile(vec![PathBuf::from("c.mkv"), PathBuf::from("a.mkv")], &movies);
+        assert_eq!(display_order(&movies, SortMode::Custom, &full, &HashMap::new()), vec![2, 0, 1, 3]);
+    }
+
+    #[test]
+    fn moves_skip_the_movies_in_between() {
+        // b.mkv is hidden by a filter, so moving c.mkv up trades places with a.mkv
+        let mut order = vec![PathBuf::from("a.mkv"), PathBuf::from("b.mkv"), PathBuf::from("c.mkv")];
+        assert!(swap_in_order(&mut order, Path::new("c.mkv"), Path::new("a.mkv")));
+        assert_eq!(order, vec![PathBuf::from("c.mkv"), PathBuf::from("b.mkv"), PathBuf::from("a.mkv")]);
+        assert!(!swap_in_order(&mut order, Path::new("c.mkv"), Path::new("d.mkv")));
+    }
+
+    #[test]
+    fn renamed_files_keep_their_manual_place() {
+        let mut order = vec![PathBuf::from("b.mkv"), PathBuf::from("heat.mkv"), PathBuf::from("a.mkv")];
+        assert!(rename_in_order(&mut order, Path::new("heat.mkv"), Path::new("Heat (1995).mkv")));
+        assert_eq!(order, vec![PathBuf::from("b.mkv"), PathBuf::from("Heat (1995).mkv"), PathBuf::from("a.mkv")]);
+        assert!(!rename_in_order(&mut order, Path::new("heat.mkv"), Path::new("c.mkv")));
+    }
+
+    #[test]
+    fn genre_view_groups_by_first_genre() {
+        let movies = vec![entry("a.mkv"), entry("b.mkv"), entry("c.mkv"), entry("d.mkv")];
+        let mut map = HashMap::new();
+        map.insert(PathBuf::from("a.mkv"), MovieInfo { genre: Some("Drama, Crime".to_string()), ..Default::default() });
+        map.insert(PathBuf::from("c.mkv"), MovieInfo { genre: Some("Comedy".to_string()), ..Default::default() });
+        map.insert(PathBuf::from("d.mkv"), MovieInfo { genre: Some("drama".to_string()), ..Default::default() });
+
+        assert_eq!(display_order(&movies, SortMode::Genre, &[], &map), vec![2, 0, 3, 1]);
+        assert_eq!(genre_group(map.get(&PathBuf::from("a.mkv"))), "Drama");
+        assert_eq!(genre_group(map.get(&PathBuf::from("b.mkv"))), UNKNOWN_GENRE);
+    }
+
+    #[test]
+    fn decade_view_is_chronological() {
+        let movies = vec![entry("a.mkv"), entry("b.mkv"), entry("c.mkv"), entry("d.mkv")];
+        let mut map = HashMap::new();
+        map.insert(PathBuf::from("a.mkv"), info("Heat", Some(1995), None));
+        map.insert(PathBuf::from("c.mkv"), info("Alien", Some(1979), None));
+        map.insert(PathBuf::from("d.mkv"), info("Speed", Some(1994), None));
+
+        assert_eq!(display_order(&movies, SortMode::Decade, &[], &map), vec![2, 3, 0, 1]);
+        assert_eq!(decade_group(map.get(&PathBuf::from("a.mkv"))), "1990s");
+        assert_eq!(decade_group(map.get(&PathBuf::from("c.mkv"))), "1970s");
+        assert_eq!(decade_group(None), UNKNOWN_YEAR);
+    }
+
+    #[test]
+    fn size_sort_uses_the_known_sizes() {
+        // None of these files exist, so only the sizes from the metadata count
+        let movies = vec![entry("a.mkv"), entry("b.mkv"), entry("c.mkv")];
+        let mut map = HashMap::new();
+        map.insert(PathBuf::from("a.mkv"), MovieInfo { file_bytes: Some(700), ..Default::default() });
+        map.insert(PathBuf::from("c.mkv"), MovieInfo { file_bytes: Some(4_000), ..Default::default() });
+        assert_eq!(display_order(&movies, SortMode::FileSize, &[], &map), vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn alphabetical_ignores_groups() {
+        let mut movies = vec![entry("Zodiac.mkv"), entry("Heat.mkv"), entry("Alien.mkv")];
+        movies[1].group_name = "Crime".to_string();
+        let mut map = HashMap::new();
+        map.insert(PathBuf::from("Zodiac.mkv"), info("Airplane!", None, None));
+        assert_eq!(alphabetical(&movies, &map), vec![0, 2, 1]);
+    }
+
+    #[test]
+    fn pinned_groups_lead_in_pin_order() {
+        let groups = ["Root", "Action", "Drama", "Action", "Comedy", "Drama"];
+        let mut pinned = Vec::new();
+        assert!(toggle_pin(&mut pinned, "Drama"));
+        assert!(toggle_pin(&mut pinned, "Action"));
+        let mut order: Vec<usize> = (0..groups.len()).collect();
+        pin_groups(&mut order, &pinned, |i| groups[i]);
+        assert_eq!(order, vec![2, 5, 1, 3, 0, 4]);
+
+        // Unpinning puts the group back among the others
+        assert!(!toggle_pin(&mut pinned, "Drama"));
+        assert_eq!(pinned, ["Action"]);
+        let mut order: Vec<usize> = (0..groups.len()).collect();
+        pin_groups(&mut order, &pinned, |i| groups[i]);
+        assert_eq!(order, vec![1, 3, 0, 2, 4, 5]);
+    }
+
+    #[test]
+    fn listed_groups_come_first() {
+        let names = || ["Archive", "Comedies", "Root", "Must Watch", "Action"].map(String::from);
+        let mut default = names();
+        order_groups(&mut default, &[]);
+        assert_eq!(default, ["Root", "Action", "Archive", "Comedies", "Must Watch"]);
+
+        let mut curated = names();
+        order_groups(&mut curated, &["must watch".to_string(), "Comedies".to_string(), "Gone".to_string()]);
+        assert_eq!(curated, ["Must Watch", "Comedies", "Root", "Action", "Archive"]);
+
+        let mut root_last = names();
+        order_groups(&mut root_last, &["Action".to_string(), "Root".to_string()]);
+        assert_eq!(root_last, ["Action", "Root", "Archive", "Comedies", "Must Watch"]);
+    }
+
+    #[test]
+    fn numbers_sort_by_value() {
+        assert_eq!(natural_cmp("Rocky 2", "Rocky 10"), Ordering::Less);
+        assert_eq!(natural_cmp("Part 10", "Part 9"), Ordering::Greater);
+        assert_eq!(natural_cmp("Part 02", "Part 2"), Ordering::Greater);
+        assert_eq!(natural_cmp("Alien", "Aliens"), Ordering::Less);
+        assert_eq!(natural_cmp("Heat", "Heat"), Ordering::Equal);
+
+        let movies = vec![entry("Rocky 10.mkv"), entry("Rocky 2.mkv"), entry("Rocky 1.mkv")];
+        assert_eq!(sorted_names(&MovieComparator::new(), &movies, &HashMap::new()), vec!["Rocky 1.mkv", "Rocky 2.mkv", "Rocky 10.mkv"]);
+    }
+
+    #[test]
+    fn full_ties_fall_back_to_file_name() {
+        let movies = vec![entry("z.mkv"), entry("m.mkv"), entry("a.mkv")];
+        let map = HashMap::new();
+        let comparator = MovieComparator::new()
+            .then(SortKey::desc(SortField::Rating))
+            .then(SortKey::asc(SortField::Year));
+        assert_eq!(sorted_names(&comparator, &movies, &map), vec!["a.mkv", "m.mkv", "z.mkv"]);
+    }
+
+    #[test]
+    fn flat_modes_put_missing_fields_last() {
+        let movies = vec![entry("a.mkv"), entry("b.mkv"), entry("c.mkv")];
+        let mut map = HashMap::new();
+        map.insert(PathBuf::from("a.mkv"), info("A", Some(1999), None));
+        map.insert(PathBuf::from("b.mkv"), info("B", None, Some(7.5)));
+        map.insert(PathBuf::from("c.mkv"), info("C", Some(2010), Some(6.0)));
+        assert_eq!(display_order(&movies, SortMode::Year, &[], &map), vec![2, 0, 1]);
+        assert_eq!(display_order(&movies, SortMode::Rating, &[], &map), vec![1, 2, 0]);
+        // Nothing to sort by: library order
+        assert_eq!(display_order(&movies, SortMode::WatchCount, &[], &map), vec![0, 1, 2]);
+        assert_eq!(display_order(&movies, SortMode::FileSize, &[], &map), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn parses_key_list() {
+        let comparator = MovieComparator::parse("rating:desc, year:desc ,title");
+        assert_eq!(comparator.keys, vec![
+            SortKey::desc(SortField::Rating),
+            SortKey::desc(SortField::Year),
+            SortKey::asc(SortField::Title),
+        ]);
+        assert!(SortKey::parse("rating:sideways").is_none());
+        assert!(SortKey::parse("budget").is_none());
+    }
+}
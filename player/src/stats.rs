@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::{MovieEntry, MovieInfo};
+
+/// Totals over the whole library, from the cached movie info
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LibraryStats {
+    pub movies: usize,
+    pub runtime_secs: u64,
+    // Movies whose runtime could be read
+    pub timed: usize,
+    pub average_rating: Option<f64>,
+    pub rated: usize,
+    pub watch_count: i64,
+    // Most common genres first
+    pub genres: Vec<(String, usize)>,
+}
+
+impl LibraryStats {
+    pub fn collect(movies: &[MovieEntry], info: &HashMap<PathBuf, MovieInfo>) -> Self {
+        let mut stats = LibraryStats { movies: movies.len(), ..Default::default() };
+        let mut rating_sum = 0.0;
+        let mut genres: HashMap<String, usize> = HashMap::new();
+        for info in movies.iter().filter_map(|m| info.get(&m.path)) {
+            if let Some(secs) = info.runtime.as_deref().and_then(parse_runtime) {
+                stats.runtime_secs += secs;
+                stats.timed += 1;
+            }
+            if let Some(rating) = info.rating {
+                rating_sum += rating;
+                stats.rated += 1;
+            }
+            stats.watch_count += i64::from(info.watch_count.unwrap_or(0).max(0));
+            for genre in info.genres() {
+                *genres.entry(genre.to_string()).or_default() += 1;
+            }
+        }
+        stats.average_rating = (stats.rated > 0).then(|| rating_sum / stats.rated as f64);
+        stats.genres = genres.into_iter().collect();
+        stats.genres.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        stats
+    }
+}
+
+/// Seconds in a runtime like "112 min", "1h 52m" (the API) or "1:52:00" (ffprobe)
+pub fn parse_runtime(runtime: &str) -> Option<u64> {
+    let runtime = runtime.trim().to_lowercase();
+    if runtime.contains(':') {
+        // [h:]mm:ss
+        let parts: Vec<u64> = runtime.split(':').map(|p| p.trim().parse().ok()).collect::<Option<_>>()?;
+        return match parts.as_slice() {
+            [h, m, s] => Some(h * 3600 + m * 60 + s),
+            [m, s] => Some(m * 60 + s),
+            _ => None,
+        };
+    }
+
+    let mut total = 0;
+    let mut found = false;
+    let mut number = String::new();
+    let mut chars = runtime.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        if c.is_whitespace() && !number.is_empty() {
+            // "112 min": the unit may follow a space
+            if chars.peek().is_some_and(|n| n.is_ascii_alphabetic()) {
+                continue;
+            }
+        }
+        if number.is_empty() {
+            continue;
+        }
+        let value: u64 = number.parse().ok()?;
+        number.clear();
+        total += match c {
+            'h' => value * 3600,
+            'm' => value * 60,
+            's' => value,
+            _ => return None,
+        };
+        found = true;
+        // Skip the rest of the unit word ("min", "hours")
+        while chars.peek().is_some_and(|n| n.is_ascii_alphabetic()) {
+            chars.next();
+        }
+    }
+    if !number.is_empty() {
+        // A bare number is minutes
+        total += number.parse::<u64>().ok()? * 60;
+        found = true;
+    }
+    found.then_some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runtime_formats() {
+        assert_eq!(parse_runtime("112 min"), Some(112 * 60));
+        assert_eq!(parse_runtime("1h 52m"), Some(112 * 60));
+        assert_eq!(parse_runtime("2h"), Some(7200));
+        assert_eq!(parse_runtime("1 hr 5 mins"), Some(65 * 60));
+        assert_eq!(parse_runtime("1:52:00"), Some(112 * 60));
+        assert_eq!(parse_runtime("52:30"), Some(52 * 60 + 30));
+        assert_eq!(parse_runtime("95"), Some(95 * 60));
+        assert_eq!(parse_runtime("N/A"), None);
+        assert_eq!(parse_runtime(""), None);
+    }
+
+    #[test]
+    fn totals_skip_missing_values() {
+        let movie = |name: &str| MovieEntry { path: PathBuf::from(name), group_name: String::new(), versions: Vec::new() };
+        let movies = vec![movie("a.mkv"), movie("b.mkv"), movie("c.mkv")];
+        let mut info = HashMap::new();
+        info.insert(PathBuf::from("a.mkv"), MovieInfo {
+            runtime: Some("1h 30m".to_string()), rating: Some(8.0), watch_count: Some(2),
+            genre: Some("Crime, Drama".to_string()), ..Default::default()
+        });
+        info.insert(PathBuf::from("b.mkv"), MovieInfo {
+            runtime: Some("N/A".to_string()), rating: Some(6.0), genre: Some("Drama".to_string()), ..Default::default()
+        });
+        let stats = LibraryStats::collect(&movies, &info);
+        assert_eq!(stats.movies, 3);
+        assert_eq!((stats.runtime_secs, stats.timed), (5400, 1));
+        assert_eq!((stats.average_rating, stats.rated), (Some(7.0), 2));
+        assert_eq!(stats.watch_count, 2);
+        assert_eq!(stats.genres, vec![("Drama".to_string(), 2), ("Crime".to_string(), 1)]);
+    }
+}
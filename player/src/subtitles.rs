@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::SUBTITLE_EXTENSIONS;
+
+// Language tags seen in subtitle names ("Heat.en.srt", "Heat.eng.srt"), by name
+const LANGUAGES: &[(&[&str], &str)] = &[
+    (&["en", "eng"], "English"),
+    (&["fr", "fre", "fra"], "French"),
+    (&["de", "ger", "deu"], "German"),
+    (&["es", "spa"], "Spanish"),
+    (&["it", "ita"], "Italian"),
+    (&["pt", "por"], "Portuguese"),
+    (&["nl", "dut", "nld"], "Dutch"),
+    (&["sv", "swe"], "Swedish"),
+    (&["no", "nor"], "Norwegian"),
+    (&["da", "dan"], "Danish"),
+    (&["fi", "fin"], "Finnish"),
+    (&["pl", "pol"], "Polish"),
+    (&["ru", "rus"], "Russian"),
+    (&["tr", "tur"], "Turkish"),
+    (&["ar", "ara"], "Arabic"),
+    (&["ja", "jpn"], "Japanese"),
+    (&["zh", "chi", "zho"], "Chinese"),
+    (&["ko", "kor"], "Korean"),
+];
+
+/// What follows `stem` in `name` when it is a subtitle file for a video called `stem`:
+/// ".srt" for "Heat.srt", ".en.forced.ass" for "Heat.en.forced.ass"
+pub fn suffix<'a>(name: &'a str, stem: &str) -> Option<&'a str> {
+    let (base, ext) = name.rsplit_once('.')?;
+    let rest = name.strip_prefix(stem)?;
+    let tags = base.strip_prefix(stem)?;
+    (SUBTITLE_EXTENSIONS.contains(&ext.to_lowercase().as_str()) && (tags.is_empty() || tags.starts_with('.'))).then_some(rest)
+}
+
+/// Whether `name` is a subtitle file for a video called `stem`
+fn belongs_to(name: &str, stem: &str) -> bool {
+    suffix(name, stem).is_some()
+}
+
+/// Subtitle files next to `video` sharing its base name, sorted by name
+pub fn find(video: &Path) -> Vec<PathBuf> {
+    find_all(std::slice::from_ref(&video.to_path_buf())).remove(video).unwrap_or_default()
+}
+
+/// Subtitle files for each of `videos`, reading every folder once
+pub fn find_all(videos: &[PathBuf]) -> HashMap<PathBuf, Vec<PathBuf>> {
+    let mut by_dir: HashMap<&Path, Vec<&PathBuf>> = HashMap::new();
+    for video in videos {
+        if let Some(dir) = video.parent() {
+            by_dir.entry(dir).or_default().push(video);
+        }
+    }
+    let mut found = HashMap::new();
+    for (dir, videos) in by_dir {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        let mut names: Vec<String> = entries.filter_map(|e| e.ok()?.file_name().into_string().ok()).collect();
+        names.sort();
+        for video in videos {
+            let Some(stem) = video.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let files: Vec<PathBuf> = names.iter().filter(|n| belongs_to(n, stem)).map(|n| dir.join(n)).collect();
+            if !files.is_empty() {
+                found.insert(video.clone(), files);
+            }
+        }
+    }
+    found
+}
+
+/// How a subtitle file is shown in the info panel: "English (srt)", or just "srt" when its
+/// name carries no language
+pub fn describe(subtitle: &Path, video: &Path) -> String {
+    let ext = subtitle.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    let stem = video.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let tag = subtitle.file_stem()
+        .and_then(|s| s.to_str())
+        .and_then(|s| s.strip_prefix(stem))
+        .and_then(|rest| rest.split('.').find(|t| !t.is_empty()));
+    match tag {
+        Some(tag) => {
+            let code = tag.to_lowercase();
+            let language = LANGUAGES.iter()
+                .find(|(codes, name)| codes.contains(&code.as_str()) || name.eq_ignore_ascii_case(tag))
+                .map_or(tag, |(_, name)| name);
+            format!("{} ({})", language, ext)
+        }
+        None => ext,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::test_dir;
+
+    #[test]
+    fn matches_by_base_name() {
+        assert!(belongs_to("Heat (1995).srt", "Heat (1995)"));
+        assert!(belongs_to("Heat (1995).en.forced.ASS", "Heat (1995)"));
+        assert!(!belongs_to("Heat (1995) 2.srt", "Heat (1995)"));
+        assert!(!belongs_to("Heat (1995).nfo", "Heat (1995)"));
+        assert!(!belongs_to("Heat 2.srt", "Heat"));
+        assert_eq!(suffix("Heat (1995).en.forced.ASS", "Heat (1995)"), Some(".en.forced.ASS"));
+        assert_eq!(suffix("Heat (1995) 2.srt", "Heat (1995)"), None);
+    }
+
+    #[test]
+    fn describes_the_language_and_format() {
+        let video = Path::new("/movies/Heat.mkv");
+        assert_eq!(describe(Path::new("/movies/Heat.en.srt"), video), "English (srt)");
+        assert_eq!(describe(Path::new("/movies/Heat.fre.forced.ass"), video), "French (ass)");
+        assert_eq!(describe(Path::new("/movies/Heat.english.srt"), video), "English (srt)");
+        assert_eq!(describe(Path::new("/movies/Heat.Klingon.srt"), video), "Klingon (srt)");
+        assert_eq!(describe(Path::new("/movies/Heat.srt"), video), "srt");
+    }
+
+    #[test]
+    fn finds_the_files_next_to_the_video() {
+        let dir = test_dir("subs");
+        for name in ["Heat.mkv", "Heat.srt", "Heat.de.ass", "Heat 2.mkv", "Heat 2.srt", "Heat.txt"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        let video = dir.join("Heat.mkv");
+        assert_eq!(find(&video), vec![dir.join("Heat.de.ass"), dir.join("Heat.srt")]);
+        let all = find_all(&[video.clone(), dir.join("Heat 2.mkv")]);
+        assert_eq!(all[&dir.join("Heat 2.mkv")], vec![dir.join("Heat 2.srt")]);
+    }
+}
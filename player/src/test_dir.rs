@@ -0,0 +1,30 @@
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A directory of its own for one test, removed with everything in it when dropped
+pub struct TestDir(PathBuf);
+
+/// Create an empty directory under the system temp dir, named after the test and the process
+/// so that parallel runs do not share it
+pub fn test_dir(name: &str) -> TestDir {
+    let dir = std::env::temp_dir().join(format!("movieplayer-{}-{}", name, std::process::id()));
+    // Left over from a run that was killed before cleaning up
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    TestDir(dir)
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,246 @@
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+/// Built-in color schemes, picked with `theme` in the config file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemePreset {
+    #[default]
+    Default,
+    // The terminal's own foreground everywhere, for monochrome terminals
+    Mono,
+    // Bright colors only, nothing dark gray, and the selected row in reverse video
+    HighContrast,
+}
+
+/// Colors by what they mark on screen rather than where
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    // Movie list border
+    pub border: Color,
+    pub taskbar_border: Color,
+    // Info panel and the full-screen views (help, stats, now playing)
+    pub panel_border: Color,
+    // Popups: search, queue, pickers and prompts
+    pub popup_border: Color,
+    pub title: Color,
+    // Group headers in the list
+    pub header: Color,
+    // The highlighted row
+    pub selected: Color,
+    // Every other row
+    pub item: Color,
+    // Field names in the info panel and stats, and their values
+    pub label: Color,
+    pub value: Color,
+    // Give the main info panel fields their own label colors (Year green, Genre yellow, ...)
+    // instead of `label`
+    pub field_label_colors: bool,
+    // Hints, watched marks and other secondary text
+    pub muted: Color,
+    // Stars, favorite marks and key names
+    pub accent: Color,
+    // Up next, resume available, picked genres and progress
+    pub positive: Color,
+    // Errors and confirmations
+    pub error: Color,
+    // Status messages in the taskbar
+    pub status: Color,
+    // Mark the highlighted row by swapping its colors rather than by its color alone
+    pub reverse_selection: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::preset(ThemePreset::Default)
+    }
+}
+
+/// Per-role colors from the `[colors]` table, over the preset's: names ("cyan", "light-red"),
+/// 256-color indexes ("208") or hex ("#ffaa00")
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorOverrides {
+    pub border: Option<String>,
+    pub taskbar_border: Option<String>,
+    pub panel_border: Option<String>,
+    pub popup_border: Option<String>,
+    pub title: Option<String>,
+    pub header: Option<String>,
+    pub selected: Option<String>,
+    pub item: Option<String>,
+    pub label: Option<String>,
+    pub value: Option<String>,
+    pub muted: Option<String>,
+    pub accent: Option<String>,
+    pub positive: Option<String>,
+    pub error: Option<String>,
+    pub status: Option<String>,
+}
+
+impl Theme {
+    pub fn preset(preset: ThemePreset) -> Self {
+        match preset {
+            ThemePreset::Default => Theme {
+                border: Color::Blue,
+                taskbar_border: Color::Cyan,
+                panel_border: Color::Magenta,
+                popup_border: Color::Green,
+                title: Color::Yellow,
+                header: Color::Yellow,
+                selected: Color::Cyan,
+                item: Color::Gray,
+                label: Color::Cyan,
+                value: Color::White,
+                field_label_colors: true,
+                muted: Color::DarkGray,
+                accent: Color::Yellow,
+                positive: Color::Green,
+                error: Color::Red,
+                status: Color::Yellow,
+                reverse_selection: false,
+            },
+            ThemePreset::Mono => Theme {
+                border: Color::Reset,
+                taskbar_border: Color::Reset,
+                panel_border: Color::Reset,
+                popup_border: Color::Reset,
+                title: Color::Reset,
+                header: Color::Reset,
+                selected: Color::Reset,
+                item: Color::Reset,
+                label: Color::Reset,
+                value: Color::Reset,
+                field_label_colors: false,
+                muted: Color::Reset,
+                accent: Color::Reset,
+                positive: Color::Reset,
+                error: Color::Reset,
+                status: Color::Reset,
+                reverse_selection: true,
+            },
+            ThemePreset::HighContrast => Theme {
+                border: Color::White,
+                taskbar_border: Color::White,
+                panel_border: Color::White,
+                popup_border: Color::White,
+                title: Color::LightYellow,
+                header: Color::LightYellow,
+                selected: Color::LightCyan,
+                item: Color::White,
+                label: Color::LightCyan,
+                value: Color::White,
+                field_label_colors: false,
+                muted: Color::Gray,
+                accent: Color::LightYellow,
+                positive: Color::LightGreen,
+                error: Color::LightRed,
+                status: Color::LightYellow,
+                reverse_selection: true,
+            },
+        }
+    }
+
+    /// The preset with the configured colors laid over it
+    pub fn from_config(preset: ThemePreset, overrides: &ColorOverrides) -> Result<Self, String> {
+        let mut theme = Theme::preset(preset);
+        let roles = [
+            ("border", &overrides.border, &mut theme.border),
+            ("taskbar_border", &overrides.taskbar_border, &mut theme.taskbar_border),
+            ("panel_border", &overrides.panel_border, &mut theme.panel_border),
+            ("popup_border", &overrides.popup_border, &mut theme.popup_border),
+            ("title", &overrides.title, &mut theme.title),
+            ("header", &overrides.header, &mut theme.header),
+            ("selected", &overrides.selected, &mut theme.selected),
+            ("item", &overrides.item, &mut theme.item),
+            ("label", &overrides.label, &mut theme.label),
+            ("value", &overrides.value, &mut theme.value),
+            ("muted", &overrides.muted, &mut theme.muted),
+            ("accent", &overrides.accent, &mut theme.accent),
+            ("positive", &overrides.positive, &mut theme.positive),
+            ("error", &overrides.error, &mut theme.error),
+            ("status", &overrides.status, &mut theme.status),
+        ];
+        for (role, name, color) in roles {
+            if let Some(name) = name {
+                *color = name.trim().parse().map_err(|_| format!("{}: unknown color \"{}\"", role, name))?;
+            }
+        }
+        // A configured label color is meant for every label
+        if overrides.label.is_some() {
+            theme.field_label_colors = false;
+        }
+        Ok(theme)
+    }
+
+    /// Color of the info panel label of `field` ("Year", "Genre", ...)
+    pub fn field_label(&self, field: &str) -> Color {
+        if !self.field_label_colors {
+            return self.label;
+        }
+        match field {
+            "Year" | "Rating" => Color::Green,
+            "Genre" | "Watch Count" | "File Size" => Color::Yellow,
+            "Director" | "Codec" => Color::Magenta,
+            "Runtime" | "Resolution" => Color::Blue,
+            _ => self.label,
+        }
+    }
+
+    /// The highlighted row: bold in the selected color, reversed where the scheme asks for it
+    pub fn selected_style(&self) -> Style {
+        let style = Style::default().fg(self.selected).add_modifier(Modifier::BOLD);
+        if self.reverse_selection { style.add_modifier(Modifier::REVERSED) } else { style }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_replace_the_preset_colors() {
+        let overrides = ColorOverrides {
+            selected: Some("light-red".to_string()),
+            border: Some("#ffaa00".to_string()),
+            muted: Some("244".to_string()),
+            ..Default::default()
+        };
+        let theme = Theme::from_config(ThemePreset::Mono, &overrides).unwrap();
+        assert_eq!(theme.selected, Color::LightRed);
+        assert_eq!(theme.border, Color::Rgb(0xff, 0xaa, 0x00));
+        assert_eq!(theme.muted, Color::Indexed(244));
+        assert_eq!(theme.value, Color::Reset);
+    }
+
+    #[test]
+    fn default_preset_keeps_the_original_colors() {
+        let theme = Theme::default();
+        assert_eq!((theme.border, theme.taskbar_border, theme.panel_border, theme.popup_border), (Color::Blue, Color::Cyan, Color::Magenta, Color::Green));
+        assert_eq!((theme.title, theme.selected, theme.item, theme.muted), (Color::Yellow, Color::Cyan, Color::Gray, Color::DarkGray));
+        let labels = ["Title", "Year", "Genre", "Director", "Runtime", "Rating", "Watch Count", "Plot", "File Size", "Codec", "Resolution"].map(|field| theme.field_label(field));
+        assert_eq!(labels, [Color::Cyan, Color::Green, Color::Yellow, Color::Magenta, Color::Blue, Color::Green, Color::Yellow, Color::Cyan, Color::Yellow, Color::Magenta, Color::Blue]);
+    }
+
+    #[test]
+    fn a_configured_label_color_applies_to_every_field() {
+        let overrides = ColorOverrides { label: Some("red".to_string()), ..Default::default() };
+        let theme = Theme::from_config(ThemePreset::Default, &overrides).unwrap();
+        assert_eq!(theme.field_label("Year"), Color::Red);
+        assert_eq!(Theme::preset(ThemePreset::HighContrast).field_label("Genre"), Color::LightCyan);
+    }
+
+    #[test]
+    fn selection_is_reversed_without_colors_to_tell_it_apart() {
+        assert!(!Theme::preset(ThemePreset::Default).selected_style().add_modifier.contains(Modifier::REVERSED));
+        for preset in [ThemePreset::Mono, ThemePreset::HighContrast] {
+            assert!(Theme::preset(preset).selected_style().add_modifier.contains(Modifier::REVERSED | Modifier::BOLD));
+        }
+    }
+
+    #[test]
+    fn unknown_colors_are_rejected() {
+        let overrides = ColorOverrides { header: Some("chartreuse".to_string()), ..Default::default() };
+        assert_eq!(Theme::from_config(ThemePreset::Default, &overrides), Err("header: unknown color \"chartreuse\"".to_string()));
+    }
+}
@@ -0,0 +1,48 @@
+use reqwest::Url;
+
+/// What to search for when the API has no trailer link: "Heat 1995 trailer"
+pub fn search_query(title: &str, year: Option<i32>) -> String {
+    match year {
+        Some(year) => format!("{} {} trailer", title.trim(), year),
+        None => format!("{} trailer", title.trim()),
+    }
+}
+
+/// What mpv is given to stream: the trailer link, or the first YouTube hit for `query`
+/// (both resolved by mpv through yt-dlp)
+pub fn stream_target(url: Option<&str>, query: &str) -> String {
+    match url {
+        Some(url) => url.to_string(),
+        None => format!("ytdl://ytsearch1:{}", query),
+    }
+}
+
+/// What the browser is sent to when the trailer cannot be streamed: the link, or a YouTube search
+pub fn browser_url(url: Option<&str>, query: &str) -> String {
+    match url {
+        Some(url) => url.to_string(),
+        None => Url::parse_with_params("https://www.youtube.com/results", [("search_query", query)])
+            .map(String::from)
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn searches_by_title_and_year() {
+        let query = search_query("Heat ", Some(1995));
+        assert_eq!(query, "Heat 1995 trailer");
+        assert_eq!(stream_target(None, &query), "ytdl://ytsearch1:Heat 1995 trailer");
+        assert_eq!(browser_url(None, &search_query("Amélie & co", None)), "https://www.youtube.com/results?search_query=Am%C3%A9lie+%26+co+trailer");
+    }
+
+    #[test]
+    fn known_links_are_used_as_they_are() {
+        let url = "https://www.youtube.com/watch?v=abc";
+        assert_eq!(stream_target(Some(url), "ignored"), url);
+        assert_eq!(browser_url(Some(url), "ignored"), url);
+    }
+}
@@ -0,0 +1,117 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::persist;
+use crate::RepeatMode;
+use crate::sort::SortMode;
+use crate::years::YearRange;
+
+/// Where the list was left, restored on the next launch (~/.local/share/movieplayer/state.json)
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    // Movie selected when the player was closed, and the first list row on screen
+    pub selected: Option<PathBuf>,
+    pub scroll_offset: usize,
+    pub sort_mode: SortMode,
+    pub genre_filter: Vec<String>,
+    pub min_rating: Option<f64>,
+    pub year_range: Option<YearRange>,
+    pub favorites_only: bool,
+    pub flat_list: bool,
+    pub pinned_groups: Vec<String>,
+    // Toggles as they were left; None keeps the config file's (or the built-in) default
+    pub shuffle: Option<bool>,
+    pub autoplay_next: Option<bool>,
+    pub idle_autoplay: Option<bool>,
+    pub subtitles: Option<bool>,
+    pub high_contrast: Option<bool>,
+    pub repeat: Option<RepeatMode>,
+    // Info panel width in percent, as moved with </>
+    pub info_percent: Option<u16>,
+    // The config file's settings for the toggles above when this was saved
+    pub config: ConfigSeen,
+}
+
+/// Config file settings that the saved state overrides, as they were on the last run; a
+/// setting changed since then wins over the saved value
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigSeen {
+    pub shuffle: Option<bool>,
+    pub autoplay_next: Option<bool>,
+    pub idle_autoplay: Option<bool>,
+    pub subtitles: Option<bool>,
+    pub high_contrast: Option<bool>,
+    pub repeat: Option<RepeatMode>,
+    pub info_percent: Option<u16>,
+}
+
+/// The value to start with: the one saved last time, unless the config file's setting was
+/// edited since (`seen` is the setting when it was saved)
+pub fn restore<T: PartialEq>(saved: Option<T>, configured: Option<T>, seen: Option<T>) -> Option<T> {
+    if configured != seen {
+        configured
+    } else {
+        saved.or(configured)
+    }
+}
+
+fn state_path() -> Option<PathBuf> {
+    persist::data_dir().map(|dir| dir.join("state.json"))
+}
+
+impl UiState {
+    pub fn load() -> Self {
+        state_path().and_then(|path| persist::load_json(&path)).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = state_path()
+            && let Err(e) = persist::save_json(&path, self) {
+            log::warn!("Failed to save the list state to {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_keep_defaults() {
+        let state: UiState = serde_json::from_str("{}").unwrap();
+        assert_eq!(state, UiState::default());
+        let state: UiState = serde_json::from_str(r#"{"selected": "/movies/Heat.mkv"}"#).unwrap();
+        assert_eq!(state.selected, Some(PathBuf::from("/movies/Heat.mkv")));
+    }
+
+    #[test]
+    fn edited_config_settings_win() {
+        // Unchanged config: the toggle stays as it was left
+        assert_eq!(restore(Some(true), Some(false), Some(false)), Some(true));
+        assert_eq!(restore(Some(true), None, None), Some(true));
+        // Edited since the last run: the config file's value
+        assert_eq!(restore(Some(true), Some(false), Some(true)), Some(false));
+        assert_eq!(restore(Some(40), Some(25), None), Some(25));
+        // Taken out of the config file: back to the built-in default
+        assert_eq!(restore(Some(true), None, Some(true)), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let state = UiState {
+            scroll_offset: 12,
+            sort_mode: SortMode::WatchCount,
+            genre_filter: vec!["Drama".to_string()],
+            min_rating: Some(7.5),
+            year_range: Some(YearRange { min: Some(1990), max: None }),
+            shuffle: Some(true),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&state).unwrap();
+        assert!(json.contains(r#""sort_mode":"watch_count""#));
+        assert_eq!(serde_json::from_str::<UiState>(&json).unwrap(), state);
+    }
+}
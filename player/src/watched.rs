@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+use crate::persist;
+
+/// A watched flag set by hand with the watched key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Mark {
+    watched: bool,
+    // Whether the API accepted it; unsynced marks are kept in watched.json
+    #[serde(skip)]
+    synced: bool,
+}
+
+/// Watched/unwatched marks that override the API's watch count
+#[derive(Clone, Debug, Default)]
+pub struct WatchedMarks {
+    marks: HashMap<PathBuf, Mark>,
+}
+
+fn marks_path() -> Option<PathBuf> {
+    persist::data_dir().map(|dir| dir.join("watched.json"))
+}
+
+impl WatchedMarks {
+    /// Marks saved while the API was unreachable
+    pub fn load() -> Self {
+        WatchedMarks {
+            marks: marks_path().and_then(|path| persist::load_json(&path)).unwrap_or_default(),
+        }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<bool> {
+        self.marks.get(path).map(|m| m.watched)
+    }
+
+    /// Record a mark; marks the API did not take are written to watched.json
+    pub fn set(&mut self, path: PathBuf, watched: bool, synced: bool) {
+        self.marks.insert(path, Mark { watched, synced });
+        self.save();
+    }
+
+    /// Forget the mark of a deleted file
+    pub fn remove(&mut self, path: &Path) {
+        if self.marks.remove(path).is_some() {
+            self.save();
+        }
+    }
+
+    /// Keep the mark of a file renamed from `old` to `new`
+    pub fn rename(&mut self, old: &Path, new: PathBuf) {
+        if let Some(mark) = self.marks.remove(old) {
+            self.marks.insert(new, mark);
+            self.save();
+        }
+    }
+
+    /// Send the marks still waiting for the API through `send` on a worker thread, so an
+    /// unreachable API does not hold up the list. The receiver gets the marks it took, for
+    /// `mark_synced`; None when nothing is waiting.
+    pub fn sync_in_background(&self, send: impl Fn(&Path, bool) -> bool + Send + 'static) -> Option<Receiver<Vec<(PathBuf, bool)>>> {
+        let pending: Vec<(PathBuf, bool)> = self.pending().into_iter().map(|(path, m)| (path.clone(), m.watched)).collect();
+        if pending.is_empty() {
+            return None;
+        }
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let sent = pending.into_iter().filter(|(path, watched)| send(path, *watched)).collect();
+            let _ = tx.send(sent);
+        });
+        Some(rx)
+    }
+
+    /// Note the marks the API took, unless they were changed again meanwhile
+    pub fn mark_synced(&mut self, sent: &[(PathBuf, bool)]) {
+        for (path, watched) in sent {
+            if let Some(mark) = self.marks.get_mut(path)
+                && mark.watched == *watched {
+                mark.synced = true;
+            }
+        }
+        self.save();
+    }
+
+    pub fn has_pending(&self) -> bool {
+        self.marks.values().any(|m| !m.synced)
+    }
+
+    /// Marks still waiting to reach the API
+    fn pending(&self) -> HashMap<&PathBuf, &Mark> {
+        self.marks.iter().filter(|(_, m)| !m.synced).collect()
+    }
+
+    fn save(&self) {
+        if let Some(path) = marks_path()
+            && let Err(e) = persist::save_json(&path, &self.pending()) {
+            log::warn!("Failed to save watched marks to {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_unsynced_marks_are_stored() {
+        let mut marks = WatchedMarks::default();
+        marks.marks.insert(PathBuf::from("a.mkv"), Mark { watched: true, synced: true });
+        marks.marks.insert(PathBuf::from("b.mkv"), Mark { watched: false, synced: false });
+        let json = serde_json::to_string(&marks.pending()).unwrap();
+        assert_eq!(json, r#"{"b.mkv":{"watched":false}}"#);
+        let loaded: HashMap<PathBuf, Mark> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.get(Path::new("b.mkv")), Some(&Mark { watched: false, synced: false }));
+    }
+
+    #[test]
+    fn background_sync_reports_what_the_api_took() {
+        let mut marks = WatchedMarks::default();
+        marks.marks.insert(PathBuf::from("a.mkv"), Mark { watched: true, synced: false });
+        marks.marks.insert(PathBuf::from("b.mkv"), Mark { watched: true, synced: false });
+        marks.marks.insert(PathBuf::from("c.mkv"), Mark { watched: false, synced: true });
+        let rx = marks.sync_in_background(|path, _| path != Path::new("b.mkv")).unwrap();
+        let sent = rx.recv().unwrap();
+        assert_eq!(sent, vec![(PathBuf::from("a.mkv"), true)]);
+        // Unmarked again while the request was out: still waiting
+        marks.marks.insert(PathBuf::from("a.mkv"), Mark { watched: false, synced: false });
+        marks.marks.get_mut(Path::new("b.mkv")).unwrap().synced = true;
+        marks.mark_synced(&sent);
+        assert!(marks.has_pending());
+        marks.mark_synced(&[(PathBuf::from("a.mkv"), false)]);
+        assert!(!marks.has_pending());
+        assert!(WatchedMarks::default().sync_in_background(|_, _| true).is_none());
+    }
+}
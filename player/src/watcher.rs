@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+// Changes closer together than this are one change; a long copy settles before the rescan
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Whether an event can change what the library holds; reads never do
+fn is_relevant(kind: &EventKind) -> bool {
+    !matches!(kind, EventKind::Access(_))
+}
+
+/// Watch the movie roots recursively. The receiver gets one message once the folders have
+/// been quiet for DEBOUNCE after a change; None if no root could be watched.
+pub fn watch(roots: &[PathBuf]) -> Option<Receiver<()>> {
+    let (raw_tx, raw_rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = match notify::recommended_watcher(raw_tx) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            log::warn!("Cannot watch the movie folders: {}", e);
+            return None;
+        }
+    };
+    let mut watching = false;
+    for root in roots {
+        match watcher.watch(root, RecursiveMode::Recursive) {
+            Ok(()) => watching = true,
+            Err(e) => log::warn!("Cannot watch {}: {}", root.display(), e),
+        }
+    }
+    if !watching {
+        return None;
+    }
+
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        // Owning the watcher here keeps it alive as long as the thread
+        let _watcher = watcher;
+        while let Ok(event) = raw_rx.recv() {
+            if !event.is_ok_and(|e| is_relevant(&e.kind)) {
+                continue;
+            }
+            loop {
+                match raw_rx.recv_timeout(DEBOUNCE) {
+                    Ok(_) => continue,
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            if tx.send(()).is_err() {
+                return;
+            }
+        }
+    });
+    Some(rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, ModifyKind, RemoveKind};
+
+    #[test]
+    fn reads_do_not_count_as_changes() {
+        assert!(is_relevant(&EventKind::Create(CreateKind::File)));
+        assert!(is_relevant(&EventKind::Remove(RemoveKind::Any)));
+        assert!(is_relevant(&EventKind::Modify(ModifyKind::Any)));
+        assert!(!is_relevant(&EventKind::Access(AccessKind::Any)));
+    }
+}
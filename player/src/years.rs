@@ -0,0 +1,113 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Release years to show, inclusive; either end may be open
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct YearRange {
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+}
+
+impl YearRange {
+    /// Parse a decade ("1980s", "80s"), a single year ("1994") or a range
+    /// ("1980-1989", "1990-" for 1990 onwards, "-1970" for up to 1970)
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        if spec.is_empty() {
+            return Err("year range is empty".to_string());
+        }
+
+        if let Some(decade) = spec.strip_suffix('s') {
+            let decade = decade.trim_end_matches('\'');
+            let start: i32 = decade.parse().map_err(|_| format!("invalid decade: {}", spec))?;
+            let start = match decade.len() {
+                // Two-digit decades: "80s" is the 1980s, "10s" the 2010s
+                2 if start < 30 => 2000 + start,
+                2 => 1900 + start,
+                4 => start,
+                _ => return Err(format!("invalid decade: {}", spec)),
+            };
+            if start % 10 != 0 {
+                return Err(format!("a decade starts at a year ending in 0: {}", spec));
+            }
+            return Ok(YearRange { min: Some(start), max: Some(start + 9) });
+        }
+
+        let year = |s: &str| -> Result<Option<i32>, String> {
+            let s = s.trim();
+            if s.is_empty() {
+                return Ok(None);
+            }
+            s.parse::<i32>().map(Some).map_err(|_| format!("invalid year: {}", s))
+        };
+        let range = match spec.split_once('-') {
+            Some((min, max)) => YearRange { min: year(min)?, max: year(max)? },
+            None => {
+                let single = year(spec)?;
+                YearRange { min: single, max: single }
+            }
+        };
+        match (range.min, range.max) {
+            (None, None) => Err(format!("invalid year range: {}", spec)),
+            (Some(min), Some(max)) if min > max => Err(format!("{} is after {}", min, max)),
+            _ => Ok(range),
+        }
+    }
+
+    pub fn contains(&self, year: i32) -> bool {
+        self.min.is_none_or(|min| year >= min) && self.max.is_none_or(|max| year <= max)
+    }
+}
+
+impl fmt::Display for YearRange {
+    /// In the form `parse` accepts
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min == max => write!(f, "{}", min),
+            (Some(min), Some(max)) if min % 10 == 0 && max == min + 9 => write!(f, "{}s", min),
+            (Some(min), Some(max)) => write!(f, "{}-{}", min, max),
+            (Some(min), None) => write!(f, "{}-", min),
+            (None, Some(max)) => write!(f, "-{}", max),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(min: Option<i32>, max: Option<i32>) -> YearRange {
+        YearRange { min, max }
+    }
+
+    #[test]
+    fn decades() {
+        assert_eq!(YearRange::parse("1980s").unwrap(), range(Some(1980), Some(1989)));
+        assert_eq!(YearRange::parse("80s").unwrap(), range(Some(1980), Some(1989)));
+        assert_eq!(YearRange::parse("10's").unwrap(), range(Some(2010), Some(2019)));
+        assert!(YearRange::parse("1985s").is_err());
+    }
+
+    #[test]
+    fn ranges_and_single_years() {
+        assert_eq!(YearRange::parse("1994").unwrap(), range(Some(1994), Some(1994)));
+        assert_eq!(YearRange::parse("1975 - 1985").unwrap(), range(Some(1975), Some(1985)));
+        assert_eq!(YearRange::parse("1990-").unwrap(), range(Some(1990), None));
+        assert_eq!(YearRange::parse("-1970").unwrap(), range(None, Some(1970)));
+        assert!(YearRange::parse("1990-1980").is_err());
+        assert!(YearRange::parse("-").is_err());
+        assert!(YearRange::parse("soon").is_err());
+    }
+
+    #[test]
+    fn contains_and_round_trip() {
+        let eighties = YearRange::parse("80s").unwrap();
+        assert!(eighties.contains(1980) && eighties.contains(1989) && !eighties.contains(1990));
+        assert!(YearRange::parse("-1970").unwrap().contains(1920));
+        for spec in ["1980s", "1994", "1975-1985", "1990-", "-1970"] {
+            assert_eq!(YearRange::parse(spec).unwrap().to_string(), spec);
+        }
+    }
+}